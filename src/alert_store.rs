@@ -0,0 +1,344 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use crate::alerts::Severity;
+use crate::config;
+use crate::routes::signals::{self, ObservedPrice, TradingViewAlert};
+use crate::scheduler;
+
+// ALERT_STORE_FILE: stored alerts as JSON lines, one appended per alert so a restart
+// keeps them. Memory only holds the newest ALERT_RETENTION; the file keeps growing
+// until compaction rewrites it down to those.
+
+// Unreadable lines logged one by one at startup; the rest are only counted
+const LOGGED_BAD_LINES: usize = 10;
+
+// Serializes changes to the file. Writers take it before updating the in-memory alerts
+// and keep it through their IO, so the file follows memory's order and an append can't
+// land between a rewrite's snapshot and its rename. The alerts lock itself is never held
+// during IO, so readers don't wait on the disk.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// What the startup load found, for /diagnostics
+static LOAD_REPORT: OnceLock<LoadReport> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+struct LoadReport {
+    file: String,
+    loaded: usize,
+    skipped_lines: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quarantined_to: Option<String>,
+}
+
+// An alert as written to the file: symbol and timestamp as held internally rather than
+// in the response formats, which can change between restarts
+#[derive(Serialize, Deserialize)]
+struct StoredAlert {
+    symbol: String,
+    price: f64,
+    alert_name: String,
+    action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw_action: Option<String>,
+    severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    observed_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    observed_price_at: Option<i64>,
+    timestamp: i64,
+}
+
+impl From<&TradingViewAlert> for StoredAlert {
+    fn from(alert: &TradingViewAlert) -> Self {
+        StoredAlert {
+            symbol: alert.symbol.clone(),
+            price: alert.price,
+            alert_name: alert.alert_name.clone(),
+            action: alert.action.clone(),
+            raw_action: alert.raw_action.clone(),
+            severity: alert.severity,
+            detail: alert.detail.clone(),
+            observed_price: alert.observed.as_ref().map(|o| o.price),
+            observed_price_at: alert.observed.as_ref().map(|o| o.at),
+            timestamp: alert.timestamp,
+        }
+    }
+}
+
+impl From<StoredAlert> for TradingViewAlert {
+    fn from(stored: StoredAlert) -> Self {
+        TradingViewAlert {
+            symbol: stored.symbol,
+            price: stored.price,
+            alert_name: stored.alert_name,
+            action: stored.action,
+            raw_action: stored.raw_action,
+            severity: stored.severity,
+            detail: stored.detail,
+            observed: stored
+                .observed_price
+                .zip(stored.observed_price_at)
+                .map(|(price, at)| ObservedPrice::new(stored.price, price, at)),
+            timestamp: stored.timestamp,
+        }
+    }
+}
+
+fn to_line(alert: &TradingViewAlert) -> Result<String, String> {
+    serde_json::to_string(&StoredAlert::from(alert)).map_err(|e| e.to_string())
+}
+
+// The newest ALERT_RETENTION alerts in the file, oldest first; empty without a file.
+// Lines that don't parse, typically one cut short by a crash mid-append, are logged and
+// skipped. With ALERT_STORE_QUARANTINE the damaged file is kept aside as
+// `<file>.corrupt-<unix time>` and replaced by the alerts that did load.
+pub fn load() -> Vec<TradingViewAlert> {
+    let config = config::get();
+    let Some(path) = &config.alert_store_file else {
+        return Vec::new();
+    };
+    let Some((alerts, skipped)) = read_alerts(path, config.alert_retention) else {
+        return Vec::new();
+    };
+
+    let quarantined_to = if skipped > 0 && config.alert_store_quarantine {
+        quarantine(path, &alerts)
+    } else {
+        None
+    };
+    if skipped > 0 {
+        println!(
+            "⚠️ Loaded {} alerts from {}, skipped {} unreadable lines{}",
+            alerts.len(),
+            path,
+            skipped,
+            quarantined_to
+                .as_ref()
+                .map(|to| format!("; damaged file moved to {}", to))
+                .unwrap_or_default()
+        );
+    } else {
+        println!("🗄️ Loaded {} alerts from {}", alerts.len(), path);
+    }
+
+    let _ = LOAD_REPORT.set(LoadReport {
+        file: path.clone(),
+        loaded: alerts.len(),
+        skipped_lines: skipped,
+        quarantined_to,
+    });
+    alerts
+}
+
+// The newest `retention` alerts in the file and how many lines were unreadable; None
+// without a file
+fn read_alerts(path: &str, retention: usize) -> Option<(Vec<TradingViewAlert>, usize)> {
+    let raw = fs::read_to_string(path).ok()?;
+    let mut alerts = Vec::new();
+    let mut skipped = 0;
+    for (number, line) in raw.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        match serde_json::from_str::<StoredAlert>(line) {
+            Ok(stored) => alerts.push(stored.into()),
+            Err(e) => {
+                skipped += 1;
+                if skipped <= LOGGED_BAD_LINES {
+                    println!("⚠️ Skipping unreadable line {} of alert file {}: {}", number + 1, path, e);
+                }
+            }
+        }
+    }
+    let excess = alerts.len().saturating_sub(retention);
+    alerts.drain(..excess);
+    Some((alerts, skipped))
+}
+
+// Moves the damaged file aside and writes the alerts that loaded in its place. Returns
+// where it went; on failure the file is left as it is for compaction to clean up.
+fn quarantine(path: &str, alerts: &[TradingViewAlert]) -> Option<String> {
+    let to = format!("{}.corrupt-{}", path, Utc::now().timestamp());
+    if let Err(e) = fs::rename(path, &to) {
+        println!("❌ Failed to quarantine alert file {}: {}", path, e);
+        return None;
+    }
+    if let Err(e) = rewrite_file(path, alerts) {
+        println!("❌ {}", e);
+    }
+    Some(to)
+}
+
+// The startup load's outcome; null without ALERT_STORE_FILE or before the load
+pub fn load_status() -> Value {
+    json!(LOAD_REPORT.get())
+}
+
+pub fn write_lock() -> MutexGuard<'static, ()> {
+    WRITE_LOCK.lock().unwrap()
+}
+
+pub fn append(alert: &TradingViewAlert) {
+    let Some(path) = &config::get().alert_store_file else {
+        return;
+    };
+    if let Err(e) = append_to(path, alert) {
+        println!("❌ Failed to append alert to {}: {}", path, e);
+    }
+}
+
+fn append_to(path: &str, alert: &TradingViewAlert) -> Result<(), String> {
+    let line = to_line(alert)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+// Replaces the file's contents with `alerts`. Writes a temp file and renames it over
+// the old one, so a crash mid-write leaves either the old file or the new one.
+// Returns the file size before and after.
+pub fn rewrite(alerts: &[TradingViewAlert]) -> Result<(u64, u64), String> {
+    match &config::get().alert_store_file {
+        Some(path) => rewrite_file(path, alerts),
+        None => Ok((0, 0)),
+    }
+}
+
+fn rewrite_file(path: &str, alerts: &[TradingViewAlert]) -> Result<(u64, u64), String> {
+    let before = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut raw = String::new();
+    for alert in alerts {
+        raw.push_str(&to_line(alert)?);
+        raw.push('\n');
+    }
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, &raw)
+        .and_then(|_| fs::rename(&tmp, Path::new(path)))
+        .map_err(|e| format!("Failed to rewrite {}: {}", path, e))?;
+    Ok((before, raw.len() as u64))
+}
+
+// ALERT_COMPACTION_INTERVAL_SECONDS: periodically drops the lines of alerts that have
+// left memory from the file.
+pub fn start_compaction() {
+    let config = config::get();
+    if config.alert_store_file.is_none() || config.alert_compaction_interval_seconds == 0 {
+        return;
+    }
+    let interval = Duration::from_secs(config.alert_compaction_interval_seconds);
+    scheduler::register("alert_compaction", interval, || async {
+        signals::compact_alerts()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(n: i64) -> TradingViewAlert {
+        TradingViewAlert {
+            symbol: "BTC".to_string(),
+            price: 60_000.0 + n as f64,
+            alert_name: format!("alert {}", n),
+            action: Some("buy".to_string()),
+            raw_action: None,
+            severity: Severity::Info,
+            detail: None,
+            observed: None,
+            timestamp: 1_700_000_000 + n,
+        }
+    }
+
+    fn temp_file() -> String {
+        std::env::temp_dir()
+            .join(format!("alerts-{}.jsonl", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn names(alerts: &[TradingViewAlert]) -> Vec<String> {
+        alerts.iter().map(|a| a.alert_name.clone()).collect()
+    }
+
+    #[test]
+    fn compaction_keeps_order_count_and_endpoints() {
+        let path = temp_file();
+        let all: Vec<TradingViewAlert> = (0..10).map(alert).collect();
+        for alert in &all {
+            append_to(&path, alert).unwrap();
+        }
+        // Memory holds the newest four, which is what compaction writes back
+        let kept = &all[6..];
+        let (before, after) = rewrite_file(&path, kept).unwrap();
+        assert!(after < before, "{} -> {}", before, after);
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+
+        let (loaded, skipped) = read_alerts(&path, 100).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(names(&loaded), names(kept));
+        assert_eq!(loaded.first().unwrap().timestamp, 1_700_000_006);
+        assert_eq!(loaded.last().unwrap().timestamp, 1_700_000_009);
+        assert_eq!(loaded.last().unwrap().price, 60_009.0);
+
+        // Appends after a compaction land behind the kept alerts
+        append_to(&path, &alert(10)).unwrap();
+        let (loaded, _) = read_alerts(&path, 100).unwrap();
+        assert_eq!(loaded.len(), 5);
+        assert_eq!(loaded.last().unwrap().alert_name, "alert 10");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_keeps_only_the_newest_retention_alerts() {
+        let path = temp_file();
+        for n in 0..10 {
+            append_to(&path, &alert(n)).unwrap();
+        }
+        let (loaded, _) = read_alerts(&path, 3).unwrap();
+        assert_eq!(names(&loaded), vec!["alert 7", "alert 8", "alert 9"]);
+        assert!(read_alerts(&temp_file(), 3).is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unreadable_lines_are_skipped_and_quarantined() {
+        let path = temp_file();
+        let good: Vec<String> = (0..4).map(|n| to_line(&alert(n)).unwrap()).collect();
+        let truncated = &good[1][..good[1].len() / 2];
+        let raw = [
+            good[0].as_str(),
+            truncated,
+            "",
+            good[1].as_str(),
+            "not json at all",
+            r#"{"symbol": "BTC", "price": "sixty thousand"}"#,
+            good[2].as_str(),
+            "   ",
+            good[3].as_str(),
+            truncated,
+        ]
+        .join("\n");
+        fs::write(&path, raw).unwrap();
+
+        let (loaded, skipped) = read_alerts(&path, 100).unwrap();
+        assert_eq!(skipped, 4);
+        assert_eq!(names(&loaded), vec!["alert 0", "alert 1", "alert 2", "alert 3"]);
+
+        let moved_to = quarantine(&path, &loaded).unwrap();
+        assert!(Path::new(&moved_to).exists());
+        let (reloaded, skipped) = read_alerts(&path, 100).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(names(&reloaded), names(&loaded));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&moved_to).unwrap();
+    }
+}
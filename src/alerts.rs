@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+// Webhook alerts accepted within the dedup window
+static RECENT: OnceLock<Mutex<Vec<RecentAlert>>> = OnceLock::new();
+
+struct RecentAlert {
+    symbol: String,
+    action: Option<String>,
+    price: f64,
+    received: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+// Signals a webhook action can be normalized to, as generate_signal names them
+pub const SIGNAL_VOCABULARY: &[&str] = &[
+    "strong_buy",
+    "buy",
+    "weak_buy",
+    "hold",
+    "weak_sell",
+    "sell",
+    "strong_sell",
+];
+
+// Stored for actions ALERT_ACTION_MAP has no entry for
+pub const UNKNOWN_ACTION: &str = "unknown";
+
+// ALERT_ACTION_MAP: the words TradingView alert templates use for an action, onto
+// SIGNAL_VOCABULARY. Phrases are matched case-insensitively with `_`, `-` and runs of
+// whitespace treated as one space, so "Enter-Long" and "enter  long" are the same.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionMap {
+    phrases: HashMap<String, String>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let defaults: &[(&str, &[&str])] = &[
+            ("strong_buy", &["strong buy", "strong long"]),
+            (
+                "buy",
+                &["buy", "long", "enter long", "go long", "open long", "long entry", "bullish", "close short", "exit short", "cover"],
+            ),
+            ("weak_buy", &["weak buy"]),
+            ("hold", &["hold", "neutral", "flat", "wait"]),
+            ("weak_sell", &["weak sell"]),
+            (
+                "sell",
+                &["sell", "short", "enter short", "go short", "open short", "short entry", "bearish", "close long", "exit long"],
+            ),
+            ("strong_sell", &["strong sell", "strong short"]),
+        ];
+        Self {
+            phrases: defaults
+                .iter()
+                .flat_map(|(signal, phrases)| phrases.iter().map(move |p| (p.to_string(), signal.to_string())))
+                .collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    fn key(phrase: &str) -> String {
+        phrase
+            .to_lowercase()
+            .replace(['_', '-'], " ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Adds or replaces a phrase; mapping one to `unknown` removes a default
+    pub fn set(&mut self, phrase: &str, signal: &str) -> Result<(), String> {
+        let signal = signal.trim().to_lowercase();
+        if signal != UNKNOWN_ACTION && !SIGNAL_VOCABULARY.contains(&signal.as_str()) {
+            return Err(format!(
+                "Invalid ALERT_ACTION_MAP signal for '{}': {}. Use {} or {}.",
+                phrase,
+                signal,
+                SIGNAL_VOCABULARY.join(", "),
+                UNKNOWN_ACTION
+            ));
+        }
+        let key = Self::key(phrase);
+        if key.is_empty() {
+            return Err("Invalid ALERT_ACTION_MAP entry: empty phrase".to_string());
+        }
+        if signal == UNKNOWN_ACTION {
+            self.phrases.remove(&key);
+        } else {
+            self.phrases.insert(key, signal);
+        }
+        Ok(())
+    }
+
+    // None when the action isn't mapped
+    pub fn normalize(&self, action: &str) -> Option<&str> {
+        self.phrases.get(&Self::key(action)).map(String::as_str)
+    }
+}
+
+// Severity score = action weight * symbol weight, compared against the thresholds.
+// With the defaults a strong_sell on BTC (3.0 * 1.5) is critical, a buy on SOL
+// (2.0 * 1.0) is a warning and a weak signal on a minor coin stays info.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeverityRules {
+    pub action_weights: HashMap<String, f64>,
+    pub symbol_weights: HashMap<String, f64>,
+    pub default_action_weight: f64,
+    pub default_symbol_weight: f64,
+    pub warning_threshold: f64,
+    pub critical_threshold: f64,
+}
+
+impl Default for SeverityRules {
+    fn default() -> Self {
+        let action_weights = [
+            ("strong_buy", 3.0),
+            ("strong_sell", 3.0),
+            ("buy", 2.0),
+            ("sell", 2.0),
+        ];
+        let symbol_weights = [("BTC", 1.5), ("ETH", 1.25)];
+
+        Self {
+            action_weights: action_weights
+                .iter()
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect(),
+            symbol_weights: symbol_weights
+                .iter()
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect(),
+            default_action_weight: 1.0,
+            default_symbol_weight: 1.0,
+            warning_threshold: 2.0,
+            critical_threshold: 4.0,
+        }
+    }
+}
+
+impl SeverityRules {
+    pub fn classify(&self, symbol: &str, action: Option<&str>) -> Severity {
+        let action_weight = action
+            .and_then(|a| self.action_weights.get(&a.to_lowercase()))
+            .copied()
+            .unwrap_or(self.default_action_weight);
+        let symbol_weight = self
+            .symbol_weights
+            .get(&symbol.to_uppercase())
+            .copied()
+            .unwrap_or(self.default_symbol_weight);
+
+        let score = action_weight * symbol_weight;
+        if score >= self.critical_threshold {
+            Severity::Critical
+        } else if score >= self.warning_threshold {
+            Severity::Warning
+        } else {
+            Severity::Info
+        }
+    }
+}
+
+// SIGNAL_CONFLICT_RESOLUTION: what /signals reports when a recent TradingView alert for a
+// coin points the other way from the internal signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    // `signal` stays the internal one; the alert is shown next to it
+    PreferInternal,
+    // `signal` is the alert's; the internal one is shown next to it
+    PreferExternal,
+    // Both are shown and, when they disagree, `signal` is `conflict`
+    ShowBoth,
+}
+
+impl ConflictResolution {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "prefer_internal" => Ok(ConflictResolution::PreferInternal),
+            "prefer_external" => Ok(ConflictResolution::PreferExternal),
+            "show_both" => Ok(ConflictResolution::ShowBoth),
+            other => Err(format!(
+                "Invalid SIGNAL_CONFLICT_RESOLUTION: {}. Use prefer_internal, prefer_external or show_both.",
+                other
+            )),
+        }
+    }
+}
+
+// Signal reported in place of two that disagree under ConflictResolution::ShowBoth
+pub const CONFLICT_SIGNAL: &str = "conflict";
+
+// 1 for the buy side, -1 for the sell side, 0 for hold and anything else
+fn direction(signal: &str) -> i8 {
+    match signal {
+        "strong_buy" | "buy" | "weak_buy" => 1,
+        "strong_sell" | "sell" | "weak_sell" => -1,
+        _ => 0,
+    }
+}
+
+// Two signals disagree when they lean different ways; strength alone (buy vs
+// strong_buy) is not a conflict.
+pub fn signals_conflict(internal: &str, external: &str) -> bool {
+    direction(internal) != direction(external)
+}
+
+// What makes two webhook alerts within ALERT_DEDUP_WINDOW_SECONDS the same alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupScope {
+    // Every alert is kept
+    Off,
+    // Same symbol and action, whatever the price
+    SymbolAction,
+    // Same symbol and action at a price within ALERT_DEDUP_PRICE_TOLERANCE_PERCENT
+    SymbolActionPrice,
+}
+
+impl DedupScope {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "off" => Ok(DedupScope::Off),
+            "symbol_action" => Ok(DedupScope::SymbolAction),
+            "symbol_action_price" => Ok(DedupScope::SymbolActionPrice),
+            other => Err(format!(
+                "Invalid ALERT_DEDUP_SCOPE: {}. Use off, symbol_action or symbol_action_price.",
+                other
+            )),
+        }
+    }
+}
+
+// True when an equivalent alert was accepted within the window; otherwise the alert
+// is remembered so later repeats of it are caught.
+pub fn is_duplicate(symbol: &str, action: Option<&str>, price: f64) -> bool {
+    let config = config::get();
+    let scope = config.alert_dedup_scope;
+    if scope == DedupScope::Off {
+        return false;
+    }
+    let window = Duration::from_secs(config.alert_dedup_window_seconds);
+    let tolerance = config.alert_dedup_price_tolerance_percent;
+
+    let mut recent = RECENT.get_or_init(|| Mutex::new(Vec::new())).lock().unwrap();
+    recent.retain(|alert| alert.received.elapsed() < window);
+
+    let duplicate = recent.iter().any(|alert| {
+        let same_price = match scope {
+            DedupScope::SymbolActionPrice => {
+                let previous = alert.price;
+                previous == price
+                    || (previous != 0.0 && ((price - previous) / previous).abs() * 100.0 <= tolerance)
+            }
+            _ => true,
+        };
+        alert.symbol == symbol && alert.action.as_deref() == action && same_price
+    });
+    if !duplicate {
+        recent.push(RecentAlert {
+            symbol: symbol.to_string(),
+            action: action.map(str::to_string),
+            price,
+            received: Instant::now(),
+        });
+    }
+    duplicate
+}
@@ -0,0 +1,399 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::HttpResponse;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+
+use crate::auth;
+use crate::config;
+use crate::error::ApiError;
+use crate::format;
+use crate::routes::{HEALTH_ENDPOINT, READY_ENDPOINT};
+
+// Header keyed clients send their key in
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 120;
+const DEFAULT_DAILY_EXPLANATIONS: u32 = 100;
+
+static USAGE: OnceLock<Mutex<UsageTable>> = OnceLock::new();
+
+// One API_KEYS entry. Keyed clients may call /explain-* within their daily quota;
+// everyone else is limited to the other endpoints at PUBLIC_REQUESTS_PER_MINUTE.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub key: String,
+    pub requests_per_minute: u32,
+    pub daily_explanations: u32,
+}
+
+// Parses "name:key[:requests_per_minute[:daily_explanations]]" entries, comma-separated
+pub fn parse_api_keys(raw: &str) -> Result<Vec<ApiKey>, String> {
+    let mut keys: Vec<ApiKey> = Vec::new();
+    for entry in raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let parts: Vec<&str> = entry.split(':').map(|s| s.trim()).collect();
+        let invalid = |reason: &str| {
+            format!(
+                "Invalid API_KEYS entry for '{}': {} (expected name:key[:requests_per_minute[:daily_explanations]])",
+                parts[0], reason
+            )
+        };
+        if parts.len() < 2 || parts.len() > 4 || parts[0].is_empty() || parts[1].is_empty() {
+            return Err(invalid("needs a name and a key"));
+        }
+        let number = |index: usize, default: u32| -> Result<u32, String> {
+            parts
+                .get(index)
+                .map_or(Ok(default), |raw| raw.parse().map_err(|e| invalid(&format!("{}", e))))
+        };
+        let key = ApiKey {
+            name: parts[0].to_string(),
+            key: parts[1].to_string(),
+            requests_per_minute: number(2, DEFAULT_REQUESTS_PER_MINUTE)?,
+            daily_explanations: number(3, DEFAULT_DAILY_EXPLANATIONS)?,
+        };
+        if keys.iter().any(|k| k.name == key.name || k.key == key.key) {
+            return Err(invalid("duplicate name or key"));
+        }
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+// TRUSTED_PROXIES: comma-separated IP addresses
+pub fn parse_trusted_proxies(raw: &str) -> Result<Vec<IpAddr>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            entry
+                .parse()
+                .map_err(|_| format!("Invalid TRUSTED_PROXIES entry '{}': expected an IP address", entry))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct Usage {
+    requests: u64,
+    explanations: u64,
+    rate_limited: u64,
+    quota_exceeded: u64,
+    #[serde(serialize_with = "format::serialize_optional_timestamp")]
+    last_used: Option<i64>,
+    // Fixed one-minute window the rate limit counts in
+    #[serde(skip)]
+    minute: i64,
+    #[serde(skip)]
+    minute_requests: u32,
+    // UTC day the explanation quota counts in
+    #[serde(skip)]
+    day: i64,
+    #[serde(skip)]
+    day_explanations: u32,
+}
+
+// Usage per client: "key:<name>" for keyed clients, "anonymous:<ip>" for the rest
+#[derive(Default)]
+struct UsageTable {
+    clients: HashMap<String, Usage>,
+    // Anonymous clients pruned once their minute window passed, rolled up so the usage
+    // report keeps counting them
+    retired_anonymous: Usage,
+    // Minute the anonymous clients were last pruned in
+    pruned_minute: i64,
+}
+
+impl UsageTable {
+    // Drops anonymous clients idle since before the current minute, at most once a minute.
+    // Keyed clients stay: there are only as many as API_KEYS lists, and they hold a daily quota.
+    fn prune(&mut self, minute: i64) {
+        if self.pruned_minute == minute {
+            return;
+        }
+        self.pruned_minute = minute;
+        let retired = &mut self.retired_anonymous;
+        self.clients.retain(|client, usage| {
+            if !client.starts_with("anonymous:") || usage.minute >= minute {
+                return true;
+            }
+            retired.requests += usage.requests;
+            retired.rate_limited += usage.rate_limited;
+            false
+        });
+    }
+
+    // Counts the request against the client's minute window and, for explanations, its
+    // daily quota, as of `now`
+    fn admit(&mut self, client: &str, rate: u32, quota: Option<u32>, explanation: bool, now: i64) -> Result<(), Rejection> {
+        self.prune(now / 60);
+        let usage = self.clients.entry(client.to_string()).or_default();
+        usage.last_used = Some(now);
+
+        if usage.minute != now / 60 {
+            usage.minute = now / 60;
+            usage.minute_requests = 0;
+        }
+        if usage.day != now / 86400 {
+            usage.day = now / 86400;
+            usage.day_explanations = 0;
+        }
+
+        if usage.minute_requests >= rate {
+            usage.rate_limited += 1;
+            let error = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                format!("Rate limit of {} requests per minute reached", rate),
+            );
+            return Err((error, Some(60 - now % 60)));
+        }
+        if let (true, Some(quota)) = (explanation, quota) {
+            if usage.day_explanations >= quota {
+                usage.quota_exceeded += 1;
+                let error = ApiError::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "quota_exceeded",
+                    format!("Daily quota of {} explanation requests used up; it resets at 00:00 UTC", quota),
+                );
+                return Err((error, Some(86400 - now % 86400)));
+            }
+            usage.day_explanations += 1;
+            usage.explanations += 1;
+        }
+        usage.minute_requests += 1;
+        usage.requests += 1;
+        Ok(())
+    }
+}
+
+fn usage_table() -> &'static Mutex<UsageTable> {
+    USAGE.get_or_init(|| Mutex::new(UsageTable::default()))
+}
+
+// The address anonymous limits are keyed on: the connection's peer, or with the peer in
+// TRUSTED_PROXIES, the nearest X-Forwarded-For hop that isn't a trusted proxy itself.
+// Hops further left were written by the client and can't be believed.
+fn client_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> String {
+    let Some(peer) = peer else {
+        return "unknown".to_string();
+    };
+    if !trusted.contains(&peer) {
+        return peer.to_string();
+    }
+    let hops: Vec<&str> = forwarded_for
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    for hop in hops.iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) if trusted.contains(&ip) => continue,
+            Ok(ip) => return ip.to_string(),
+            // Not an address: nothing before it can be trusted either
+            Err(_) => break,
+        }
+    }
+    peer.to_string()
+}
+
+fn is_explanation(path: &str) -> bool {
+    path.starts_with("/explain-")
+}
+
+// A refused request and, for limits that lift on their own, the seconds until they do
+type Rejection = (ApiError, Option<i64>);
+
+fn rejected((error, retry_after): Rejection) -> HttpResponse {
+    let mut builder = error.response_builder();
+    if let Some(seconds) = retry_after {
+        builder.insert_header((header::RETRY_AFTER, seconds.max(1).to_string()));
+    }
+    builder.json(error.body())
+}
+
+// UsageTable::admit on the process-wide table, at the current time
+fn admit(client: &str, rate: u32, quota: Option<u32>, explanation: bool) -> Result<(), Rejection> {
+    usage_table()
+        .lock()
+        .unwrap()
+        .admit(client, rate, quota, explanation, Utc::now().timestamp())
+}
+
+// Middleware: with API_KEYS set, `/explain-*` needs a valid key and is counted against
+// the key's daily quota, and every client is rate limited per minute. Requests with
+// the admin token and the health endpoint are let through untouched.
+pub async fn gate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let config = config::get();
+    let admin = config.admin_token.is_some() && auth::authorize(req.request()).is_ok();
+    let probe = req.path() == HEALTH_ENDPOINT || req.path() == READY_ENDPOINT;
+    if config.api_keys.is_empty() || admin || probe {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let explanation = is_explanation(req.path());
+    let presented = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string());
+
+    let admitted = match presented {
+        Some(presented) => match config.api_keys.iter().find(|k| auth::constant_time_eq(&k.key, &presented)) {
+            Some(key) => admit(
+                &format!("key:{}", key.name),
+                key.requests_per_minute,
+                Some(key.daily_explanations),
+                explanation,
+            ),
+            None => Err((
+                ApiError::new(StatusCode::UNAUTHORIZED, "invalid_api_key", "Unknown API key"),
+                None,
+            )),
+        },
+        None if explanation => Err((
+            ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "api_key_required",
+                format!("Explanations need an API key in the {} header", API_KEY_HEADER),
+            ),
+            None,
+        )),
+        None => {
+            let forwarded_for = req.headers().get("x-forwarded-for").and_then(|v| v.to_str().ok());
+            let ip = client_ip(req.peer_addr().map(|a| a.ip()), forwarded_for, &config.trusted_proxies);
+            admit(&format!("anonymous:{}", ip), config.public_requests_per_minute, None, false)
+        }
+    };
+
+    match admitted {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err(rejection) => Ok(req.into_response(rejected(rejection).map_into_right_body())),
+    }
+}
+
+// For the admin usage endpoint: every key with its limits and usage, plus anonymous
+// clients rolled up
+pub fn usage_report() -> Value {
+    let table = usage_table().lock().unwrap();
+    let usage = &table.clients;
+    let keys: Vec<Value> = config::get()
+        .api_keys
+        .iter()
+        .map(|key| {
+            let used = usage.get(&format!("key:{}", key.name)).cloned().unwrap_or_default();
+            let today = if used.day == Utc::now().timestamp() / 86400 { used.day_explanations } else { 0 };
+            json!({
+                "name": key.name,
+                "requests_per_minute": key.requests_per_minute,
+                "daily_explanations": key.daily_explanations,
+                "explanations_today": today,
+                "usage": used,
+            })
+        })
+        .collect();
+
+    let anonymous: Vec<&Usage> = usage
+        .iter()
+        .filter(|(client, _)| client.starts_with("anonymous:"))
+        .map(|(_, used)| used)
+        .collect();
+    let retired = &table.retired_anonymous;
+    json!({
+        "keys": keys,
+        "anonymous": {
+            // Seen within the current rate-limit window
+            "clients": anonymous.len(),
+            "requests": retired.requests + anonymous.iter().map(|u| u.requests).sum::<u64>(),
+            "rate_limited": retired.rate_limited + anonymous.iter().map(|u| u.rate_limited).sum::<u64>(),
+            "requests_per_minute": config::get().public_requests_per_minute,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_untrusted_peers() {
+        let peer = Some(ip("203.0.113.7"));
+        assert_eq!(client_ip(peer, Some("198.51.100.1"), &[]), "203.0.113.7");
+        assert_eq!(client_ip(peer, Some("198.51.100.1"), &[ip("10.0.0.1")]), "203.0.113.7");
+        assert_eq!(client_ip(None, Some("198.51.100.1"), &[]), "unknown");
+    }
+
+    #[test]
+    fn trusted_proxies_yield_the_nearest_untrusted_hop() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let peer = Some(ip("10.0.0.1"));
+        // The client's own entry (first) is whatever it sent; the proxy appended the real one
+        assert_eq!(client_ip(peer, Some("1.2.3.4, 198.51.100.9"), &trusted), "198.51.100.9");
+        assert_eq!(client_ip(peer, Some("198.51.100.9, 10.0.0.2"), &trusted), "198.51.100.9");
+        assert_eq!(client_ip(peer, Some("junk, 198.51.100.9"), &trusted), "198.51.100.9");
+        assert_eq!(client_ip(peer, Some("198.51.100.9, junk"), &trusted), "10.0.0.1");
+        assert_eq!(client_ip(peer, None, &trusted), "10.0.0.1");
+    }
+
+    #[test]
+    fn parses_trusted_proxies() {
+        assert_eq!(parse_trusted_proxies(" 10.0.0.1, ::1 ,").unwrap(), vec![ip("10.0.0.1"), ip("::1")]);
+        assert!(parse_trusted_proxies("10.0.0.0/8").is_err());
+    }
+
+    #[test]
+    fn idle_anonymous_clients_are_pruned_and_rolled_up() {
+        let mut table = UsageTable::default();
+        for (client, minute) in [("anonymous:198.51.100.1", 10), ("anonymous:198.51.100.2", 11), ("key:dashboard", 10)] {
+            let usage = table.clients.entry(client.to_string()).or_default();
+            usage.minute = minute;
+            usage.requests = 5;
+            usage.rate_limited = 1;
+        }
+
+        table.prune(11);
+        let mut clients: Vec<&String> = table.clients.keys().collect();
+        clients.sort();
+        assert_eq!(clients, vec!["anonymous:198.51.100.2", "key:dashboard"]);
+        assert_eq!(table.retired_anonymous.requests, 5);
+        assert_eq!(table.retired_anonymous.rate_limited, 1);
+
+        // Once per minute
+        table.clients.get_mut("anonymous:198.51.100.2").unwrap().minute = 5;
+        table.prune(11);
+        assert_eq!(table.clients.len(), 2);
+        table.prune(12);
+        assert_eq!(table.clients.len(), 1);
+        assert_eq!(table.retired_anonymous.requests, 10);
+    }
+
+    #[test]
+    fn rotating_forwarded_for_does_not_escape_the_limit() {
+        let peer = Some(ip("192.0.2.55"));
+        let now = 1_700_000_000;
+        let mut table = UsageTable::default();
+        let mut outcomes = Vec::new();
+        for n in 0..4 {
+            let spoofed = format!("198.51.100.{}", n);
+            let client = format!("anonymous:{}", client_ip(peer, Some(&spoofed), &[]));
+            outcomes.push(table.admit(&client, 3, None, false, now).is_ok());
+        }
+        assert_eq!(outcomes, vec![true, true, true, false]);
+    }
+}
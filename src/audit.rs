@@ -0,0 +1,50 @@
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+use crate::format;
+
+// Events kept in memory; the oldest are dropped first
+const RETENTION: usize = 500;
+
+static LOG: OnceLock<Mutex<VecDeque<AuditEvent>>> = OnceLock::new();
+
+// Something the service did to its own state without being asked, kept so an
+// operator can see it after the fact.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEvent {
+    pub event: String,
+    #[serde(serialize_with = "format::serialize_symbol")]
+    pub symbol: String,
+    pub detail: Value,
+    #[serde(serialize_with = "format::serialize_timestamp")]
+    pub at: i64,
+}
+
+fn log() -> &'static Mutex<VecDeque<AuditEvent>> {
+    LOG.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+pub fn record(event: &str, symbol: &str, detail: Value) {
+    let mut log = log().lock().unwrap();
+    log.push_back(AuditEvent {
+        event: event.to_string(),
+        symbol: symbol.to_uppercase(),
+        detail,
+        at: Utc::now().timestamp(),
+    });
+    while log.len() > RETENTION {
+        log.pop_front();
+    }
+}
+
+// Newest first
+pub fn events() -> Vec<AuditEvent> {
+    log().lock().unwrap().iter().rev().cloned().collect()
+}
+
+pub fn retention() -> usize {
+    RETENTION
+}
@@ -0,0 +1,76 @@
+use actix_web::dev::Payload;
+use actix_web::{error, Error, FromRequest, HttpRequest, HttpResponse};
+use serde_json::json;
+use std::future::{ready, Ready};
+
+use crate::config;
+
+// Extractor guarding admin endpoints. Accepts `Authorization: Bearer <token>` or
+// `X-Admin-Token: <token>`. When ADMIN_TOKEN is unset admin endpoints are open.
+pub struct Admin;
+
+fn presented_token(req: &HttpRequest) -> Option<&str> {
+    let headers = req.headers();
+    headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("X-Admin-Token").and_then(|v| v.to_str().ok()))
+        .map(|t| t.trim())
+}
+
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+// For handlers where only some requests need admin rights (e.g. ?no_cache=true).
+pub fn authorize(req: &HttpRequest) -> Result<Admin, Error> {
+    let config = config::get();
+    let Some(expected) = &config.admin_token else {
+        return Ok(Admin);
+    };
+
+    let unauthorized = |message: &str| {
+        error::InternalError::from_response(
+            message.to_string(),
+            HttpResponse::Unauthorized().json(json!({
+                "status": "error",
+                "message": message,
+            })),
+        )
+        .into()
+    };
+
+    let Some(token) = presented_token(req) else {
+        return Err(unauthorized("Admin token required"));
+    };
+
+    if constant_time_eq(token, expected) {
+        return Ok(Admin);
+    }
+    if let Some(previous) = &config.admin_token_previous {
+        if constant_time_eq(token, previous) {
+            println!(
+                "⚠️ {} {} authenticated with ADMIN_TOKEN_PREVIOUS - switch clients to the new ADMIN_TOKEN",
+                req.method(),
+                req.path()
+            );
+            return Ok(Admin);
+        }
+    }
+
+    Err(unauthorized("Invalid admin token"))
+}
+
+impl FromRequest for Admin {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authorize(req))
+    }
+}
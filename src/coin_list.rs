@@ -0,0 +1,166 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::audit;
+use crate::coingecko;
+use crate::config;
+use crate::scheduler;
+
+// The latest CoinGecko coin list. A refresh builds a whole new list and swaps it in,
+// so readers see either the old list or the new one, never a mix.
+static LIST: OnceLock<RwLock<Option<Arc<CoinList>>>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedCoin {
+    pub symbol: String,
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct CoinList {
+    // By CoinGecko id
+    coins: HashMap<String, ListedCoin>,
+    // Ids per upper-cased symbol; several coins can share a ticker
+    ids_by_symbol: HashMap<String, Vec<String>>,
+    fetched_at: i64,
+}
+
+impl CoinList {
+    fn new(entries: Vec<(String, String, String)>) -> Self {
+        let mut coins = HashMap::new();
+        let mut ids_by_symbol: HashMap<String, Vec<String>> = HashMap::new();
+        for (id, symbol, name) in entries {
+            ids_by_symbol.entry(symbol.clone()).or_default().push(id.clone());
+            coins.insert(id, ListedCoin { symbol, name });
+        }
+        CoinList {
+            coins,
+            ids_by_symbol,
+            fetched_at: Utc::now().timestamp(),
+        }
+    }
+}
+
+fn slot() -> &'static RwLock<Option<Arc<CoinList>>> {
+    LIST.get_or_init(|| RwLock::new(None))
+}
+
+fn current() -> Option<Arc<CoinList>> {
+    slot().read().unwrap().clone()
+}
+
+// What the coin list says about a CoinGecko id, once a list has been fetched
+pub fn lookup(coingecko_id: &str) -> Option<ListedCoin> {
+    current()?.coins.get(coingecko_id).cloned()
+}
+
+// CoinGecko ids listed under the symbol
+pub fn ids_for(symbol: &str) -> Vec<String> {
+    current()
+        .and_then(|list| list.ids_by_symbol.get(&symbol.to_uppercase()).cloned())
+        .unwrap_or_default()
+}
+
+// For /diagnostics: how big and how old the list is
+pub fn status() -> Value {
+    match current() {
+        Some(list) => json!({
+            "coins": list.coins.len(),
+            "fetched_at": crate::format::timestamp(list.fetched_at),
+        }),
+        None => json!({"coins": 0, "fetched_at": null}),
+    }
+}
+
+// COIN_LIST_REFRESH_SECONDS: re-fetches CoinGecko's coin list so listings, delistings
+// and renames are noticed on a long-running deployment. A failed fetch keeps the
+// previous list; the scheduler records the error.
+pub fn start() {
+    let seconds = config::get().coin_list_refresh_seconds;
+    if seconds == 0 {
+        return;
+    }
+    scheduler::register("coin_list_refresh", Duration::from_secs(seconds), || async {
+        refresh().await
+    });
+}
+
+async fn refresh() -> Result<(), String> {
+    let entries = coingecko::fetch_coin_list().await?;
+    if entries.is_empty() {
+        return Err("Coin list response was empty; keeping the previous list".to_string());
+    }
+    let next = Arc::new(CoinList::new(entries));
+    let previous = slot().write().unwrap().replace(next.clone());
+
+    match previous {
+        Some(previous) => report_changes(&previous, &next),
+        None => {
+            println!("📇 Loaded CoinGecko coin list ({} coins)", next.coins.len());
+            for coin in config::get().coins() {
+                if !next.coins.contains_key(&coin.coingecko_id) {
+                    println!(
+                        "⚠️ Tracked {} uses CoinGecko id '{}', which the coin list doesn't have (listed under {}: [{}])",
+                        coin.symbol,
+                        coin.coingecko_id,
+                        coin.symbol,
+                        ids_for(&coin.symbol).join(", ")
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Logs (and audits) what changed for tracked symbols: their coin delisted or renamed,
+// or a new coin listed under the same ticker.
+fn report_changes(previous: &CoinList, next: &CoinList) {
+    for coin in config::get().coins() {
+        let before = previous.coins.get(&coin.coingecko_id);
+        let after = next.coins.get(&coin.coingecko_id);
+        let change = match (before, after) {
+            (Some(_), None) => Some(json!({"change": "removed", "coingecko_id": coin.coingecko_id})),
+            (None, Some(listed)) => Some(json!({
+                "change": "added",
+                "coingecko_id": coin.coingecko_id,
+                "name": listed.name,
+            })),
+            (Some(before), Some(after)) if before != after => Some(json!({
+                "change": "renamed",
+                "coingecko_id": coin.coingecko_id,
+                "from": {"symbol": before.symbol, "name": before.name},
+                "to": {"symbol": after.symbol, "name": after.name},
+            })),
+            _ => None,
+        };
+        if let Some(detail) = change {
+            println!("📇 Coin list: tracked {} changed: {}", coin.symbol, detail);
+            audit::record("coin_list_changed", &coin.symbol, detail);
+        }
+
+        let known = previous.ids_by_symbol.get(&coin.symbol);
+        for id in next.ids_by_symbol.get(&coin.symbol).into_iter().flatten() {
+            if known.is_some_and(|ids| ids.contains(id)) || *id == coin.coingecko_id {
+                continue;
+            }
+            let detail = json!({"change": "listed_same_symbol", "coingecko_id": id});
+            println!("📇 Coin list: new coin '{}' listed under tracked symbol {}", id, coin.symbol);
+            audit::record("coin_list_changed", &coin.symbol, detail);
+        }
+    }
+
+    let added = next.coins.keys().filter(|id| !previous.coins.contains_key(*id)).count();
+    let removed = previous.coins.keys().filter(|id| !next.coins.contains_key(*id)).count();
+    if added > 0 || removed > 0 {
+        println!(
+            "📇 Refreshed CoinGecko coin list: {} coins, {} added, {} removed",
+            next.coins.len(),
+            added,
+            removed
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use chrono::Utc;
+
+use crate::config;
+use crate::format;
+use crate::history::Sample;
+use crate::latency;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+const BASE_URL: &str = "https://api.coingecko.com/api/v3";
+
+// simple/price is the cheap default; coins/markets adds rank and ATH data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoinGeckoEndpoint {
+    Simple,
+    Markets,
+}
+
+impl CoinGeckoEndpoint {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "simple" => Ok(CoinGeckoEndpoint::Simple),
+            "markets" => Ok(CoinGeckoEndpoint::Markets),
+            other => Err(format!(
+                "Invalid COINGECKO_ENDPOINT: {}. Use simple or markets.",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+    // The request succeeded but the coin was not in the response
+    Missing(String),
+    Failed(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Missing(msg) | FetchError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<String> for FetchError {
+    fn from(msg: String) -> Self {
+        FetchError::Failed(msg)
+    }
+}
+
+// Where a served price came from. cache means an earlier CoinGecko fetch still
+// within the refresh interval; none marks a placeholder when nothing was available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    Coingecko,
+    Cache,
+    None,
+}
+
+// live: the normal path for the source. fallback: served because the normal path failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceType {
+    Live,
+    Fallback,
+}
+
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct PriceData {
+    #[serde(serialize_with = "format::serialize_symbol")]
+    pub symbol: String,
+    pub price: f64,
+    #[serde(serialize_with = "format::serialize_timestamp")]
+    pub timestamp: i64,
+    pub change_24h: f64,
+    pub market_cap: Option<f64>,
+    pub volume_24h: Option<f64>,
+    // Only populated by the markets endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_cap_rank: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ath: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ath_change_percentage: Option<f64>,
+    // Markets endpoint with MULTI_TIMEFRAME_CHANGES only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_7d: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_30d: Option<f64>,
+    // Upstream price and why it wasn't served; only set when the price was adjusted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<String>,
+    pub source: PriceSource,
+    pub source_type: SourceType,
+}
+
+// Reads a JSON body of at most `limit` bytes (`setting` names where the limit comes from).
+// The read is abandoned as soon as the limit is passed, so a runaway response is never
+// buffered whole.
+async fn read_json(mut response: reqwest::Response, limit: usize, setting: &str) -> Result<Value, String> {
+    let path = response.url().path().to_string();
+    let too_large = |size: String| {
+        println!(
+            "📦 CoinGecko response too large: {} bytes, limit is {} ({}) - {}",
+            size, limit, setting, path
+        );
+        format!("Response too large: over {} bytes", limit)
+    };
+
+    if let Some(length) = response.content_length() {
+        if length as usize > limit {
+            return Err(too_large(length.to_string()));
+        }
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?
+    {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large(format!("{}+", body.len() + chunk.len())));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    serde_json::from_slice(&body).map_err(|e| format!("JSON error: {}", e))
+}
+
+async fn fetch_json(url: &str) -> Result<Value, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+
+    let response = client
+        .get(url)
+        .header("User-Agent", "TradingSignalsBot/1.0")
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API error: {}", response.status()));
+    }
+
+    read_json(response, config::get().max_upstream_response_bytes, "MAX_UPSTREAM_RESPONSE_BYTES").await
+}
+
+pub async fn fetch_price(
+    endpoint: CoinGeckoEndpoint,
+    symbol: &str,
+    coin_id: &str,
+) -> Result<PriceData, FetchError> {
+    let url = match endpoint {
+        CoinGeckoEndpoint::Simple => format!(
+            "{}/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true&include_market_cap=true&include_24hr_vol=true",
+            BASE_URL, coin_id
+        ),
+        CoinGeckoEndpoint::Markets => format!(
+            "{}/coins/markets?vs_currency=usd&ids={}&price_change_percentage={}",
+            BASE_URL,
+            coin_id,
+            if config::get().multi_timeframe_changes { "24h,7d,30d" } else { "24h" }
+        ),
+    };
+
+    let started = Instant::now();
+    let data = fetch_json(&url).await;
+    latency::observe_upstream_fetch(started.elapsed());
+    let data = data?;
+
+    match endpoint {
+        CoinGeckoEndpoint::Simple => parse_simple_price(symbol, coin_id, &data),
+        CoinGeckoEndpoint::Markets => parse_markets(symbol, coin_id, &data),
+    }
+}
+
+// {"bitcoin": {"usd": 1.0, "usd_24h_change": 1.0, "usd_market_cap": 1.0, "usd_24h_vol": 1.0}}
+fn parse_simple_price(symbol: &str, coin_id: &str, data: &Value) -> Result<PriceData, FetchError> {
+    let coin_data = data
+        .get(coin_id)
+        .ok_or_else(|| FetchError::Missing(format!("No data for {}", symbol)))?;
+
+    let price = coin_data
+        .get("usd")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| FetchError::Failed("No price in response".to_string()))?;
+
+    Ok(PriceData {
+        symbol: symbol.to_string(),
+        price,
+        timestamp: Utc::now().timestamp(),
+        change_24h: coin_data
+            .get("usd_24h_change")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0),
+        market_cap: coin_data.get("usd_market_cap").and_then(|v| v.as_f64()),
+        volume_24h: coin_data.get("usd_24h_vol").and_then(|v| v.as_f64()),
+        market_cap_rank: None,
+        ath: None,
+        ath_change_percentage: None,
+        change_7d: None,
+        change_30d: None,
+        raw_price: None,
+        adjustment: None,
+        source: PriceSource::Coingecko,
+        source_type: SourceType::Live,
+    })
+}
+
+// [{"id": "bitcoin", "current_price": 1.0, "market_cap": 1.0, "market_cap_rank": 1, ...}]
+fn parse_markets(symbol: &str, coin_id: &str, data: &Value) -> Result<PriceData, FetchError> {
+    let coin_data = data
+        .as_array()
+        .and_then(|coins| {
+            coins
+                .iter()
+                .find(|c| c.get("id").and_then(|v| v.as_str()) == Some(coin_id))
+        })
+        .ok_or_else(|| FetchError::Missing(format!("No data for {}", symbol)))?;
+
+    let price = coin_data
+        .get("current_price")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| FetchError::Failed("No price in response".to_string()))?;
+
+    Ok(PriceData {
+        symbol: symbol.to_string(),
+        price,
+        timestamp: Utc::now().timestamp(),
+        change_24h: coin_data
+            .get("price_change_percentage_24h")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0),
+        market_cap: coin_data.get("market_cap").and_then(|v| v.as_f64()),
+        volume_24h: coin_data.get("total_volume").and_then(|v| v.as_f64()),
+        market_cap_rank: coin_data
+            .get("market_cap_rank")
+            .and_then(|v| v.as_u64())
+            .map(|r| r as u32),
+        ath: coin_data.get("ath").and_then(|v| v.as_f64()),
+        ath_change_percentage: coin_data
+            .get("ath_change_percentage")
+            .and_then(|v| v.as_f64()),
+        change_7d: coin_data
+            .get("price_change_percentage_7d_in_currency")
+            .and_then(|v| v.as_f64()),
+        change_30d: coin_data
+            .get("price_change_percentage_30d_in_currency")
+            .and_then(|v| v.as_f64()),
+        raw_price: None,
+        adjustment: None,
+        source: PriceSource::Coingecko,
+        source_type: SourceType::Live,
+    })
+}
+
+// (symbol, coingecko id, market cap rank) for the top `count` coins, best ranked first.
+pub async fn fetch_top_coins(count: usize) -> Result<Vec<(String, String, u32)>, String> {
+    let url = format!(
+        "{}/coins/markets?vs_currency=usd&order=market_cap_desc&per_page={}&page=1",
+        BASE_URL,
+        count.clamp(1, 250)
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+
+    let response = client
+        .get(&url)
+        .header("User-Agent", "TradingSignalsBot/1.0")
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API error: {}", response.status()));
+    }
+
+    let data = read_json(response, config::get().max_upstream_response_bytes, "MAX_UPSTREAM_RESPONSE_BYTES").await?;
+
+    // [{"id": "bitcoin", "symbol": "btc", "market_cap_rank": 1, ...}]
+    let entries = data.as_array().ok_or("Markets response is not a list")?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.get("id")?.as_str()?;
+            let symbol = entry.get("symbol")?.as_str()?;
+            let rank = entry.get("market_cap_rank")?.as_u64()?;
+            Some((symbol.to_uppercase(), id.to_string(), rank as u32))
+        })
+        .collect())
+}
+
+// Every coin CoinGecko lists, from /coins/list: (id, symbol upper-cased, name)
+pub async fn fetch_coin_list() -> Result<Vec<(String, String, String)>, String> {
+    let url = format!("{}/coins/list", BASE_URL);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+
+    let response = client
+        .get(&url)
+        .header("User-Agent", "TradingSignalsBot/1.0")
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API error: {}", response.status()));
+    }
+
+    let data = read_json(response, config::get().max_coin_list_response_bytes, "MAX_COIN_LIST_RESPONSE_BYTES").await?;
+
+    // [{"id": "bitcoin", "symbol": "btc", "name": "Bitcoin"}, ...]
+    let entries = data.as_array().ok_or("Coin list response is not a list")?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.get("id")?.as_str()?;
+            let symbol = entry.get("symbol")?.as_str()?;
+            let name = entry.get("name")?.as_str()?;
+            Some((id.to_string(), symbol.to_uppercase(), name.to_string()))
+        })
+        .collect())
+}
+
+// Historical prices from /coins/{id}/market_chart, oldest first. CoinGecko picks the
+// granularity: 5-minute points for 1 day, hourly up to 90 days.
+pub async fn fetch_market_chart(coin_id: &str, days: u32) -> Result<Vec<Sample>, String> {
+    let url = format!(
+        "{}/coins/{}/market_chart?vs_currency=usd&days={}",
+        BASE_URL, coin_id, days
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+
+    let response = client
+        .get(&url)
+        .header("User-Agent", "TradingSignalsBot/1.0")
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API error: {}", response.status()));
+    }
+
+    let data = read_json(response, config::get().max_upstream_response_bytes, "MAX_UPSTREAM_RESPONSE_BYTES").await?;
+
+    // {"prices": [[timestamp_ms, price], ...], ...}
+    let points = data
+        .get("prices")
+        .and_then(|v| v.as_array())
+        .ok_or("No prices in market_chart response")?;
+
+    Ok(points
+        .iter()
+        .filter_map(|point| {
+            let timestamp_ms = point.get(0)?.as_f64()?;
+            let price = point.get(1)?.as_f64()?;
+            Some(Sample {
+                timestamp: (timestamp_ms / 1000.0) as i64,
+                price,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::stub_server;
+
+    #[tokio::test]
+    async fn oversized_responses_are_rejected() {
+        let body = serde_json::to_string(&vec!["padding"; 1000]).unwrap();
+        let stub = stub_server(200, body.clone(), Duration::ZERO).await;
+
+        let response = reqwest::get(&stub.base_url).await.unwrap();
+        let error = read_json(response, 1024, "MAX_UPSTREAM_RESPONSE_BYTES").await.unwrap_err();
+        assert_eq!(error, "Response too large: over 1024 bytes");
+
+        let response = reqwest::get(&stub.base_url).await.unwrap();
+        let data = read_json(response, body.len(), "MAX_UPSTREAM_RESPONSE_BYTES").await.unwrap();
+        assert_eq!(data.as_array().map(Vec::len), Some(1000));
+    }
+
+    #[test]
+    fn sub_cent_markets_prices_keep_full_precision() {
+        let payload = r#"[{"id": "shiba-inu", "symbol": "shib", "current_price": 0.00001234567891,
+            "market_cap": 7275312345.0, "market_cap_rank": 17, "total_volume": 181234567.0,
+            "price_change_percentage_24h": -1.23456}]"#;
+        let data: Value = serde_json::from_str(payload).unwrap();
+        let price = parse_markets("SHIB", "shiba-inu", &data).unwrap();
+
+        assert_eq!(price.price, 0.00001234567891);
+        assert_eq!(price.market_cap_rank, Some(17));
+        let json = format::to_json_string(&price.price, format::NumberFormat::Fixed);
+        assert_eq!(json, "0.00001234567891");
+
+        assert!(matches!(
+            parse_markets("PEPE", "pepe", &data),
+            Err(FetchError::Missing(_))
+        ));
+    }
+}
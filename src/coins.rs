@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+
+use crate::format;
+
+// Fraction of the CoinGecko call budget at which startup starts warning.
+const BUDGET_WARN_RATIO: f64 = 0.8;
+
+// Coins picked by TRACK_TOP_N. Replaces the configured list once the first ranking is in.
+static TRACKED: OnceLock<RwLock<Vec<Coin>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coin {
+    #[serde(serialize_with = "format::serialize_symbol")]
+    pub symbol: String,
+    pub coingecko_id: String,
+}
+
+fn known_coingecko_id(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "BTC" => Some("bitcoin"),
+        "ETH" => Some("ethereum"),
+        "SOL" => Some("solana"),
+        "PAXG" => Some("pax-gold"),
+        "BNB" => Some("binancecoin"),
+        "XRP" => Some("ripple"),
+        "ADA" => Some("cardano"),
+        "DOGE" => Some("dogecoin"),
+        "USDT" => Some("tether"),
+        "USDC" => Some("usd-coin"),
+        _ => None,
+    }
+}
+
+pub fn tracked() -> Option<Vec<Coin>> {
+    TRACKED.get().map(|tracked| tracked.read().unwrap().clone())
+}
+
+pub fn set_tracked(coins: Vec<Coin>) {
+    let tracked = TRACKED.get_or_init(|| RwLock::new(Vec::new()));
+    *tracked.write().unwrap() = coins;
+}
+
+pub fn default_coins() -> Vec<Coin> {
+    ["BTC", "ETH", "SOL", "PAXG"]
+        .iter()
+        .map(|symbol| Coin {
+            symbol: symbol.to_string(),
+            coingecko_id: known_coingecko_id(symbol).unwrap().to_string(),
+        })
+        .collect()
+}
+
+// Parses "BTC,ETH:ethereum,WIF:dogwifcoin". Bare symbols must be ones we know the
+// CoinGecko ID for; anything else needs an explicit SYMBOL:coingecko-id pair.
+pub fn parse_supported_coins(raw: &str) -> Result<Vec<Coin>, String> {
+    let mut coins: Vec<Coin> = Vec::new();
+
+    for entry in raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let (symbol, id) = match entry.split_once(':') {
+            Some((symbol, id)) => (symbol.trim().to_uppercase(), id.trim().to_lowercase()),
+            None => {
+                let symbol = entry.to_uppercase();
+                let id = known_coingecko_id(&symbol).ok_or_else(|| {
+                    format!(
+                        "Unknown coin '{}' in SUPPORTED_COINS: use SYMBOL:coingecko-id",
+                        entry
+                    )
+                })?;
+                (symbol, id.to_string())
+            }
+        };
+
+        if symbol.is_empty() || id.is_empty() {
+            return Err(format!("Invalid SUPPORTED_COINS entry '{}'", entry));
+        }
+        if coins.iter().any(|c| c.symbol == symbol) {
+            return Err(format!("Duplicate symbol '{}' in SUPPORTED_COINS", symbol));
+        }
+
+        coins.push(Coin {
+            symbol,
+            coingecko_id: id,
+        });
+    }
+
+    if coins.is_empty() {
+        return Err("SUPPORTED_COINS must list at least one coin".to_string());
+    }
+
+    Ok(coins)
+}
+
+// What to do when REFRESH_INTERVAL_SECONDS is below the minimum safe interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefreshIntervalPolicy {
+    // Log a warning and run at the configured interval anyway
+    Warn,
+    // Log a warning and raise the interval to the minimum safe one
+    Clamp,
+    // Refuse to start
+    Refuse,
+}
+
+impl RefreshIntervalPolicy {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "warn" => Ok(RefreshIntervalPolicy::Warn),
+            "clamp" => Ok(RefreshIntervalPolicy::Clamp),
+            "refuse" => Ok(RefreshIntervalPolicy::Refuse),
+            other => Err(format!(
+                "Invalid REFRESH_INTERVAL_POLICY: {}. Use warn, clamp or refuse.",
+                other
+            )),
+        }
+    }
+}
+
+// Shortest interval that keeps one call per coin per refresh within the budget.
+pub fn min_safe_refresh_interval(coin_count: usize, calls_per_minute_budget: u32) -> u64 {
+    let budget = calls_per_minute_budget.max(1) as u64;
+    ((coin_count as u64 * 60).div_ceil(budget)).max(1)
+}
+
+// Prices are fetched one coin per request, so a client polling constantly costs
+// one CoinGecko call per coin every refresh interval.
+pub fn estimated_calls_per_minute(coin_count: usize, refresh_interval_seconds: u64) -> f64 {
+    coin_count as f64 * 60.0 / refresh_interval_seconds.max(1) as f64
+}
+
+pub fn budget_warning(
+    coin_count: usize,
+    refresh_interval_seconds: u64,
+    calls_per_minute_budget: u32,
+) -> Option<String> {
+    let estimated = estimated_calls_per_minute(coin_count, refresh_interval_seconds);
+    let budget = calls_per_minute_budget as f64;
+
+    if estimated > budget {
+        Some(format!(
+            "{} coins every {}s needs ~{:.0} CoinGecko calls/min, over the budget of {}; expect rate limiting",
+            coin_count, refresh_interval_seconds, estimated, calls_per_minute_budget
+        ))
+    } else if estimated >= budget * BUDGET_WARN_RATIO {
+        Some(format!(
+            "{} coins every {}s needs ~{:.0} CoinGecko calls/min, close to the budget of {}",
+            coin_count, refresh_interval_seconds, estimated, calls_per_minute_budget
+        ))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,219 @@
+use actix_web::body::{self, EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::web::Bytes;
+use serde::Deserialize;
+use std::io::Write;
+
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum CompressionAlgorithm {
+    Gzip,
+    Brotli,
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "gzip" => Ok(CompressionAlgorithm::Gzip),
+            "br" | "brotli" => Ok(CompressionAlgorithm::Brotli),
+            "zstd" => Ok(CompressionAlgorithm::Zstd),
+            other => Err(format!("Unknown compression algorithm: {}. Use gzip, br or zstd.", other)),
+        }
+    }
+
+    // Content-Encoding token
+    pub fn token(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Gzip => "gzip",
+            CompressionAlgorithm::Brotli => "br",
+            CompressionAlgorithm::Zstd => "zstd",
+        }
+    }
+
+    fn levels(&self) -> (u32, u32) {
+        match self {
+            CompressionAlgorithm::Gzip => (0, 9),
+            CompressionAlgorithm::Brotli => (0, 11),
+            CompressionAlgorithm::Zstd => (1, 22),
+        }
+    }
+
+    // (fast, default, best)
+    fn presets(&self) -> (u32, u32, u32) {
+        match self {
+            CompressionAlgorithm::Gzip => (1, 6, 9),
+            CompressionAlgorithm::Brotli => (1, 5, 11),
+            // 20 and up are zstd's memory-hungry "ultra" levels
+            CompressionAlgorithm::Zstd => (1, 3, 19),
+        }
+    }
+
+    fn compress(&self, data: &[u8], level: u32) -> std::io::Result<Vec<u8>> {
+        match self {
+            CompressionAlgorithm::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            CompressionAlgorithm::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, level, 22);
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+            CompressionAlgorithm::Zstd => zstd::stream::encode_all(data, level as i32),
+        }
+    }
+}
+
+// COMPRESSION_ALGORITHMS (preference order, "off" disables) and COMPRESSION_LEVEL: a
+// preset (fast, default or best), per-algorithm levels such as "br=4,gzip=9", or a
+// preset followed by overrides, e.g. "fast,br=5". Each level is checked against its
+// algorithm's range.
+pub fn parse_compression(algorithms: &str, level: &str) -> Result<Vec<(CompressionAlgorithm, u32)>, String> {
+    if algorithms.trim().eq_ignore_ascii_case("off") {
+        return Ok(Vec::new());
+    }
+    let mut order = Vec::new();
+    for token in algorithms.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let algorithm = CompressionAlgorithm::parse(token)?;
+        if !order.contains(&algorithm) {
+            order.push(algorithm);
+        }
+    }
+
+    let mut preset = "default";
+    let mut overrides = Vec::new();
+    for entry in level.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match entry.split_once('=') {
+            Some((name, value)) => {
+                let algorithm = CompressionAlgorithm::parse(name)?;
+                let value: u32 = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid COMPRESSION_LEVEL for {}: {}", algorithm.token(), value.trim()))?;
+                overrides.push((algorithm, value));
+            }
+            None => match entry.to_lowercase().as_str() {
+                "fast" | "default" | "best" => preset = entry,
+                other => {
+                    return Err(format!(
+                        "Invalid COMPRESSION_LEVEL: {}. Use fast, default, best or algorithm=level.",
+                        other
+                    ))
+                }
+            },
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|algorithm| {
+            let (fast, default, best) = algorithm.presets();
+            let level = match overrides.iter().rev().find(|(a, _)| *a == algorithm) {
+                Some((_, level)) => *level,
+                None => match preset.to_lowercase().as_str() {
+                    "fast" => fast,
+                    "best" => best,
+                    _ => default,
+                },
+            };
+            let (min, max) = algorithm.levels();
+            if level < min || level > max {
+                return Err(format!(
+                    "COMPRESSION_LEVEL {} for {} is out of range ({}-{})",
+                    level,
+                    algorithm.token(),
+                    min,
+                    max
+                ));
+            }
+            Ok((algorithm, level))
+        })
+        .collect()
+}
+
+// The first configured algorithm the client accepts with a non-zero q-value; an
+// explicit entry beats `*`
+fn negotiate(accept_encoding: &str) -> Option<(CompressionAlgorithm, u32)> {
+    let offered: Vec<(String, f64)> = accept_encoding
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let token = parts.next()?.trim().to_lowercase();
+            let q = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f64>().ok())
+                .unwrap_or(1.0);
+            (!token.is_empty()).then_some((token, q))
+        })
+        .collect();
+    let accepts = |token: &str| {
+        offered
+            .iter()
+            .find(|(t, _)| t == token)
+            .or_else(|| offered.iter().find(|(t, _)| t == "*"))
+            .is_some_and(|(_, q)| *q > 0.0)
+    };
+    config::get()
+        .compression
+        .iter()
+        .copied()
+        .find(|(algorithm, _)| accepts(algorithm.token()))
+}
+
+// Middleware: compresses sized responses of at least COMPRESSION_MIN_BYTES with the
+// negotiated algorithm. Streams (SSE, WebSocket upgrades) and already-encoded
+// responses pass through untouched, as does anything that wouldn't get smaller.
+pub async fn compress(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody, Bytes>>, actix_web::Error> {
+    let chosen = req
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(negotiate);
+    let response = next.call(req).await?;
+
+    let Some((algorithm, level)) = chosen else {
+        return Ok(response.map_into_left_body());
+    };
+    let size = match response.response().body().size() {
+        body::BodySize::Sized(size) => size,
+        _ => return Ok(response.map_into_left_body()),
+    };
+    if size < config::get().compression_min_bytes || response.headers().contains_key(header::CONTENT_ENCODING) {
+        return Ok(response.map_into_left_body());
+    }
+
+    let (req, response) = response.into_parts();
+    let (mut response, body) = response.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        actix_web::error::ErrorInternalServerError(e.to_string())
+    })?;
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    let bytes = match algorithm.compress(&bytes, level) {
+        Ok(compressed) if compressed.len() < bytes.len() => {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_ENCODING, HeaderValue::from_static(algorithm.token()));
+            response.headers_mut().remove(header::CONTENT_LENGTH);
+            Bytes::from(compressed)
+        }
+        Ok(_) => bytes,
+        Err(e) => {
+            println!("⚠️ {} compression failed, sending uncompressed: {}", algorithm.token(), e);
+            bytes
+        }
+    };
+    let response = response.set_body(bytes);
+    Ok(ServiceResponse::new(req, response).map_into_right_body())
+}
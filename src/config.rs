@@ -1,7 +1,31 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use crate::alerts::{ActionMap, ConflictResolution, DedupScope, SeverityRules};
+use crate::api_keys::{self, ApiKey};
+use crate::routes::{self, ai_explanation::{self, AiMode, AiProvider}};
+use crate::coingecko::CoinGeckoEndpoint;
+use crate::coins::{self, Coin, RefreshIntervalPolicy};
+use crate::compression::{self, CompressionAlgorithm};
+use crate::format::{NumberFormat, SymbolCase, TimestampFormat};
+use crate::history::WarmupMode;
+use crate::indicators::{IndicatorSettings, NonFinite};
+use crate::liquidity::{self, LiquidityWindow};
+use crate::notifier::{self, NotifyFormat};
+use crate::quality::QualityWeights;
+use crate::strategy::{self, SignalBasis};
+use crate::streams::EvictionPolicy;
+use crate::watch_rules::{self, WatchRule};
+
+const DEFAULT_MAX_SUPPORTED_COINS: usize = 25;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
 
 #[derive(Debug, Clone, Deserialize)]
+#[allow(dead_code)]
 pub struct Config {
     pub host: String,
     pub port: u16,
@@ -18,12 +42,148 @@ pub struct Config {
     pub macd_fast: usize,
     pub macd_slow: usize,
     pub macd_signal: usize,
+    pub non_finite_numbers: NonFinite,
+    pub divergence_lookback: usize,
+    pub divergence_min_rsi_gap: f64,
     
     // Solana blockchain
     pub solana_rpc_url: Option<String>,
     pub solana_wallet_key: Option<String>,
     pub solana_program_id: Option<String>,
     pub update_interval_seconds: Option<u64>,
+
+    // Price data
+    // SUPPORTED_COINS, or the starting set until TRACK_TOP_N's first ranking; use coins()
+    pub supported_coins: Vec<Coin>,
+    pub track_top_n: usize,
+    pub track_top_n_hysteresis: usize,
+    pub track_top_n_interval_seconds: u64,
+    pub coin_list_refresh_seconds: u64,
+    pub stablecoins: Vec<String>,
+    pub max_supported_coins: usize,
+    // Effective interval, after REFRESH_INTERVAL_POLICY has been applied
+    pub refresh_interval_seconds: u64,
+    pub configured_refresh_interval_seconds: u64,
+    pub min_refresh_interval_seconds: u64,
+    pub refresh_interval_policy: RefreshIntervalPolicy,
+    pub coingecko_calls_per_minute: u32,
+    pub price_outlier_percent: f64,
+    pub single_flight_fetches: bool,
+    pub forced_fetch_min_interval_seconds: u64,
+    pub coingecko_endpoint: CoinGeckoEndpoint,
+    // Coins always fetched through coins/markets for its extra significant figures
+    pub coingecko_markets_symbols: Vec<String>,
+    pub multi_timeframe_changes: bool,
+    pub max_upstream_response_bytes: usize,
+    // coins/list only; the whole listing is far larger than any other response
+    pub max_coin_list_response_bytes: usize,
+    pub number_format: NumberFormat,
+    pub timestamp_format: TimestampFormat,
+    pub symbol_case: SymbolCase,
+    pub background_refresh: bool,
+    pub stale_data_alert_seconds: u64,
+    pub clock_jump_alert_seconds: u64,
+    pub delisted_after_refreshes: u32,
+    pub history_capacity: usize,
+    pub history_seed_days: u32,
+    pub history_min_change_percent: f64,
+    pub history_min_change_absolute: f64,
+    pub min_history_samples: usize,
+    pub signals_warmup: WarmupMode,
+    pub signal_hold_seconds: u64,
+    // Serve /signals as newline-delimited JSON on request
+    pub signals_ndjson: bool,
+    // Empty is off; LOW_LIQUIDITY_SYMBOLS empty applies the windows to every coin
+    pub low_liquidity_windows: Vec<LiquidityWindow>,
+    pub low_liquidity_symbols: Vec<String>,
+    pub low_liquidity_confidence_factor: f64,
+    pub signals_ws_batch_ms: u64,
+    pub max_stream_connections: usize,
+    pub stream_eviction: EvictionPolicy,
+    pub signal_changes_retention: usize,
+    pub metrics_histogram_buckets_ms: Vec<f64>,
+    pub request_log_sample_rate: u64,
+    // Algorithms in preference order with their levels; empty disables compression
+    pub compression: Vec<(CompressionAlgorithm, u32)>,
+    pub compression_min_bytes: u64,
+    pub request_log_seed: Option<u64>,
+    pub pushgateway_url: Option<String>,
+    pub pushgateway_interval_seconds: u64,
+    pub pushgateway_job: String,
+    pub pushgateway_instance: String,
+    pub data_quality_weights: QualityWeights,
+    pub signal_strength_weights: HashMap<String, f64>,
+    // What /signals bands into the signal: the 24h change (default) or the composite score
+    pub signal_basis: SignalBasis,
+    // History positions the strength gauge's indicator leans are averaged over, and the
+    // half-life of their weights in samples (0 = equal weights)
+    pub signal_smoothing_samples: usize,
+    pub signal_half_life_samples: f64,
+    pub symbol_purge: bool,
+    pub symbol_purge_grace_seconds: u64,
+
+    // Outbound notifications
+    pub notify_webhook_url: Option<String>,
+    pub notify_format: NotifyFormat,
+    // NOTIFY_TEMPLATE, replacing the NOTIFY_FORMAT preset
+    pub notify_template: Option<serde_json::Value>,
+    pub notify_symbol_interval_seconds: u64,
+    pub notify_summarize: bool,
+
+    // Paper trading
+    pub paper_trading: bool,
+    pub paper_starting_balance: f64,
+    pub paper_position_size: f64,
+    pub paper_state_file: String,
+    pub paper_trade_log_retention: usize,
+
+    // Endpoint patterns from routes::ROUTES that answer 404
+    pub disabled_endpoints: Vec<String>,
+
+    // Admin
+    pub admin_token: Option<String>,
+    pub admin_token_previous: Option<String>,
+
+    // Client tiers; empty leaves every endpoint open and unlimited
+    pub api_keys: Vec<ApiKey>,
+    pub public_requests_per_minute: u32,
+    // Proxies whose X-Forwarded-For is believed when telling anonymous clients apart
+    pub trusted_proxies: Vec<IpAddr>,
+    // In-flight requests above which /ready fails; 0 never sheds
+    pub load_shed_threshold: usize,
+
+    // Alerts
+    pub alert_dedup_scope: DedupScope,
+    pub alert_dedup_window_seconds: u64,
+    pub alert_dedup_price_tolerance_percent: f64,
+    pub alert_severity: SeverityRules,
+    pub alert_action_map: ActionMap,
+    pub watch_rules: Vec<WatchRule>,
+    pub confidence_drop_alert: f64,
+    pub log_webhook_bodies: bool,
+    pub alert_price_enrichment: bool,
+    pub alert_retention: usize,
+    pub alert_store_file: Option<String>,
+    pub alert_compaction_interval_seconds: u64,
+    pub alert_store_quarantine: bool,
+    pub signal_conflict_resolution: ConflictResolution,
+    pub external_signal_max_age_seconds: u64,
+    pub confidence_drop_alert_symbols: HashMap<String, f64>,
+
+    // AI explanations
+    pub ai_mode: AiMode,
+    pub ai_providers: Vec<AiProvider>,
+    pub explanation_cache_ttl_seconds: u64,
+    pub explanation_cache_max_entries: usize,
+    pub single_flight_explanations: bool,
+    pub openai_max_concurrency: usize,
+    pub openai_daily_budget: u32,
+    pub openai_auth_cooldown_seconds: u64,
+    pub ai_latency_budget_ms: u64,
+    pub explanation_prewarm: bool,
+    pub explain_all_min_interval_seconds: u64,
+    pub explain_batch_max_symbols: usize,
+    pub explanation_prewarm_concurrency: usize,
 }
 
 impl Config {
@@ -34,7 +194,71 @@ impl Config {
             .map(|s| s.trim().to_string())
             .collect();
         
-        Ok(Config {
+        let supported_coins = match env::var("SUPPORTED_COINS") {
+            Ok(raw) => coins::parse_supported_coins(&raw)?,
+            Err(_) => coins::default_coins(),
+        };
+        let max_supported_coins = parse_or("MAX_SUPPORTED_COINS", DEFAULT_MAX_SUPPORTED_COINS)?;
+        if supported_coins.len() > max_supported_coins {
+            return Err(format!(
+                "SUPPORTED_COINS lists {} coins but MAX_SUPPORTED_COINS is {}",
+                supported_coins.len(),
+                max_supported_coins
+            ));
+        }
+
+        let track_top_n: usize = parse_or("TRACK_TOP_N", 0)?;
+        let track_top_n_hysteresis: usize = parse_or("TRACK_TOP_N_HYSTERESIS", 5)?;
+        if track_top_n > 0 {
+            if env::var("SUPPORTED_COINS").is_ok() {
+                return Err("TRACK_TOP_N and SUPPORTED_COINS are mutually exclusive; set only one".to_string());
+            }
+            if track_top_n > max_supported_coins {
+                return Err(format!(
+                    "TRACK_TOP_N is {} but MAX_SUPPORTED_COINS is {}",
+                    track_top_n, max_supported_coins
+                ));
+            }
+        }
+        // Budget for the most coins that can be tracked at once
+        let coin_count = if track_top_n > 0 {
+            (track_top_n + track_top_n_hysteresis).min(max_supported_coins)
+        } else {
+            supported_coins.len()
+        };
+        
+        let configured_refresh_interval_seconds: u64 = parse_or("REFRESH_INTERVAL_SECONDS", 30)?;
+        if configured_refresh_interval_seconds == 0 {
+            return Err("REFRESH_INTERVAL_SECONDS must be greater than 0".to_string());
+        }
+        let coingecko_calls_per_minute: u32 = parse_or("COINGECKO_CALLS_PER_MINUTE", 30)?;
+        let min_refresh_interval_seconds =
+            coins::min_safe_refresh_interval(coin_count, coingecko_calls_per_minute);
+        let refresh_interval_policy = match env::var("REFRESH_INTERVAL_POLICY") {
+            Ok(raw) => RefreshIntervalPolicy::parse(&raw)?,
+            Err(_) => RefreshIntervalPolicy::Warn,
+        };
+        let refresh_interval_seconds = if configured_refresh_interval_seconds >= min_refresh_interval_seconds {
+            configured_refresh_interval_seconds
+        } else {
+            match refresh_interval_policy {
+                RefreshIntervalPolicy::Warn => configured_refresh_interval_seconds,
+                RefreshIntervalPolicy::Clamp => min_refresh_interval_seconds,
+                RefreshIntervalPolicy::Refuse => {
+                    return Err(format!(
+                        "REFRESH_INTERVAL_SECONDS={} is below the minimum of {}s for {} coins within {} CoinGecko calls/min",
+                        configured_refresh_interval_seconds,
+                        min_refresh_interval_seconds,
+                        coin_count,
+                        coingecko_calls_per_minute
+                    ))
+                }
+            }
+        };
+        
+        let alert_severity = parse_severity_rules()?;
+
+        let mut config = Config {
             host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             port: env::var("PORT")
                 .unwrap_or_else(|_| "8080".to_string())
@@ -54,6 +278,12 @@ impl Config {
             macd_fast: 12,
             macd_slow: 26,
             macd_signal: 9,
+            non_finite_numbers: match env::var("NON_FINITE_NUMBERS") {
+                Ok(raw) => NonFinite::parse(&raw)?,
+                Err(_) => NonFinite::Null,
+            },
+            divergence_lookback: parse_or("DIVERGENCE_LOOKBACK", 30)?,
+            divergence_min_rsi_gap: parse_or("DIVERGENCE_MIN_RSI_GAP", 2.0)?,
             
             // Solana (optional)
             solana_rpc_url: env::var("SOLANA_RPC_URL").ok(),
@@ -62,6 +292,582 @@ impl Config {
             update_interval_seconds: env::var("UPDATE_INTERVAL_SECONDS")
                 .ok()
                 .and_then(|s| s.parse().ok()),
-        })
+
+            supported_coins,
+            track_top_n,
+            track_top_n_hysteresis,
+            track_top_n_interval_seconds: parse_or::<u64>("TRACK_TOP_N_INTERVAL_SECONDS", 3600)?.max(60),
+            coin_list_refresh_seconds: match parse_or::<u64>("COIN_LIST_REFRESH_SECONDS", 86400)? {
+                0 => 0,
+                seconds => seconds.max(3600),
+            },
+            stablecoins: env::var("STABLECOINS")
+                .unwrap_or_else(|_| "USDT,USDC,DAI".to_string())
+                .split(',')
+                .map(|s| s.trim().to_uppercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            max_supported_coins,
+            refresh_interval_seconds,
+            configured_refresh_interval_seconds,
+            min_refresh_interval_seconds,
+            refresh_interval_policy,
+            coingecko_calls_per_minute,
+            price_outlier_percent: parse_or("PRICE_OUTLIER_PERCENT", 0.0)?,
+            single_flight_fetches: parse_or("SINGLE_FLIGHT_FETCHES", true)?,
+            forced_fetch_min_interval_seconds: parse_or("FORCED_FETCH_MIN_INTERVAL_SECONDS", 10)?,
+            max_upstream_response_bytes: parse_or::<usize>("MAX_UPSTREAM_RESPONSE_BYTES", 2_000_000)?.max(1024),
+            max_coin_list_response_bytes: parse_or::<usize>("MAX_COIN_LIST_RESPONSE_BYTES", 8_000_000)?.max(1024),
+            coingecko_endpoint: match env::var("COINGECKO_ENDPOINT") {
+                Ok(raw) => CoinGeckoEndpoint::parse(&raw)?,
+                Err(_) => CoinGeckoEndpoint::Simple,
+            },
+            coingecko_markets_symbols: env::var("COINGECKO_MARKETS_SYMBOLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_uppercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            multi_timeframe_changes: parse_or("MULTI_TIMEFRAME_CHANGES", false)?,
+            number_format: match env::var("NUMBER_FORMAT") {
+                Ok(raw) => NumberFormat::parse(&raw)?,
+                Err(_) => NumberFormat::Number,
+            },
+            timestamp_format: match env::var("TIMESTAMP_FORMAT") {
+                Ok(raw) => TimestampFormat::parse(&raw)?,
+                Err(_) => TimestampFormat::Rfc3339,
+            },
+            symbol_case: match env::var("SYMBOL_CASE") {
+                Ok(raw) => SymbolCase::parse(&raw)?,
+                Err(_) => SymbolCase::Upper,
+            },
+            background_refresh: parse_or("BACKGROUND_REFRESH", true)?,
+            stale_data_alert_seconds: parse_or("STALE_DATA_ALERT_SECONDS", 600)?,
+            clock_jump_alert_seconds: parse_or("CLOCK_JUMP_ALERT_SECONDS", 30)?,
+            delisted_after_refreshes: parse_or::<u32>("DELISTED_AFTER_REFRESHES", 5)?.max(1),
+            history_capacity: parse_or::<usize>("HISTORY_CAPACITY", 500)?.max(1),
+            history_seed_days: parse_or("HISTORY_SEED_DAYS", 0)?,
+            history_min_change_percent: parse_or("HISTORY_MIN_CHANGE_PERCENT", 0.0)?,
+            history_min_change_absolute: parse_or("HISTORY_MIN_CHANGE_ABSOLUTE", 0.0)?,
+            min_history_samples: parse_or("MIN_HISTORY_SAMPLES", 0)?,
+            signals_warmup: match env::var("SIGNALS_WARMUP") {
+                Ok(raw) => WarmupMode::parse(&raw)?,
+                Err(_) => WarmupMode::Strict,
+            },
+            signal_hold_seconds: parse_or("SIGNAL_HOLD_SECONDS", 0)?,
+            signals_ndjson: parse_or("SIGNALS_NDJSON", true)?,
+            low_liquidity_windows: match env::var("LOW_LIQUIDITY_WINDOWS") {
+                Ok(raw) => liquidity::parse_windows(&raw)?,
+                Err(_) => Vec::new(),
+            },
+            low_liquidity_symbols: env::var("LOW_LIQUIDITY_SYMBOLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_uppercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            low_liquidity_confidence_factor: parse_or("LOW_LIQUIDITY_CONFIDENCE_FACTOR", 0.8)?,
+            signals_ws_batch_ms: parse_or("SIGNALS_WS_BATCH_MS", 1000)?,
+            max_stream_connections: parse_or("MAX_STREAM_CONNECTIONS", 100)?,
+            stream_eviction: match env::var("STREAM_EVICTION") {
+                Ok(raw) => EvictionPolicy::parse(&raw)?,
+                Err(_) => EvictionPolicy::Reject,
+            },
+            signal_changes_retention: parse_or::<usize>("SIGNAL_CHANGES_RETENTION", 500)?.max(1),
+            metrics_histogram_buckets_ms: parse_histogram_buckets()?,
+            request_log_sample_rate: parse_or("REQUEST_LOG_SAMPLE_RATE", 0)?,
+            compression: compression::parse_compression(
+                &env::var("COMPRESSION_ALGORITHMS").unwrap_or_else(|_| "br,zstd,gzip".to_string()),
+                &env::var("COMPRESSION_LEVEL").unwrap_or_default(),
+            )?,
+            compression_min_bytes: parse_or("COMPRESSION_MIN_BYTES", 1024)?,
+            request_log_seed: match env::var("REQUEST_LOG_SEED") {
+                Ok(raw) => Some(raw.trim().parse().map_err(|e| format!("Invalid REQUEST_LOG_SEED: {}", e))?),
+                Err(_) => None,
+            },
+            pushgateway_url: env::var("PUSHGATEWAY_URL").ok().filter(|s| !s.is_empty()),
+            pushgateway_interval_seconds: parse_or::<u64>("PUSHGATEWAY_INTERVAL_SECONDS", 30)?.max(1),
+            pushgateway_job: env::var("PUSHGATEWAY_JOB")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "trading-signals-backend".to_string()),
+            pushgateway_instance: env::var("PUSHGATEWAY_INSTANCE")
+                .or_else(|_| env::var("HOSTNAME"))
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "default".to_string()),
+            data_quality_weights: parse_quality_weights()?,
+            signal_strength_weights: parse_strength_weights()?,
+            signal_basis: match env::var("SIGNAL_BASIS") {
+                Ok(raw) => SignalBasis::parse(&raw)?,
+                Err(_) => SignalBasis::Change24h,
+            },
+            signal_smoothing_samples: parse_or::<usize>("SIGNAL_SMOOTHING_SAMPLES", 1)?.max(1),
+            signal_half_life_samples: parse_or("SIGNAL_HALF_LIFE_SAMPLES", 0.0)?,
+            symbol_purge: parse_or("SYMBOL_PURGE", true)?,
+            symbol_purge_grace_seconds: parse_or("SYMBOL_PURGE_GRACE_SECONDS", 600)?,
+
+            notify_webhook_url: env::var("NOTIFY_WEBHOOK_URL").ok().filter(|s| !s.is_empty()),
+            notify_format: match env::var("NOTIFY_FORMAT") {
+                Ok(raw) => NotifyFormat::parse(&raw)?,
+                Err(_) => NotifyFormat::Generic,
+            },
+            notify_template: match env::var("NOTIFY_TEMPLATE") {
+                Ok(_) if env::var("NOTIFY_FORMAT").is_ok() => {
+                    return Err("NOTIFY_TEMPLATE and NOTIFY_FORMAT are mutually exclusive; set only one".to_string())
+                }
+                Ok(raw) => Some(notifier::parse_template(&raw)?),
+                Err(_) => None,
+            },
+            notify_symbol_interval_seconds: parse_or("NOTIFY_SYMBOL_INTERVAL_SECONDS", 300)?,
+            notify_summarize: parse_or("NOTIFY_SUMMARIZE", true)?,
+
+            paper_trading: parse_or("PAPER_TRADING", false)?,
+            paper_starting_balance: parse_or("PAPER_STARTING_BALANCE", 10_000.0)?,
+            paper_position_size: parse_or("PAPER_POSITION_SIZE", 1_000.0)?,
+            paper_state_file: env::var("PAPER_STATE_FILE")
+                .unwrap_or_else(|_| "paper_portfolio.json".to_string()),
+            paper_trade_log_retention: parse_or("PAPER_TRADE_LOG_RETENTION", 1000)?,
+
+            disabled_endpoints: parse_disabled_endpoints()?,
+
+            admin_token: env::var("ADMIN_TOKEN").ok().filter(|s| !s.is_empty()),
+            admin_token_previous: env::var("ADMIN_TOKEN_PREVIOUS").ok().filter(|s| !s.is_empty()),
+
+            api_keys: match env::var("API_KEYS") {
+                Ok(raw) => api_keys::parse_api_keys(&raw)?,
+                Err(_) => Vec::new(),
+            },
+            public_requests_per_minute: parse_or("PUBLIC_REQUESTS_PER_MINUTE", 60)?,
+            trusted_proxies: match env::var("TRUSTED_PROXIES") {
+                Ok(raw) => api_keys::parse_trusted_proxies(&raw)?,
+                Err(_) => Vec::new(),
+            },
+            load_shed_threshold: parse_or("LOAD_SHED_THRESHOLD", 0)?,
+
+            alert_dedup_scope: match env::var("ALERT_DEDUP_SCOPE") {
+                Ok(raw) => DedupScope::parse(&raw)?,
+                Err(_) => DedupScope::SymbolActionPrice,
+            },
+            alert_dedup_window_seconds: parse_or("ALERT_DEDUP_WINDOW_SECONDS", 60)?,
+            alert_dedup_price_tolerance_percent: parse_or("ALERT_DEDUP_PRICE_TOLERANCE_PERCENT", 0.5)?,
+            alert_severity,
+            alert_action_map: parse_action_map()?,
+            watch_rules: Vec::new(),
+            confidence_drop_alert: parse_or("CONFIDENCE_DROP_ALERT", 0.0)?,
+            log_webhook_bodies: parse_or("LOG_WEBHOOK_BODIES", false)?,
+            alert_price_enrichment: parse_or("ALERT_PRICE_ENRICHMENT", false)?,
+            alert_retention: parse_or::<usize>("ALERT_RETENTION", 50)?.max(1),
+            alert_store_file: env::var("ALERT_STORE_FILE").ok().filter(|s| !s.is_empty()),
+            alert_compaction_interval_seconds: parse_or("ALERT_COMPACTION_INTERVAL_SECONDS", 3600)?,
+            alert_store_quarantine: parse_or("ALERT_STORE_QUARANTINE", false)?,
+            signal_conflict_resolution: match env::var("SIGNAL_CONFLICT_RESOLUTION") {
+                Ok(raw) => ConflictResolution::parse(&raw)?,
+                Err(_) => ConflictResolution::PreferInternal,
+            },
+            external_signal_max_age_seconds: parse_or("EXTERNAL_SIGNAL_MAX_AGE_SECONDS", 3600)?,
+            confidence_drop_alert_symbols: match env::var("CONFIDENCE_DROP_ALERT_SYMBOLS") {
+                Ok(raw) => parse_weights("CONFIDENCE_DROP_ALERT_SYMBOLS", &raw)?
+                    .into_iter()
+                    .map(|(k, v)| (k.to_uppercase(), v))
+                    .collect(),
+                Err(_) => HashMap::new(),
+            },
+
+            ai_mode: match env::var("AI_MODE") {
+                Ok(raw) => AiMode::parse(&raw)?,
+                Err(_) => AiMode::Real,
+            },
+            ai_providers: ai_explanation::parse_providers()?,
+            explanation_cache_ttl_seconds: parse_or("EXPLANATION_CACHE_TTL_SECONDS", 300)?,
+            explanation_cache_max_entries: parse_or::<usize>("EXPLANATION_CACHE_MAX_ENTRIES", 1000)?.max(1),
+            single_flight_explanations: parse_or("SINGLE_FLIGHT_EXPLANATIONS", true)?,
+            openai_max_concurrency: parse_or::<usize>("OPENAI_MAX_CONCURRENCY", 2)?.max(1),
+            openai_daily_budget: parse_or("OPENAI_DAILY_BUDGET", 1000)?,
+            openai_auth_cooldown_seconds: parse_or("OPENAI_AUTH_COOLDOWN_SECONDS", 3600)?,
+            ai_latency_budget_ms: parse_or::<u64>("AI_LATENCY_BUDGET_MS", 8000)?.max(1),
+            explanation_prewarm: parse_or("EXPLANATION_PREWARM", false)?,
+            explain_all_min_interval_seconds: parse_or("EXPLAIN_ALL_MIN_INTERVAL_SECONDS", 60)?,
+            explain_batch_max_symbols: parse_or::<usize>("EXPLAIN_BATCH_MAX_SYMBOLS", 10)?.max(1),
+            explanation_prewarm_concurrency: parse_or::<usize>("EXPLANATION_PREWARM_CONCURRENCY", 1)?.max(1),
+        };
+
+        let settings = config.indicator_settings();
+        if settings.divergence_lookback > 0 {
+            if settings.divergence_lookback < 4 {
+                return Err("DIVERGENCE_LOOKBACK must be 0 (off) or at least 4".to_string());
+            }
+            if settings.divergence_required_samples() > config.history_capacity {
+                return Err(format!(
+                    "DIVERGENCE_LOOKBACK {} needs {} samples but HISTORY_CAPACITY is {}",
+                    settings.divergence_lookback,
+                    settings.divergence_required_samples(),
+                    config.history_capacity
+                ));
+            }
+        }
+
+        if !config.signal_half_life_samples.is_finite() || config.signal_half_life_samples < 0.0 {
+            return Err("SIGNAL_HALF_LIFE_SAMPLES must be 0 (equal weights) or more".to_string());
+        }
+
+        if !(0.0..=1.0).contains(&config.low_liquidity_confidence_factor) {
+            return Err(format!(
+                "LOW_LIQUIDITY_CONFIDENCE_FACTOR must be between 0 and 1, got {}",
+                config.low_liquidity_confidence_factor
+            ));
+        }
+
+        if let Some(url) = &config.pushgateway_url {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                _ => return Err(format!("Invalid PUSHGATEWAY_URL: {} (expected an http or https URL)", url)),
+            }
+            for (name, value) in [("PUSHGATEWAY_JOB", &config.pushgateway_job), ("PUSHGATEWAY_INSTANCE", &config.pushgateway_instance)] {
+                if value.contains('/') {
+                    return Err(format!("Invalid {}: {} (label values can't contain '/')", name, value));
+                }
+            }
+        }
+
+        // Validated against the coins and indicator settings above
+        if let Ok(raw) = env::var("WATCH_RULES") {
+            config.watch_rules = watch_rules::parse_watch_rules(&raw, &config)?;
+        }
+
+        Ok(config)
+    }
+}
+
+impl Config {
+    // Coins currently tracked: SUPPORTED_COINS, or the latest TRACK_TOP_N selection
+    pub fn coins(&self) -> Vec<Coin> {
+        coins::tracked().unwrap_or_else(|| self.supported_coins.clone())
+    }
+
+    // CONFIDENCE_DROP_ALERT_SYMBOLS entry for the symbol, else CONFIDENCE_DROP_ALERT; 0 is off
+    pub fn confidence_drop_threshold(&self, symbol: &str) -> f64 {
+        self.confidence_drop_alert_symbols
+            .get(&symbol.to_uppercase())
+            .copied()
+            .unwrap_or(self.confidence_drop_alert)
+    }
+
+    // Most coins tracked at once; TRACK_TOP_N can go over N by the hysteresis band
+    pub fn max_tracked_coins(&self) -> usize {
+        if self.track_top_n > 0 {
+            (self.track_top_n + self.track_top_n_hysteresis).min(self.max_supported_coins)
+        } else {
+            self.supported_coins.len()
+        }
+    }
+
+    pub fn supported_symbols(&self) -> Vec<String> {
+        self.coins().into_iter().map(|c| c.symbol).collect()
+    }
+
+    pub fn coingecko_id(&self, symbol: &str) -> Option<String> {
+        let symbol = symbol.to_uppercase();
+        self.coins()
+            .into_iter()
+            .find(|c| c.symbol == symbol)
+            .map(|c| c.coingecko_id)
+    }
+
+    // COINGECKO_ENDPOINT, or coins/markets for a coin in COINGECKO_MARKETS_SYMBOLS
+    pub fn coingecko_endpoint_for(&self, symbol: &str) -> CoinGeckoEndpoint {
+        if self.coingecko_markets_symbols.contains(&symbol.to_uppercase()) {
+            CoinGeckoEndpoint::Markets
+        } else {
+            self.coingecko_endpoint
+        }
+    }
+
+    pub fn is_supported(&self, symbol: &str) -> bool {
+        self.coingecko_id(symbol).is_some()
+    }
+
+    pub fn is_stablecoin(&self, symbol: &str) -> bool {
+        self.stablecoins.contains(&symbol.to_uppercase())
+    }
+
+    pub fn indicator_settings(&self) -> IndicatorSettings {
+        IndicatorSettings {
+            rsi_period: self.rsi_period,
+            rsi_overbought: self.rsi_overbought,
+            rsi_oversold: self.rsi_oversold,
+            ema_short_period: self.ema_short_period,
+            ema_long_period: self.ema_long_period,
+            macd_fast: self.macd_fast,
+            macd_slow: self.macd_slow,
+            macd_signal: self.macd_signal,
+            non_finite: self.non_finite_numbers,
+            divergence_lookback: self.divergence_lookback,
+            divergence_min_rsi_gap: self.divergence_min_rsi_gap,
+        }
+    }
+
+    pub fn startup_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if self.refresh_interval_seconds != self.configured_refresh_interval_seconds {
+            warnings.push(format!(
+                "REFRESH_INTERVAL_SECONDS raised from {}s to {}s to stay within the CoinGecko budget (REFRESH_INTERVAL_POLICY=clamp)",
+                self.configured_refresh_interval_seconds, self.refresh_interval_seconds
+            ));
+        } else if let Some(warning) = coins::budget_warning(
+            self.max_tracked_coins(),
+            self.refresh_interval_seconds,
+            self.coingecko_calls_per_minute,
+        ) {
+            warnings.push(warning);
+        }
+
+        if self.admin_token.is_none() {
+            warnings.push("ADMIN_TOKEN is not set - admin endpoints are unprotected".to_string());
+        }
+        if self.admin_token_previous.is_some() {
+            warnings.push(
+                "ADMIN_TOKEN_PREVIOUS is set - unset it once every client uses the new ADMIN_TOKEN"
+                    .to_string(),
+            );
+        }
+        if self.low_liquidity_windows.is_empty() && !self.low_liquidity_symbols.is_empty() {
+            warnings.push(
+                "LOW_LIQUIDITY_SYMBOLS is set but LOW_LIQUIDITY_WINDOWS is empty - no low-liquidity periods apply"
+                    .to_string(),
+            );
+        }
+        if self.multi_timeframe_changes && self.coingecko_endpoint != CoinGeckoEndpoint::Markets {
+            warnings.push(if self.coingecko_markets_symbols.is_empty() {
+                "MULTI_TIMEFRAME_CHANGES needs COINGECKO_ENDPOINT=markets - change_7d and change_30d are left out"
+                    .to_string()
+            } else {
+                format!(
+                    "MULTI_TIMEFRAME_CHANGES needs COINGECKO_ENDPOINT=markets - change_7d and change_30d are only given for {}",
+                    self.coingecko_markets_symbols.join(", ")
+                )
+            });
+        }
+        let untracked: Vec<&str> = self
+            .coingecko_markets_symbols
+            .iter()
+            .filter(|symbol| self.coingecko_id(symbol).is_none())
+            .map(String::as_str)
+            .collect();
+        if !untracked.is_empty() {
+            warnings.push(format!(
+                "COINGECKO_MARKETS_SYMBOLS lists coins that aren't tracked: {}",
+                untracked.join(", ")
+            ));
+        }
+        if self.log_webhook_bodies {
+            warnings.push(
+                "LOG_WEBHOOK_BODIES is on - webhook bodies are printed to the log; turn it off outside development"
+                    .to_string(),
+            );
+        }
+
+        warnings
+    }
+}
+
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get().expect("Config not initialised")
+}
+
+fn parse_or<T>(name: &str, default: T) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match env::var(name) {
+        Ok(raw) => raw
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid {}: {}", name, e)),
+        Err(_) => Ok(default),
     }
 }
+
+// ENABLED_ENDPOINTS keeps only the listed endpoints; DISABLED_ENDPOINTS turns the listed
+// ones off. Entries are paths as written in routes::ROUTES, e.g. /prices or /alerts/{symbol}.
+fn parse_disabled_endpoints() -> Result<Vec<String>, String> {
+    let parse_list = |name: &str| -> Result<Option<Vec<String>>, String> {
+        let Ok(raw) = env::var(name) else {
+            return Ok(None);
+        };
+        let mut paths = Vec::new();
+        for entry in raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let path = if entry.starts_with('/') {
+                entry.to_string()
+            } else {
+                format!("/{}", entry)
+            };
+            if !routes::ROUTES.iter().any(|(_, pattern)| *pattern == path) {
+                return Err(format!("Unknown endpoint '{}' in {}", entry, name));
+            }
+            paths.push(path);
+        }
+        Ok(Some(paths))
+    };
+
+    let enabled = parse_list("ENABLED_ENDPOINTS")?;
+    let disabled = parse_list("DISABLED_ENDPOINTS")?.unwrap_or_default();
+
+    let mut off: Vec<String> = Vec::new();
+    for (_, pattern) in routes::ROUTES {
+        let listed = enabled.as_ref().is_none_or(|enabled| enabled.iter().any(|p| p == pattern));
+        let pattern = pattern.to_string();
+        if (!listed || disabled.contains(&pattern)) && !off.contains(&pattern) {
+            off.push(pattern);
+        }
+    }
+
+    if off.iter().any(|p| p == routes::HEALTH_ENDPOINT) {
+        return Err(format!("{} can't be disabled", routes::HEALTH_ENDPOINT));
+    }
+    Ok(off)
+}
+
+// ALERT_ACTION_MAP entries are PHRASE=signal, merged over the defaults
+fn parse_action_map() -> Result<ActionMap, String> {
+    let mut map = ActionMap::default();
+    let Ok(raw) = env::var("ALERT_ACTION_MAP") else {
+        return Ok(map);
+    };
+    for entry in raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let (phrase, signal) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid ALERT_ACTION_MAP entry '{}': expected PHRASE=signal", entry))?;
+        map.set(phrase, signal)?;
+    }
+    Ok(map)
+}
+
+fn parse_severity_rules() -> Result<SeverityRules, String> {
+    let mut rules = SeverityRules::default();
+
+    if let Ok(raw) = env::var("ALERT_ACTION_WEIGHTS") {
+        rules.action_weights = parse_weights("ALERT_ACTION_WEIGHTS", &raw)?
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect();
+    }
+    if let Ok(raw) = env::var("ALERT_SYMBOL_WEIGHTS") {
+        rules.symbol_weights = parse_weights("ALERT_SYMBOL_WEIGHTS", &raw)?
+            .into_iter()
+            .map(|(k, v)| (k.to_uppercase(), v))
+            .collect();
+    }
+    if let Ok(raw) = env::var("ALERT_SEVERITY_WARNING") {
+        rules.warning_threshold = raw
+            .parse()
+            .map_err(|e| format!("Invalid ALERT_SEVERITY_WARNING: {}", e))?;
+    }
+    if let Ok(raw) = env::var("ALERT_SEVERITY_CRITICAL") {
+        rules.critical_threshold = raw
+            .parse()
+            .map_err(|e| format!("Invalid ALERT_SEVERITY_CRITICAL: {}", e))?;
+    }
+
+    if rules.warning_threshold > rules.critical_threshold {
+        return Err(format!(
+            "ALERT_SEVERITY_WARNING ({}) must not exceed ALERT_SEVERITY_CRITICAL ({})",
+            rules.warning_threshold, rules.critical_threshold
+        ));
+    }
+
+    Ok(rules)
+}
+
+// METRICS_HISTOGRAM_BUCKETS_MS: upper bounds in milliseconds, e.g. "100,250,1000".
+fn parse_histogram_buckets() -> Result<Vec<f64>, String> {
+    let Ok(raw) = env::var("METRICS_HISTOGRAM_BUCKETS_MS") else {
+        return Ok(vec![5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0]);
+    };
+    let mut buckets: Vec<f64> = Vec::new();
+    for entry in raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let bound: f64 = entry
+            .parse()
+            .map_err(|e| format!("Invalid METRICS_HISTOGRAM_BUCKETS_MS entry '{}': {}", entry, e))?;
+        if bound <= 0.0 || !bound.is_finite() {
+            return Err(format!("METRICS_HISTOGRAM_BUCKETS_MS entry '{}' must be positive", entry));
+        }
+        if buckets.last().is_some_and(|last| bound <= *last) {
+            return Err(format!(
+                "METRICS_HISTOGRAM_BUCKETS_MS must be in increasing order without repeats ('{}' after '{}')",
+                entry,
+                buckets.last().unwrap()
+            ));
+        }
+        buckets.push(bound);
+    }
+    if buckets.is_empty() {
+        return Err("METRICS_HISTOGRAM_BUCKETS_MS needs at least one bucket".to_string());
+    }
+    Ok(buckets)
+}
+
+// DATA_QUALITY_WEIGHTS overrides the listed factors, e.g. "staleness=50,warmup=0".
+fn parse_quality_weights() -> Result<QualityWeights, String> {
+    let mut weights = QualityWeights::default();
+    if let Ok(raw) = env::var("DATA_QUALITY_WEIGHTS") {
+        for (factor, weight) in parse_weights("DATA_QUALITY_WEIGHTS", &raw)? {
+            if weight < 0.0 || !weight.is_finite() {
+                return Err(format!("DATA_QUALITY_WEIGHTS weight for '{}' must be 0 or more", factor));
+            }
+            weights.set(&factor, weight)?;
+        }
+    }
+    if weights.total() <= 0.0 {
+        return Err("DATA_QUALITY_WEIGHTS needs at least one weight above 0".to_string());
+    }
+    Ok(weights)
+}
+
+fn parse_strength_weights() -> Result<HashMap<String, f64>, String> {
+    let mut weights = strategy::default_strength_weights();
+    if let Ok(raw) = env::var("SIGNAL_STRENGTH_WEIGHTS") {
+        for (factor, weight) in parse_weights("SIGNAL_STRENGTH_WEIGHTS", &raw)? {
+            let factor = factor.to_lowercase();
+            if !strategy::STRENGTH_FACTORS.contains(&factor.as_str()) {
+                return Err(format!(
+                    "Unknown SIGNAL_STRENGTH_WEIGHTS factor: {}. Use {}.",
+                    factor,
+                    strategy::STRENGTH_FACTORS.join(", ")
+                ));
+            }
+            if weight < 0.0 || !weight.is_finite() {
+                return Err(format!("SIGNAL_STRENGTH_WEIGHTS weight for '{}' must be 0 or more", factor));
+            }
+            weights.insert(factor, weight);
+        }
+    }
+    if weights.values().sum::<f64>() <= 0.0 {
+        return Err("SIGNAL_STRENGTH_WEIGHTS needs at least one weight above 0".to_string());
+    }
+    Ok(weights)
+}
+
+// Parses "KEY=1.5,OTHER=2" into a map.
+fn parse_weights(name: &str, raw: &str) -> Result<HashMap<String, f64>, String> {
+    raw.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid {} entry '{}': expected KEY=VALUE", name, pair))?;
+            let weight = value
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("Invalid {} weight for '{}': {}", name, key, e))?;
+            Ok((key.trim().to_string(), weight))
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::coingecko;
+use crate::coins::{self, Coin};
+use crate::config;
+use crate::scheduler;
+
+// TRACK_TOP_N: follows CoinGecko's market cap ranking instead of a fixed coin list.
+// The first ranking is awaited so the refresher and history seeding start on the
+// right coins; if it fails, the default coins are tracked until the next attempt.
+pub async fn start() {
+    let config = config::get();
+    if config.track_top_n == 0 {
+        return;
+    }
+    if let Err(e) = run().await {
+        println!("⚠️ TRACK_TOP_N: first ranking failed, starting with the default coins: {}", e);
+    }
+
+    let interval = Duration::from_secs(config.track_top_n_interval_seconds);
+    // The scheduler runs a task straight away; that run was just done above
+    let skip_first = Arc::new(AtomicBool::new(true));
+    scheduler::register("top_n_discovery", interval, move || {
+        let skip = skip_first.swap(false, Ordering::Relaxed);
+        async move {
+            if skip {
+                return Ok(());
+            }
+            run().await
+        }
+    });
+}
+
+async fn run() -> Result<(), String> {
+    let config = config::get();
+    let n = config.track_top_n;
+    let band = config.track_top_n_hysteresis;
+
+    let mut ranking = coingecko::fetch_top_coins(n + band).await?;
+    ranking.sort_by_key(|(_, _, rank)| *rank);
+    // Two coins can share a ticker; the better ranked one keeps it
+    let mut seen = HashSet::new();
+    ranking.retain(|(symbol, _, _)| seen.insert(symbol.clone()));
+
+    let previous = coins::tracked();
+    let current: HashSet<String> = previous
+        .iter()
+        .flatten()
+        .map(|coin| coin.symbol.clone())
+        .collect();
+
+    // Newcomers need a top-N rank; coins already tracked stay until they drop
+    // below N + band, so a coin hovering around rank N doesn't flap in and out.
+    // The first ranking has nothing to hold on to and takes the top N as-is.
+    let mut selected: Vec<(Coin, u32)> = ranking
+        .into_iter()
+        .filter(|(symbol, _, rank)| {
+            let rank = *rank as usize;
+            rank <= n || (current.contains(symbol) && rank <= n + band)
+        })
+        .map(|(symbol, coingecko_id, rank)| (Coin { symbol, coingecko_id }, rank))
+        .collect();
+    selected.truncate(config.max_supported_coins);
+
+    if selected.is_empty() {
+        return Err("Markets ranking returned no coins".to_string());
+    }
+
+    let next: HashSet<String> = selected.iter().map(|(coin, _)| coin.symbol.clone()).collect();
+    if previous.is_none() {
+        let listed: Vec<String> = selected
+            .iter()
+            .map(|(coin, rank)| format!("{} (#{})", coin.symbol, rank))
+            .collect();
+        println!("🏆 TRACK_TOP_N: tracking {}", listed.join(", "));
+    } else if next != current {
+        let added: Vec<String> = selected
+            .iter()
+            .filter(|(coin, _)| !current.contains(&coin.symbol))
+            .map(|(coin, rank)| format!("{} (#{})", coin.symbol, rank))
+            .collect();
+        let mut removed: Vec<&String> = current.iter().filter(|s| !next.contains(*s)).collect();
+        removed.sort();
+        println!(
+            "🔁 TRACK_TOP_N: added [{}], removed [{}]",
+            added.join(", "),
+            removed.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    coins::set_tracked(selected.into_iter().map(|(coin, _)| coin).collect());
+    Ok(())
+}
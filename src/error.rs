@@ -0,0 +1,64 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, HttpResponseBuilder, ResponseError};
+use serde_json::json;
+use std::fmt;
+
+// Standard error envelope: {"status": "error", "code": "...", "message": "..."}.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_allowed(method: &str, path: &str, allowed: &[&str]) -> Self {
+        ApiError::new(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed",
+            format!(
+                "{} is not supported on {}. Allowed: {}",
+                method,
+                path,
+                allowed.join(", ")
+            ),
+        )
+    }
+
+    // Builder with the status set, for responses that need extra headers.
+    pub fn response_builder(&self) -> HttpResponseBuilder {
+        HttpResponse::build(self.status)
+    }
+
+    pub fn body(&self) -> serde_json::Value {
+        json!({
+            "status": "error",
+            "code": self.code,
+            "message": self.message,
+        })
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.response_builder().json(self.body())
+    }
+}
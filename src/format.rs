@@ -0,0 +1,216 @@
+use actix_web::{HttpResponse, HttpResponseBuilder};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use serde_json::ser::{CompactFormatter, Formatter};
+use std::io;
+
+use crate::config;
+
+// number: serde_json's default, which prints tiny values like 1.2e-7 in scientific notation.
+// fixed: JSON numbers always in plain decimal (0.00000012).
+// string: plain decimal strings ("0.00000012") for clients that parse numbers lossily.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    Number,
+    Fixed,
+    String,
+}
+
+impl NumberFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "number" => Ok(NumberFormat::Number),
+            "fixed" => Ok(NumberFormat::Fixed),
+            "string" => Ok(NumberFormat::String),
+            other => Err(format!(
+                "Invalid NUMBER_FORMAT: {}. Use number, fixed or string.",
+                other
+            )),
+        }
+    }
+}
+
+struct PlainDecimalFormatter {
+    quoted: bool,
+}
+
+impl PlainDecimalFormatter {
+    // f64's Display never uses an exponent and round-trips exactly
+    fn write_plain<W: ?Sized + io::Write>(&self, writer: &mut W, value: f64) -> io::Result<()> {
+        if self.quoted {
+            write!(writer, "\"{}\"", value)
+        } else {
+            write!(writer, "{}", value)
+        }
+    }
+}
+
+impl Formatter for PlainDecimalFormatter {
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        self.write_plain(writer, value)
+    }
+
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        self.write_plain(writer, value as f64)
+    }
+}
+
+pub fn to_json_string<T: Serialize>(value: &T, format: NumberFormat) -> String {
+    try_to_json_string(value, format).unwrap_or_else(|e| {
+        println!("❌ JSON serialization failed: {}", e);
+        "null".to_string()
+    })
+}
+
+// to_json_string for callers that report a failure themselves
+pub fn try_to_json_string<T: Serialize>(value: &T, format: NumberFormat) -> Result<String, String> {
+    let mut out = Vec::new();
+    let result = match format {
+        NumberFormat::Number => {
+            let mut ser = serde_json::Serializer::with_formatter(&mut out, CompactFormatter);
+            value.serialize(&mut ser)
+        }
+        NumberFormat::Fixed | NumberFormat::String => {
+            let formatter = PlainDecimalFormatter {
+                quoted: format == NumberFormat::String,
+            };
+            let mut ser = serde_json::Serializer::with_formatter(&mut out, formatter);
+            value.serialize(&mut ser)
+        }
+    };
+    result.map_err(|e| e.to_string())?;
+    String::from_utf8(out).map_err(|e| e.to_string())
+}
+
+// Like HttpResponseBuilder::json, but honours NUMBER_FORMAT.
+pub fn json<T: Serialize>(mut builder: HttpResponseBuilder, value: &T) -> HttpResponse {
+    builder
+        .content_type("application/json")
+        .body(to_json_string(value, config::get().number_format))
+}
+
+// How timestamps are written in responses. Internally they stay epoch seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    // "2024-05-01T12:00:00Z"
+    Rfc3339,
+    // 1714564800000
+    EpochMs,
+}
+
+impl TimestampFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "epoch_ms" => Ok(TimestampFormat::EpochMs),
+            other => Err(format!(
+                "Invalid TIMESTAMP_FORMAT: {}. Use rfc3339 or epoch_ms.",
+                other
+            )),
+        }
+    }
+}
+
+// Epoch seconds rendered per TIMESTAMP_FORMAT.
+pub fn timestamp(seconds: i64) -> Value {
+    match config::get().timestamp_format {
+        TimestampFormat::Rfc3339 => match DateTime::<Utc>::from_timestamp(seconds, 0) {
+            Some(time) => Value::String(time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            None => Value::Null,
+        },
+        TimestampFormat::EpochMs => Value::from(seconds.saturating_mul(1000)),
+    }
+}
+
+pub fn now() -> Value {
+    timestamp(Utc::now().timestamp())
+}
+
+// "12s ago", "5m ago", "3h ago", "2d ago" relative to now, in the largest whole unit.
+// A timestamp in the future (clock skew) reads "just now".
+pub fn age_human(seconds: i64) -> String {
+    let age = Utc::now().timestamp() - seconds;
+    match age {
+        ..=0 => "just now".to_string(),
+        1..=59 => format!("{}s ago", age),
+        60..=3599 => format!("{}m ago", age / 60),
+        3600..=86_399 => format!("{}h ago", age / 3600),
+        _ => format!("{}d ago", age / 86_400),
+    }
+}
+
+// For #[serde(serialize_with)] on epoch-second fields.
+pub fn serialize_timestamp<S: Serializer>(seconds: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    timestamp(*seconds).serialize(serializer)
+}
+
+pub fn serialize_optional_timestamp<S: Serializer>(
+    seconds: &Option<i64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    seconds.map(timestamp).serialize(serializer)
+}
+
+// Casing of symbols in responses. Symbols are accepted in any casing and stored upper-case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolCase {
+    Upper,
+    Lower,
+}
+
+impl SymbolCase {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "upper" => Ok(SymbolCase::Upper),
+            "lower" => Ok(SymbolCase::Lower),
+            other => Err(format!("Invalid SYMBOL_CASE: {}. Use upper or lower.", other)),
+        }
+    }
+}
+
+pub fn symbol(symbol: &str) -> String {
+    match config::get().symbol_case {
+        SymbolCase::Upper => symbol.to_uppercase(),
+        SymbolCase::Lower => symbol.to_lowercase(),
+    }
+}
+
+pub fn symbols(symbols: &[String]) -> Vec<String> {
+    symbols.iter().map(|s| symbol(s)).collect()
+}
+
+// For #[serde(serialize_with)] on symbol fields.
+pub fn serialize_symbol<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&symbol(value))
+}
+
+pub fn serialize_symbols<S: Serializer>(values: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    symbols(values).serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sub_cent_prices_are_written_without_an_exponent() {
+        let value = json!({"price": 0.00000123});
+        assert_eq!(to_json_string(&value, NumberFormat::Number), r#"{"price":1.23e-6}"#);
+        assert_eq!(to_json_string(&value, NumberFormat::Fixed), r#"{"price":0.00000123}"#);
+        assert_eq!(to_json_string(&value, NumberFormat::String), r#"{"price":"0.00000123"}"#);
+    }
+
+    #[test]
+    fn plain_decimals_round_trip_exactly() {
+        for price in [0.00000123, 1.2e-10, 0.1 + 0.2, 67_123.45, 3e20] {
+            let json = to_json_string(&json!(price), NumberFormat::Fixed);
+            assert!(!json.contains('e'), "{}", json);
+            assert_eq!(json.parse::<f64>().unwrap(), price);
+        }
+    }
+}
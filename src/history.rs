@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config;
+use crate::format;
+
+static HISTORY: OnceLock<Mutex<HashMap<String, VecDeque<Sample>>>> = OnceLock::new();
+// Symbols whose buffer was backfilled from CoinGecko's market_chart
+static SEEDED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Sample {
+    #[serde(serialize_with = "format::serialize_timestamp")]
+    pub timestamp: i64,
+    pub price: f64,
+}
+
+// SIGNALS_WARMUP: what /signals does for a coin still short of MIN_HISTORY_SAMPLES
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupMode {
+    // Withhold the signal (`insufficient_data`) until the gate is passed
+    Strict,
+    // Signal from whatever has warmed up, flagged `partial` with the indicators still pending
+    BestEffort,
+}
+
+impl WarmupMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "strict" => Ok(WarmupMode::Strict),
+            "best_effort" => Ok(WarmupMode::BestEffort),
+            other => Err(format!("Invalid SIGNALS_WARMUP: {}. Use strict or best_effort.", other)),
+        }
+    }
+}
+
+fn history() -> &'static Mutex<HashMap<String, VecDeque<Sample>>> {
+    HISTORY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// HISTORY_MIN_CHANGE_PERCENT / HISTORY_MIN_CHANGE_ABSOLUTE: a price within every
+// configured delta of the last stored sample isn't worth storing.
+fn barely_moved(last: f64, price: f64) -> bool {
+    let config = config::get();
+    let percent = config.history_min_change_percent;
+    let absolute = config.history_min_change_absolute;
+    if percent <= 0.0 && absolute <= 0.0 {
+        return false;
+    }
+    let delta = (price - last).abs();
+    let within_percent = percent <= 0.0 || (last != 0.0 && delta / last.abs() * 100.0 <= percent);
+    let within_absolute = absolute <= 0.0 || delta <= absolute;
+    within_percent && within_absolute
+}
+
+// Appends a price sample, dropping the oldest once the buffer is full. Skipped when
+// the price barely moved since the last stored sample.
+pub fn record(symbol: &str, timestamp: i64, price: f64) {
+    let capacity = config::get().history_capacity;
+    let mut history = history().lock().unwrap();
+    let samples = history.entry(symbol.to_uppercase()).or_default();
+    if samples.back().is_some_and(|last| barely_moved(last.price, price)) {
+        return;
+    }
+
+    samples.push_back(Sample { timestamp, price });
+    while samples.len() > capacity {
+        samples.pop_front();
+    }
+}
+
+// Oldest first
+pub fn prices(symbol: &str) -> Vec<f64> {
+    history()
+        .lock()
+        .unwrap()
+        .get(&symbol.to_uppercase())
+        .map(|samples| samples.iter().map(|s| s.price).collect())
+        .unwrap_or_default()
+}
+
+// Oldest first, with timestamps
+pub fn samples(symbol: &str) -> Vec<Sample> {
+    history()
+        .lock()
+        .unwrap()
+        .get(&symbol.to_uppercase())
+        .map(|samples| samples.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+pub fn len(symbol: &str) -> usize {
+    history()
+        .lock()
+        .unwrap()
+        .get(&symbol.to_uppercase())
+        .map(|samples| samples.len())
+        .unwrap_or(0)
+}
+
+// Symbols with a history buffer, tracked or not
+pub fn symbols() -> Vec<String> {
+    history().lock().unwrap().keys().cloned().collect()
+}
+
+// Drops the symbol's buffer; returns how many samples it held
+pub fn forget(symbol: &str) -> usize {
+    let symbol = symbol.to_uppercase();
+    if let Some(seeded) = SEEDED.get() {
+        seeded.lock().unwrap().remove(&symbol);
+    }
+    history()
+        .lock()
+        .unwrap()
+        .remove(&symbol)
+        .map(|samples| samples.len())
+        .unwrap_or(0)
+}
+
+// Replaces the buffer with backfilled samples (oldest first), keeping any samples
+// recorded since that are newer than the backfill.
+pub fn seed(symbol: &str, seeded: Vec<Sample>) {
+    let symbol = symbol.to_uppercase();
+    let capacity = config::get().history_capacity;
+    let mut history = history().lock().unwrap();
+    let existing = history.remove(&symbol).unwrap_or_default();
+
+    let last_seeded = seeded.last().map(|s| s.timestamp).unwrap_or(i64::MIN);
+    let mut samples: VecDeque<Sample> = seeded.into_iter().collect();
+    samples.extend(existing.into_iter().filter(|s| s.timestamp > last_seeded));
+    while samples.len() > capacity {
+        samples.pop_front();
+    }
+    history.insert(symbol.clone(), samples);
+
+    SEEDED
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .unwrap()
+        .insert(symbol);
+}
+
+pub fn is_seeded(symbol: &str) -> bool {
+    SEEDED
+        .get()
+        .is_some_and(|seeded| seeded.lock().unwrap().contains(&symbol.to_uppercase()))
+}
+
+// MIN_HISTORY_SAMPLES gate: enough samples recorded, or backfilled at startup.
+pub fn is_ready(symbol: &str) -> bool {
+    let required = config::get().min_history_samples;
+    required == 0 || is_seeded(symbol) || len(symbol) >= required
+}
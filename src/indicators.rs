@@ -0,0 +1,533 @@
+use serde::{Deserialize, Serialize};
+
+// NON_FINITE_NUMBERS: what becomes of an indicator value that came out NaN or Infinity.
+// JSON has no way to write either, so left alone they would turn into an unexplained null.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NonFinite {
+    // Dropped, as if there were not enough history for it
+    Null,
+    // Infinity pinned to the indicator's range (0-100 for RSI); NaN has no side, so it is dropped
+    Clamp,
+}
+
+impl NonFinite {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "null" => Ok(NonFinite::Null),
+            "clamp" => Ok(NonFinite::Clamp),
+            other => Err(format!(
+                "Invalid NON_FINITE_NUMBERS: {}. Use null or clamp.",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndicatorSettings {
+    pub rsi_period: usize,
+    pub rsi_overbought: f64,
+    pub rsi_oversold: f64,
+    pub ema_short_period: usize,
+    pub ema_long_period: usize,
+    pub macd_fast: usize,
+    pub macd_slow: usize,
+    pub macd_signal: usize,
+    pub non_finite: NonFinite,
+    // Prices compared for RSI divergence; 0 turns detection off
+    pub divergence_lookback: usize,
+    // How far RSI must lag the new price extreme, in RSI points
+    pub divergence_min_rsi_gap: f64,
+}
+
+impl IndicatorSettings {
+    // Samples needed before divergence can be checked: an RSI value for every price in the window
+    pub fn divergence_required_samples(&self) -> usize {
+        self.divergence_lookback + self.rsi_period
+    }
+}
+
+// Price and RSI disagreeing at the recent extreme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Divergence {
+    // Lower price low, higher RSI low: selling is losing momentum
+    BullishDivergence,
+    // Higher price high, lower RSI high: buying is losing momentum
+    BearishDivergence,
+}
+
+// Latest and previous value of each indicator, so crossings can be detected.
+// A field is None until there is enough history to compute it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndicatorSnapshot {
+    pub rsi: Option<f64>,
+    pub prev_rsi: Option<f64>,
+    pub ema_short: Option<f64>,
+    pub ema_long: Option<f64>,
+    pub prev_ema_short: Option<f64>,
+    pub prev_ema_long: Option<f64>,
+    pub macd_histogram: Option<f64>,
+    pub prev_macd_histogram: Option<f64>,
+    // Fields whose computed value was NaN or Infinity and was replaced per NON_FINITE_NUMBERS
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub non_finite: Vec<&'static str>,
+    pub divergence: Option<Divergence>,
+}
+
+// Wilder-smoothed RSI. The first value needs period + 1 prices.
+pub fn rsi(prices: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || prices.len() <= period {
+        return vec![];
+    }
+
+    let changes: Vec<f64> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+    let gains: Vec<f64> = changes.iter().map(|c| c.max(0.0)).collect();
+    let losses: Vec<f64> = changes.iter().map(|c| (-c).max(0.0)).collect();
+
+    let rsi_from = |avg_gain: f64, avg_loss: f64| {
+        if avg_loss == 0.0 {
+            100.0
+        } else {
+            100.0 - (100.0 / (1.0 + avg_gain / avg_loss))
+        }
+    };
+
+    let mut avg_gain = gains[..period].iter().sum::<f64>() / period as f64;
+    let mut avg_loss = losses[..period].iter().sum::<f64>() / period as f64;
+    let mut values = vec![rsi_from(avg_gain, avg_loss)];
+
+    for i in period..gains.len() {
+        avg_gain = (avg_gain * (period - 1) as f64 + gains[i]) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + losses[i]) / period as f64;
+        values.push(rsi_from(avg_gain, avg_loss));
+    }
+
+    values
+}
+
+// EMA seeded with the SMA of the first `period` prices.
+pub fn ema(prices: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || prices.len() < period {
+        return vec![];
+    }
+
+    let k = 2.0 / (period as f64 + 1.0);
+    let mut current = prices[..period].iter().sum::<f64>() / period as f64;
+    let mut values = vec![current];
+
+    for price in &prices[period..] {
+        current = price * k + current * (1.0 - k);
+        values.push(current);
+    }
+
+    values
+}
+
+// MACD histogram (MACD line minus its signal line), aligned to the latest price.
+pub fn macd_histogram(prices: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<f64> {
+    let fast_ema = ema(prices, fast);
+    let slow_ema = ema(prices, slow);
+    if slow_ema.is_empty() || fast_ema.len() < slow_ema.len() {
+        return vec![];
+    }
+
+    let offset = fast_ema.len() - slow_ema.len();
+    let macd_line: Vec<f64> = slow_ema
+        .iter()
+        .enumerate()
+        .map(|(i, slow)| fast_ema[i + offset] - slow)
+        .collect();
+    let signal_line = ema(&macd_line, signal);
+    if signal_line.is_empty() {
+        return vec![];
+    }
+
+    let offset = macd_line.len() - signal_line.len();
+    signal_line
+        .iter()
+        .enumerate()
+        .map(|(i, signal)| macd_line[i + offset] - signal)
+        .collect()
+}
+
+// Splits the last `lookback` prices into an earlier and a recent half and compares the
+// extremes of each. Bearish when the recent half's high beats the earlier high while RSI
+// at that high is at least `min_gap` points below RSI at the earlier one; bullish is the
+// same at the lows. None without an RSI value for every price in the window.
+pub fn divergence(prices: &[f64], rsi_period: usize, lookback: usize, min_gap: f64) -> Option<Divergence> {
+    if lookback < 4 {
+        return None;
+    }
+    let rsi = rsi(prices, rsi_period);
+    if rsi.len() < lookback {
+        return None;
+    }
+    // rsi[i] belongs to prices[i + rsi_period]
+    let prices = &prices[prices.len() - lookback..];
+    let rsi = &rsi[rsi.len() - lookback..];
+    let half = lookback / 2;
+
+    let extreme = |range: std::ops::Range<usize>, highest: bool| {
+        range
+            .max_by(|&a, &b| {
+                let order = prices[a].total_cmp(&prices[b]);
+                if highest { order } else { order.reverse() }
+            })
+            .unwrap()
+    };
+
+    let (earlier_high, recent_high) = (extreme(0..half, true), extreme(half..lookback, true));
+    if prices[recent_high] > prices[earlier_high] && rsi[recent_high] + min_gap <= rsi[earlier_high] {
+        return Some(Divergence::BearishDivergence);
+    }
+    let (earlier_low, recent_low) = (extreme(0..half, false), extreme(half..lookback, false));
+    if prices[recent_low] < prices[earlier_low] && rsi[recent_low] >= rsi[earlier_low] + min_gap {
+        return Some(Divergence::BullishDivergence);
+    }
+    None
+}
+
+fn last_two(values: &[f64]) -> (Option<f64>, Option<f64>) {
+    let n = values.len();
+    (
+        values.last().copied(),
+        n.checked_sub(2).map(|i| values[i]),
+    )
+}
+
+// Passes finite values through; anything else is replaced per `mode` and noted in `flagged`
+fn sanitize(
+    value: Option<f64>,
+    field: &'static str,
+    (min, max): (f64, f64),
+    mode: NonFinite,
+    flagged: &mut Vec<&'static str>,
+) -> Option<f64> {
+    let value = value?;
+    if value.is_finite() {
+        return Some(value);
+    }
+    flagged.push(field);
+    match mode {
+        NonFinite::Clamp if !value.is_nan() => Some(value.clamp(min, max)),
+        _ => None,
+    }
+}
+
+pub fn compute(prices: &[f64], settings: &IndicatorSettings) -> IndicatorSnapshot {
+    let (rsi, prev_rsi) = last_two(&rsi(prices, settings.rsi_period));
+    let (ema_short, prev_ema_short) = last_two(&ema(prices, settings.ema_short_period));
+    let (ema_long, prev_ema_long) = last_two(&ema(prices, settings.ema_long_period));
+    let (macd_histogram, prev_macd_histogram) = last_two(&macd_histogram(
+        prices,
+        settings.macd_fast,
+        settings.macd_slow,
+        settings.macd_signal,
+    ));
+
+    let mode = settings.non_finite;
+    let mut flagged = Vec::new();
+    let rsi_range = (0.0, 100.0);
+    let unbounded = (f64::MIN, f64::MAX);
+    IndicatorSnapshot {
+        rsi: sanitize(rsi, "rsi", rsi_range, mode, &mut flagged),
+        prev_rsi: sanitize(prev_rsi, "prev_rsi", rsi_range, mode, &mut flagged),
+        ema_short: sanitize(ema_short, "ema_short", unbounded, mode, &mut flagged),
+        ema_long: sanitize(ema_long, "ema_long", unbounded, mode, &mut flagged),
+        prev_ema_short: sanitize(prev_ema_short, "prev_ema_short", unbounded, mode, &mut flagged),
+        prev_ema_long: sanitize(prev_ema_long, "prev_ema_long", unbounded, mode, &mut flagged),
+        macd_histogram: sanitize(macd_histogram, "macd_histogram", unbounded, mode, &mut flagged),
+        prev_macd_histogram: sanitize(prev_macd_histogram, "prev_macd_histogram", unbounded, mode, &mut flagged),
+        non_finite: flagged,
+        divergence: match settings.divergence_lookback {
+            0 => None,
+            lookback => divergence(prices, settings.rsi_period, lookback, settings.divergence_min_rsi_gap),
+        },
+    }
+}
+
+impl IndicatorSnapshot {
+    // Indicators without enough history for a value yet, given `samples` prices. A value
+    // dropped as non-finite is reported under `non_finite` instead.
+    pub fn pending(&self, settings: &IndicatorSettings, samples: usize) -> Vec<&'static str> {
+        let mut pending: Vec<&'static str> = [
+            ("rsi", self.rsi),
+            ("ema_short", self.ema_short),
+            ("ema_long", self.ema_long),
+            ("macd_histogram", self.macd_histogram),
+        ]
+        .into_iter()
+        .filter(|(name, value)| value.is_none() && !self.non_finite.contains(name))
+        .map(|(name, _)| name)
+        .collect();
+        if settings.divergence_lookback > 0 && samples < settings.divergence_required_samples() {
+            pending.push("divergence");
+        }
+        pending
+    }
+}
+
+// Human-readable facts about thresholds crossed (or currently breached), most
+// significant first.
+pub fn describe(snapshot: &IndicatorSnapshot, settings: &IndicatorSettings) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    if let Some(rsi) = snapshot.rsi {
+        let prev = snapshot.prev_rsi.unwrap_or(rsi);
+        let (oversold, overbought) = (settings.rsi_oversold, settings.rsi_overbought);
+        let reason = if prev <= oversold && rsi > oversold {
+            format!("RSI crossed above {:.0} from oversold ({:.1})", oversold, rsi)
+        } else if prev >= overbought && rsi < overbought {
+            format!("RSI crossed below {:.0} from overbought ({:.1})", overbought, rsi)
+        } else if rsi <= oversold {
+            format!("RSI is oversold at {:.1} (below {:.0})", rsi, oversold)
+        } else if rsi >= overbought {
+            format!("RSI is overbought at {:.1} (above {:.0})", rsi, overbought)
+        } else {
+            format!("RSI is neutral at {:.1}", rsi)
+        };
+        reasons.push(reason);
+    }
+
+    match snapshot.divergence {
+        Some(Divergence::BearishDivergence) => reasons.push(format!(
+            "Bearish RSI divergence: a new price high over the last {} samples without a higher RSI",
+            settings.divergence_lookback
+        )),
+        Some(Divergence::BullishDivergence) => reasons.push(format!(
+            "Bullish RSI divergence: a new price low over the last {} samples without a lower RSI",
+            settings.divergence_lookback
+        )),
+        None => {}
+    }
+
+    if let (Some(hist), Some(prev)) = (snapshot.macd_histogram, snapshot.prev_macd_histogram) {
+        if prev <= 0.0 && hist > 0.0 {
+            reasons.push("MACD histogram turned positive".to_string());
+        } else if prev >= 0.0 && hist < 0.0 {
+            reasons.push("MACD histogram turned negative".to_string());
+        }
+    }
+
+    if let (Some(short), Some(long), Some(prev_short), Some(prev_long)) = (
+        snapshot.ema_short,
+        snapshot.ema_long,
+        snapshot.prev_ema_short,
+        snapshot.prev_ema_long,
+    ) {
+        if prev_short <= prev_long && short > long {
+            reasons.push(format!(
+                "EMA{} crossed above EMA{}",
+                settings.ema_short_period, settings.ema_long_period
+            ));
+        } else if prev_short >= prev_long && short < long {
+            reasons.push(format!(
+                "EMA{} crossed below EMA{}",
+                settings.ema_short_period, settings.ema_long_period
+            ));
+        }
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{to_json_string, NumberFormat};
+    use crate::test_support::{default_settings, Scenario};
+
+    const UPTREND: Scenario = Scenario::Uptrend { step_percent: 1.0 };
+    const DOWNTREND: Scenario = Scenario::Downtrend { step_percent: 1.0 };
+
+    #[test]
+    fn rsi_pins_to_the_extremes_of_a_one_way_trend() {
+        let up = rsi(&UPTREND.series(100.0, 40), 14);
+        assert_eq!(up.len(), 40 - 14);
+        assert!(up.iter().all(|v| *v == 100.0), "{}: {:?}", UPTREND.name(), up);
+
+        let down = rsi(&DOWNTREND.series(100.0, 40), 14);
+        assert!(down.iter().all(|v| *v == 0.0), "{}: {:?}", DOWNTREND.name(), down);
+    }
+
+    #[test]
+    fn rsi_needs_period_plus_one_prices() {
+        assert!(rsi(&UPTREND.series(100.0, 14), 14).is_empty());
+        assert_eq!(rsi(&UPTREND.series(100.0, 15), 14).len(), 1);
+        assert!(rsi(&UPTREND.series(100.0, 15), 0).is_empty());
+    }
+
+    #[test]
+    fn rsi_of_a_range_stays_between_the_thresholds() {
+        let scenario = Scenario::Range { amplitude_percent: 2.0, period: 8 };
+        let values = rsi(&scenario.series(100.0, 120), 14);
+        let last = *values.last().unwrap();
+        assert!(last > 30.0 && last < 70.0, "{}: {}", scenario.name(), last);
+    }
+
+    #[test]
+    fn ema_is_seeded_with_the_sma_and_tracks_a_flat_series() {
+        let flat = Scenario::Gap { gap_percent: 0.0, at: 0 }.series(50.0, 30);
+        let values = ema(&flat, 12);
+        assert_eq!(values.len(), 30 - 12 + 1);
+        assert!(values.iter().all(|v| (*v - 50.0).abs() < 1e-9));
+
+        let seeded = ema(&[1.0, 2.0, 3.0, 4.0], 3);
+        assert_eq!(seeded[0], 2.0);
+        // k = 0.5: 4 * 0.5 + 2 * 0.5
+        assert_eq!(seeded[1], 3.0);
+    }
+
+    #[test]
+    fn short_ema_leads_the_long_one_in_a_trend() {
+        let settings = default_settings();
+        let up = compute(&UPTREND.series(100.0, 60), &settings);
+        assert!(up.ema_short.unwrap() > up.ema_long.unwrap());
+
+        let down = compute(&DOWNTREND.series(100.0, 60), &settings);
+        assert!(down.ema_short.unwrap() < down.ema_long.unwrap());
+    }
+
+    #[test]
+    fn ema_crosses_after_a_gap_up() {
+        let settings = default_settings();
+        let prices = Scenario::Gap { gap_percent: 10.0, at: 40 }.series(100.0, 41);
+        let snapshot = compute(&prices, &settings);
+        assert!(describe(&snapshot, &settings)
+            .iter()
+            .any(|r| r == "EMA12 crossed above EMA26"));
+    }
+
+    #[test]
+    fn macd_needs_slow_plus_signal_history() {
+        let settings = default_settings();
+        let needed = settings.macd_slow + settings.macd_signal - 1;
+        let hist = |len| macd_histogram(&UPTREND.series(100.0, len), 12, 26, 9);
+        assert!(hist(needed - 1).is_empty());
+        assert_eq!(hist(needed).len(), 1);
+    }
+
+    #[test]
+    fn macd_histogram_follows_the_direction_of_a_reversal() {
+        let uptrend = macd_histogram(&UPTREND.series(100.0, 80), 12, 26, 9);
+        assert!(*uptrend.last().unwrap() > 0.0);
+
+        // A compounding downtrend decelerates in price terms, so a gap down stands in for it
+        let gap_down = Scenario::Gap { gap_percent: -10.0, at: 60 }.series(100.0, 62);
+        let gap_down = macd_histogram(&gap_down, 12, 26, 9);
+        assert!(*gap_down.last().unwrap() < 0.0);
+
+        let reversal = Scenario::VReversal { depth_percent: 20.0 };
+        let hist = macd_histogram(&reversal.series(100.0, 81), 12, 26, 9);
+        assert!(*hist.last().unwrap() > 0.0, "{}: {:?}", reversal.name(), hist.last());
+    }
+
+    #[test]
+    fn pending_lists_indicators_without_enough_history() {
+        let settings = default_settings();
+        let prices = UPTREND.series(100.0, 20);
+        let snapshot = compute(&prices, &settings);
+        assert_eq!(
+            snapshot.pending(&settings, prices.len()),
+            vec!["ema_long", "macd_histogram", "divergence"]
+        );
+    }
+
+    fn non_finite_prices() -> Vec<f64> {
+        let mut prices = UPTREND.series(100.0, 60);
+        prices[45] = f64::NAN;
+        prices[52] = f64::INFINITY;
+        prices[58] = f64::NEG_INFINITY;
+        prices
+    }
+
+    #[test]
+    fn non_finite_inputs_are_sanitized_out_of_the_json() {
+        for mode in [NonFinite::Null, NonFinite::Clamp] {
+            let settings = IndicatorSettings { non_finite: mode, ..default_settings() };
+            let snapshot = compute(&non_finite_prices(), &settings);
+
+            assert!(!snapshot.non_finite.is_empty(), "{:?}: nothing flagged", mode);
+            for value in [
+                snapshot.rsi,
+                snapshot.prev_rsi,
+                snapshot.ema_short,
+                snapshot.ema_long,
+                snapshot.prev_ema_short,
+                snapshot.prev_ema_long,
+                snapshot.macd_histogram,
+                snapshot.prev_macd_histogram,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                assert!(value.is_finite(), "{:?}: {} passed through", mode, value);
+            }
+            // The plain-decimal formatter would write NaN and inf verbatim
+            for format in [NumberFormat::Number, NumberFormat::Fixed, NumberFormat::String] {
+                let json = to_json_string(&snapshot, format);
+                assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok(), "{}", json);
+                assert!(!json.contains("NaN") && !json.contains("inf"), "{}", json);
+            }
+        }
+    }
+
+    #[test]
+    fn clamp_pins_infinite_rsi_and_drops_nan() {
+        let mut flagged = Vec::new();
+        let range = (0.0, 100.0);
+        assert_eq!(sanitize(Some(f64::INFINITY), "rsi", range, NonFinite::Clamp, &mut flagged), Some(100.0));
+        assert_eq!(sanitize(Some(f64::NEG_INFINITY), "rsi", range, NonFinite::Clamp, &mut flagged), Some(0.0));
+        assert_eq!(sanitize(Some(f64::NAN), "rsi", range, NonFinite::Clamp, &mut flagged), None);
+        assert_eq!(sanitize(Some(f64::INFINITY), "rsi", range, NonFinite::Null, &mut flagged), None);
+        assert_eq!(sanitize(Some(42.0), "rsi", range, NonFinite::Null, &mut flagged), Some(42.0));
+        assert_eq!(flagged.len(), 4);
+    }
+
+    // Choppy warm-up, a sharp rally to 120, a pullback to 105, then a choppy grind to a
+    // marginally higher high whose RSI is well below the rally's
+    fn bearish_divergence_series() -> Vec<f64> {
+        let warm_up = (0..20).map(|i| if i % 2 == 0 { 99.0 } else { 101.0 });
+        let rally = (1..=10).map(|i| 100.0 + 2.0 * i as f64);
+        let pullback = (1..=6).map(|i| 120.0 - 2.5 * i as f64);
+        let grind = (0..14).scan(105.0, |price, i| {
+            *price += if i % 2 == 0 { 3.0 } else { -0.6 };
+            Some(*price)
+        });
+        warm_up.chain(rally).chain(pullback).chain(grind).collect()
+    }
+
+    #[test]
+    fn detects_bearish_divergence() {
+        let prices = bearish_divergence_series();
+        assert_eq!(divergence(&prices, 14, 30, 2.0), Some(Divergence::BearishDivergence));
+        let snapshot = compute(&prices, &default_settings());
+        assert_eq!(snapshot.divergence, Some(Divergence::BearishDivergence));
+        assert!(describe(&snapshot, &default_settings())
+            .iter()
+            .any(|r| r.starts_with("Bearish RSI divergence")));
+    }
+
+    #[test]
+    fn detects_bullish_divergence() {
+        // The bearish series upside down: a lower low on a higher RSI
+        let prices: Vec<f64> = bearish_divergence_series().iter().map(|p| 240.0 - p).collect();
+        assert_eq!(divergence(&prices, 14, 30, 2.0), Some(Divergence::BullishDivergence));
+        assert_eq!(compute(&prices, &default_settings()).divergence, Some(Divergence::BullishDivergence));
+    }
+
+    #[test]
+    fn no_divergence_when_rsi_confirms_the_move() {
+        assert_eq!(divergence(&UPTREND.series(100.0, 60), 14, 30, 2.0), None);
+        assert_eq!(divergence(&DOWNTREND.series(100.0, 60), 14, 30, 2.0), None);
+        // Too short for an RSI value at every price in the window
+        assert_eq!(divergence(&bearish_divergence_series()[..43], 14, 30, 2.0), None);
+        // A gap demand larger than the RSI difference
+        assert_eq!(divergence(&bearish_divergence_series(), 14, 30, 20.0), None);
+
+        let off = IndicatorSettings { divergence_lookback: 0, ..default_settings() };
+        assert_eq!(compute(&bearish_divergence_series(), &off).divergence, None);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::config;
+
+// Request handling and CoinGecko fetch times, bucketed by METRICS_HISTOGRAM_BUCKETS_MS
+// for the Prometheus histograms on /metrics, and AI call times for its summary.
+static REQUESTS: OnceLock<Mutex<Histogram>> = OnceLock::new();
+static UPSTREAM_FETCHES: OnceLock<Mutex<Histogram>> = OnceLock::new();
+static AI_CALLS: OnceLock<Mutex<AiCalls>> = OnceLock::new();
+
+// Recent AI calls the percentiles are taken over
+const AI_CALL_WINDOW: usize = 500;
+pub const AI_CALL_QUANTILES: &[f64] = &[0.5, 0.9, 0.99];
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    // Observations per bucket (not cumulative); the last slot is everything above the highest bound
+    pub counts: Vec<u64>,
+    pub sum_seconds: f64,
+    pub count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            counts: vec![0; config::get().metrics_histogram_buckets_ms.len() + 1],
+            sum_seconds: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let bucket = config::get()
+            .metrics_histogram_buckets_ms
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(self.counts.len() - 1);
+        self.counts[bucket] += 1;
+        self.sum_seconds += elapsed.as_secs_f64();
+        self.count += 1;
+    }
+}
+
+fn requests() -> &'static Mutex<Histogram> {
+    REQUESTS.get_or_init(|| Mutex::new(Histogram::new()))
+}
+
+fn upstream_fetches() -> &'static Mutex<Histogram> {
+    UPSTREAM_FETCHES.get_or_init(|| Mutex::new(Histogram::new()))
+}
+
+pub fn observe_request(elapsed: Duration) {
+    requests().lock().unwrap().observe(elapsed);
+}
+
+pub fn observe_upstream_fetch(elapsed: Duration) {
+    upstream_fetches().lock().unwrap().observe(elapsed);
+}
+
+pub fn request_histogram() -> Histogram {
+    requests().lock().unwrap().clone()
+}
+
+pub fn upstream_fetch_histogram() -> Histogram {
+    upstream_fetches().lock().unwrap().clone()
+}
+
+#[derive(Default)]
+struct AiCalls {
+    // Durations in seconds of the latest AI_CALL_WINDOW calls, oldest first
+    recent: VecDeque<f64>,
+    sum_seconds: f64,
+    count: u64,
+    timeouts: u64,
+}
+
+pub struct AiCallSummary {
+    // (quantile, seconds) for AI_CALL_QUANTILES; None before any call
+    pub quantiles: Vec<(f64, Option<f64>)>,
+    pub sum_seconds: f64,
+    pub count: u64,
+    pub timeouts: u64,
+}
+
+fn ai_calls() -> &'static Mutex<AiCalls> {
+    AI_CALLS.get_or_init(|| Mutex::new(AiCalls::default()))
+}
+
+// Every AI provider call, whatever its outcome; a timed-out call counts up to the cut-off
+pub fn observe_ai_call(elapsed: Duration, timed_out: bool) {
+    let mut calls = ai_calls().lock().unwrap();
+    calls.recent.push_back(elapsed.as_secs_f64());
+    while calls.recent.len() > AI_CALL_WINDOW {
+        calls.recent.pop_front();
+    }
+    calls.sum_seconds += elapsed.as_secs_f64();
+    calls.count += 1;
+    if timed_out {
+        calls.timeouts += 1;
+    }
+}
+
+pub fn ai_call_summary() -> AiCallSummary {
+    let calls = ai_calls().lock().unwrap();
+    let mut sorted: Vec<f64> = calls.recent.iter().copied().collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    // Nearest rank
+    let quantiles = AI_CALL_QUANTILES
+        .iter()
+        .map(|q| {
+            let rank = ((q * sorted.len() as f64).ceil() as usize).max(1);
+            (*q, sorted.get(rank - 1).copied())
+        })
+        .collect();
+    AiCallSummary {
+        quantiles,
+        sum_seconds: calls.sum_seconds,
+        count: calls.count,
+        timeouts: calls.timeouts,
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use serde::Deserialize;
+
+use crate::config;
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+// A recurring UTC window in which markets are thin: every listed day from `start` to
+// `end` (minutes since midnight). An end before the start runs into the next day.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LiquidityWindow {
+    // Indexed from Monday
+    days: [bool; 7],
+    start: u32,
+    end: u32,
+}
+
+impl LiquidityWindow {
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        let day = at.weekday().num_days_from_monday() as usize;
+        let minute = at.hour() * 60 + at.minute();
+        if self.start < self.end {
+            return self.days[day] && minute >= self.start && minute < self.end;
+        }
+        let previous = (day + 6) % 7;
+        (self.days[day] && minute >= self.start) || (self.days[previous] && minute < self.end)
+    }
+}
+
+// LOW_LIQUIDITY_WINDOWS, comma-separated "DAY[-DAY][ HH:MM-HH:MM]" in UTC, e.g.
+// "sat-sun" or "fri 21:00-24:00,sun 22:00-01:00". No time range is the whole day.
+pub fn parse_windows(raw: &str) -> Result<Vec<LiquidityWindow>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| parse_window(entry).map_err(|e| format!("Invalid LOW_LIQUIDITY_WINDOWS entry '{}': {}", entry, e)))
+        .collect()
+}
+
+fn parse_window(entry: &str) -> Result<LiquidityWindow, String> {
+    let mut parts = entry.split_whitespace();
+    let days = parse_days(parts.next().unwrap_or_default())?;
+    let (start, end) = match parts.next() {
+        Some(range) => {
+            let (start, end) = range
+                .split_once('-')
+                .ok_or_else(|| "expected a time range like 21:00-24:00".to_string())?;
+            (parse_time(start)?, parse_time(end)?)
+        }
+        None => (0, MINUTES_PER_DAY),
+    };
+    if parts.next().is_some() {
+        return Err("expected DAY[-DAY] followed by at most one time range".to_string());
+    }
+    if start == end || start == MINUTES_PER_DAY {
+        return Err("the time range is empty".to_string());
+    }
+    Ok(LiquidityWindow { days, start, end })
+}
+
+// "sat" or a range such as "fri-mon", wrapping past Sunday
+fn parse_days(raw: &str) -> Result<[bool; 7], String> {
+    let day = |name: &str| {
+        DAYS.iter()
+            .position(|d| d.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown day '{}' (use {})", name, DAYS.join(", ")))
+    };
+    let (first, last) = match raw.split_once('-') {
+        Some((first, last)) => (day(first)?, day(last)?),
+        None => (day(raw)?, day(raw)?),
+    };
+    let mut days = [false; 7];
+    let mut current = first;
+    loop {
+        days[current] = true;
+        if current == last {
+            return Ok(days);
+        }
+        current = (current + 1) % 7;
+    }
+}
+
+// HH:MM, with 24:00 for the end of the day
+fn parse_time(raw: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid time '{}' (expected HH:MM)", raw);
+    let (hours, minutes) = raw.split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    match (hours, minutes) {
+        (24, 0) => Ok(MINUTES_PER_DAY),
+        (h, m) if h < 24 && m < 60 => Ok(h * 60 + m),
+        _ => Err(invalid()),
+    }
+}
+
+// Whether the coin is inside one of LOW_LIQUIDITY_WINDOWS right now. Coins outside
+// LOW_LIQUIDITY_SYMBOLS (when set) are never affected.
+pub fn is_low_liquidity(symbol: &str) -> bool {
+    let config = config::get();
+    if config.low_liquidity_windows.is_empty() {
+        return false;
+    }
+    let symbol = symbol.to_uppercase();
+    if !config.low_liquidity_symbols.is_empty() && !config.low_liquidity_symbols.contains(&symbol) {
+        return false;
+    }
+    let now = Utc::now();
+    config.low_liquidity_windows.iter().any(|window| window.contains(now))
+}
+
+// The confidence scaled by LOW_LIQUIDITY_CONFIDENCE_FACTOR inside a window, and whether it was
+pub fn adjust_confidence(symbol: &str, confidence: f64) -> (f64, bool) {
+    if is_low_liquidity(symbol) {
+        (confidence * config::get().low_liquidity_confidence_factor, true)
+    } else {
+        (confidence, false)
+    }
+}
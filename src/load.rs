@@ -0,0 +1,51 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config;
+use crate::routes::{HEALTH_ENDPOINT, READY_ENDPOINT};
+
+// Requests currently being handled, probes left out
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+// Counts a request for as long as it is alive, including when its future is dropped
+struct InFlight;
+
+impl InFlight {
+    fn enter() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub fn in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+// Whether /ready should turn traffic away: more than LOAD_SHED_THRESHOLD requests in
+// flight (0 never sheds)
+pub fn shedding() -> bool {
+    let threshold = config::get().load_shed_threshold;
+    threshold > 0 && in_flight() > threshold
+}
+
+// Middleware: tracks in-flight requests for /ready. The probes themselves aren't counted,
+// so a burst of health checks can't make the instance look busy. A streaming response
+// counts until its headers are sent.
+pub async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if req.path() == HEALTH_ENDPOINT || req.path() == READY_ENDPOINT {
+        return next.call(req).await;
+    }
+    let _in_flight = InFlight::enter();
+    next.call(req).await
+}
@@ -1,12 +1,64 @@
-use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{get, middleware, web, App, HttpResponse, HttpServer, Responder};
+mod alert_store;
+mod alerts;
+mod api_keys;
+mod audit;
+mod auth;
+mod coin_list;
+mod coingecko;
+mod coins;
+mod compression;
+mod config;
+mod discovery;
+mod error;
+mod format;
+mod history;
+mod indicators;
+mod latency;
+mod liquidity;
+mod load;
+mod monitor;
+mod notifier;
+mod paper;
+mod prewarm;
+mod prices;
+mod purge;
+mod pushgateway;
+mod quality;
+mod refresher;
+mod request_log;
 mod routes;
-use routes::signals;
+mod scheduler;
+mod signal_tracker;
+mod strategy;
+mod streams;
+#[cfg(test)]
+mod test_support;
+mod watch_rules;
+mod ws;
+use routes::{config as config_route, correlation, diagnostics, metrics, paper as paper_route, selftest, signals, signals_ws, stream};
 
 #[get("/_health")]
 async fn health() -> impl Responder {
     HttpResponse::Ok().body("OK")
 }
 
+// Readiness, unlike /_health, fails while the instance is overloaded so the load
+// balancer sends traffic elsewhere without the orchestrator restarting it
+#[get("/ready")]
+async fn ready() -> impl Responder {
+    let body = serde_json::json!({
+        "status": if load::shedding() { "overloaded" } else { "ready" },
+        "in_flight": load::in_flight(),
+        "shed_threshold": config::get().load_shed_threshold,
+    });
+    if load::shedding() {
+        HttpResponse::ServiceUnavailable().json(body)
+    } else {
+        HttpResponse::Ok().json(body)
+    }
+}
+
 #[get("/")]
 async fn index() -> impl Responder {
     HttpResponse::Ok()
@@ -54,18 +106,46 @@ async fn index() -> impl Responder {
             <span class="method get">GET</span> 
             <a href="/_health">/_health</a> - Simple health check
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/ready">/ready</a> - Readiness probe, 503 while overloaded
+        </div>
         <div class="endpoint">
             <span class="method get">GET</span> 
             <a href="/health">/health</a> - Detailed health with endpoints
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/endpoints">/endpoints</a> - Endpoints and whether each is enabled
+        </div>
         <div class="endpoint">
             <span class="method get">GET</span> 
             <a href="/prices">/prices</a> - <strong>LIVE</strong> crypto prices from CoinGecko
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/prices/stream">/prices/stream</a> - SSE price ticker (<code>?symbols=BTC,ETH</code>)
+        </div>
         <div class="endpoint">
             <span class="method get">GET</span> 
             <a href="/signals">/signals</a> - Trading signals based on live prices
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            /signals/ws - WebSocket signal updates (<code>?symbols=BTC,ETH&amp;batch_ms=1000</code>)
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/signals/correlation">/signals/correlation</a> - Correlation of recent returns (<code>?window=24h</code>)
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/signals/changes">/signals/changes</a> - Signal changes across all coins, newest first (<code>?limit=50&amp;before=</code>)
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/signals/raw">/signals/raw</a> - Cached prices with the data-quality breakdown
+        </div>
         <div class="endpoint">
             <span class="method get">GET</span> 
             <a href="/explain-signal">/explain-signal</a> - AI explains trading signals
@@ -74,6 +154,10 @@ async fn index() -> impl Responder {
             <span class="method get">GET</span> 
             <a href="/explain-all-signals">/explain-all-signals</a> - AI explains all signals
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/explain-signals?symbols=BTC,ETH">/explain-signals</a> - AI explains the listed signals (<code>?symbols=BTC,ETH</code>)
+        </div>
         <div class="endpoint">
             <span class="method post">POST</span> 
             /tradingview-webhook - Receive TradingView alerts
@@ -86,10 +170,46 @@ async fn index() -> impl Responder {
             <span class="method get">GET</span> 
             <a href="/alerts/BTC">/alerts/{symbol}</a> - Alerts for specific symbol
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/alerts/BTC/histogram">/alerts/{symbol}/histogram</a> - Alert counts per time bucket (<code>?bucket=1h&amp;window=24h&amp;split=action</code>)
+        </div>
         <div class="endpoint">
             <span class="method get">GET</span> 
             <a href="/cache-stats">/cache-stats</a> - Cache statistics
         </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/diagnostics">/diagnostics</a> - Background task status
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/audit-log">/audit-log</a> - State the service changed on its own, e.g. purged coins (admin)
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/admin/api-keys">/admin/api-keys</a> - API key limits and usage (admin)
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/metrics">/metrics</a> - Prometheus metrics
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/config">/config</a> - Effective configuration
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/paper/portfolio">/paper/portfolio</a> - Paper-trading portfolio following the signals
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            <a href="/paper/trades">/paper/trades</a> - Paper-trading entries and exits (<code>?symbol=BTC&amp;limit=50&amp;before=</code>)
+        </div>
+        <div class="endpoint">
+            <span class="method get">GET</span> 
+            /selftest - Admin: fetch-to-explanation smoke test (<code>?symbol=BTC&amp;explain=true</code>)
+        </div>
         <div class="endpoint">
             <span class="method post">POST</span> 
             /clear-alerts - Clear all alerts
@@ -98,6 +218,10 @@ async fn index() -> impl Responder {
             <span class="method post">POST</span> 
             /clear-cache - Clear price cache
         </div>
+        <div class="endpoint">
+            <span class="method post">POST</span> 
+            /admin/refresh - Refresh every price now (admin)
+        </div>
         
         <h3>🔧 Testing:</h3>
         <p>Test with curl:</p>
@@ -112,33 +236,99 @@ curl http://localhost:8080/explain-all-signals</code></pre>
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "8080".to_string())
-        .parse::<u16>()
-        .expect("PORT must be a number");
+    let config = match config::Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let host = config.host.clone();
+    let port = config.port;
+    let supported = if config.track_top_n > 0 {
+        format!("top {} by market cap (TRACK_TOP_N)", config.track_top_n)
+    } else {
+        config.supported_symbols().join(", ")
+    };
+    let background_refresh = config.background_refresh;
+    for warning in config.startup_warnings() {
+        println!("⚠️ {}", warning);
+    }
+    config::init(config);
     
     println!("🚀 Trading Signals Backend starting on port {}", port);
     println!("📊 Fetching LIVE prices from CoinGecko API");
-    println!("✅ Supported coins: BTC, ETH, SOL, PAXG");
+    println!("✅ Supported coins: {}", supported);
     println!("🤖 AI Explanations available at /explain-signal");
+    if config::get().disabled_endpoints.is_empty() {
+        println!("🔌 All endpoints enabled");
+    } else {
+        let enabled: Vec<String> = routes::ROUTES
+            .iter()
+            .filter(|(_, pattern)| routes::is_enabled(pattern))
+            .map(|(method, pattern)| format!("{} {}", method, pattern))
+            .collect();
+        println!("🔌 Enabled endpoints: {}", enabled.join(", "));
+    }
+    
+    discovery::start().await;
+    if background_refresh {
+        refresher::seed_history();
+        refresher::start();
+        monitor::start_stale_data_monitor();
+    }
+    monitor::start_clock_monitor();
+    purge::start();
+    signals::load_alerts();
+    alert_store::start_compaction();
+    coin_list::start();
+    pushgateway::start();
     
     HttpServer::new(|| {
         App::new()
+            .wrap(middleware::from_fn(api_keys::gate))
+            .wrap(middleware::from_fn(routes::endpoint_gate))
+            .wrap(middleware::from_fn(metrics::record_latency))
+            .wrap(middleware::from_fn(load::track_in_flight))
+            .wrap(middleware::from_fn(request_log::log_requests))
+            .wrap(middleware::from_fn(compression::compress))
             .service(health)
+            .service(ready)
+            .service(routes::list_endpoints)
             .service(index)
             .service(signals::health_check)
             .service(signals::get_prices)
+            .service(stream::price_stream)
             .service(signals::get_signals)
+            .service(signals_ws::signals_ws)
+            .service(correlation::get_correlation)
+            .service(signals::get_signal_changes)
+            .service(signals::get_signals_raw)
             .service(signals::get_tradingview_alerts)
             .service(signals::get_symbol_alerts)
+            .service(signals::get_symbol_alert_histogram)
             .service(signals::get_cache_stats)
+            .service(diagnostics::get_diagnostics)
+            .service(diagnostics::get_audit_log)
+            .service(diagnostics::get_api_key_usage)
+            .service(metrics::get_metrics)
+            .service(config_route::get_config)
+            .service(selftest::run_selftest)
+            .service(paper_route::get_portfolio)
+            .service(paper_route::get_trades)
             .route("/explain-signal", web::get().to(signals::explain_signal))
+            .route("/explain-signals", web::get().to(signals::explain_signals))
             .route("/explain-all-signals", web::get().to(signals::explain_all_signals))
             .route("/tradingview-webhook", web::post().to(signals::tradingview_webhook))
             .route("/clear-alerts", web::post().to(signals::clear_alerts))
             .route("/clear-cache", web::post().to(signals::clear_cache))
+            .route("/admin/refresh", web::post().to(signals::admin_refresh))
+            .default_service(web::to(routes::unmatched))
     })
-    .bind(("0.0.0.0", port))?
+    .bind((host.as_str(), port))?
     .run()
-    .await
+    .await?;
+    
+    scheduler::shutdown();
+    Ok(())
 }
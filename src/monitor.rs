@@ -0,0 +1,117 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::format;
+use crate::notifier::{self, Notification};
+use crate::prices;
+use crate::scheduler;
+
+// Dead-man's switch: notifies when no price refresh has succeeded for the configured
+// threshold, and again once refreshes recover. Catches a refresher that is alive
+// but failing against upstream every time.
+pub fn start_stale_data_monitor() {
+    let threshold = match config::get().stale_data_alert_seconds {
+        0 => return,
+        seconds => Duration::from_secs(seconds),
+    };
+    let check_every = (threshold / 4).clamp(Duration::from_secs(1), Duration::from_secs(30));
+    let alerting = Arc::new(AtomicBool::new(false));
+
+    scheduler::register("stale_data_monitor", check_every, move || {
+        let alerting = alerting.clone();
+        async move {
+            check_staleness(threshold, &alerting);
+            Ok(())
+        }
+    });
+}
+
+fn check_staleness(threshold: Duration, alerting: &AtomicBool) {
+    let since = prices::time_since_successful_refresh();
+
+    if since >= threshold && !alerting.swap(true, Ordering::SeqCst) {
+        println!("🚨 No successful price refresh for {}s", since.as_secs());
+        notifier::notify(Notification::new(
+            "data_stale",
+            "Price data is stale",
+            format!(
+                "No successful price refresh for {}s (threshold {}s)",
+                since.as_secs(),
+                threshold.as_secs()
+            ),
+        ));
+    } else if since < threshold && alerting.swap(false, Ordering::SeqCst) {
+        println!("✅ Price refreshes recovered");
+        notifier::notify(Notification::new(
+            "data_recovered",
+            "Price data recovered",
+            "Price refreshes are succeeding again".to_string(),
+        ));
+    }
+}
+
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+static CLOCK_JUMPS: OnceLock<Mutex<ClockJumps>> = OnceLock::new();
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClockJumps {
+    pub detected: u64,
+    // Wall clock minus monotonic elapsed; negative means the clock went backwards
+    pub last_jump_seconds: Option<i64>,
+    #[serde(serialize_with = "format::serialize_optional_timestamp")]
+    pub last_detected_at: Option<i64>,
+}
+
+fn clock_jumps() -> &'static Mutex<ClockJumps> {
+    CLOCK_JUMPS.get_or_init(|| Mutex::new(ClockJumps::default()))
+}
+
+pub fn clock_jumps_snapshot() -> ClockJumps {
+    clock_jumps().lock().unwrap().clone()
+}
+
+// Compares wall-clock progress with the monotonic clock between checks. A large
+// difference means NTP corrected the clock or the container was migrated, which
+// shifts every wall-clock timestamp recorded from then on.
+pub fn start_clock_monitor() {
+    let threshold = match config::get().clock_jump_alert_seconds {
+        0 => return,
+        seconds => seconds as i64,
+    };
+    let last = Arc::new(Mutex::new((Instant::now(), Utc::now().timestamp_millis())));
+
+    scheduler::register("clock_monitor", CLOCK_CHECK_INTERVAL, move || {
+        let last = last.clone();
+        async move {
+            check_clock(threshold, &last);
+            Ok(())
+        }
+    });
+}
+
+fn check_clock(threshold: i64, last: &Mutex<(Instant, i64)>) {
+    let now = (Instant::now(), Utc::now().timestamp_millis());
+    let previous = std::mem::replace(&mut *last.lock().unwrap(), now);
+
+    let monotonic_ms = now.0.duration_since(previous.0).as_millis() as i64;
+    let wall_ms = now.1 - previous.1;
+    let jump_seconds = (wall_ms - monotonic_ms) / 1000;
+
+    if jump_seconds.abs() >= threshold {
+        let direction = if jump_seconds < 0 { "backwards" } else { "forwards" };
+        println!(
+            "🕰️ Wall clock jumped {} by {}s - cache TTLs are unaffected, but timestamps around now may be out of order",
+            direction,
+            jump_seconds.abs()
+        );
+        let mut jumps = clock_jumps().lock().unwrap();
+        jumps.detected += 1;
+        jumps.last_jump_seconds = Some(jump_seconds);
+        jumps.last_detected_at = Some(now.1 / 1000);
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+static ALERT_THROTTLE: OnceLock<Mutex<HashMap<String, ThrottleState>>> = OnceLock::new();
+
+#[derive(Default)]
+struct ThrottleState {
+    last_sent: Option<Instant>,
+    pending: Vec<String>,
+    // Action and price of the latest pending alert, for templates
+    action: Option<String>,
+    price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyFormat {
+    Generic,
+    Discord,
+    Slack,
+}
+
+impl NotifyFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "generic" => Ok(NotifyFormat::Generic),
+            "discord" => Ok(NotifyFormat::Discord),
+            "slack" => Ok(NotifyFormat::Slack),
+            other => Err(format!(
+                "Invalid NOTIFY_FORMAT: {}. Use generic, discord or slack.",
+                other
+            )),
+        }
+    }
+}
+
+impl NotifyFormat {
+    // The built-in formats, written as NOTIFY_TEMPLATE templates
+    fn preset(&self) -> Value {
+        match self {
+            NotifyFormat::Discord => json!({ "content": "**{{title}}**\n{{message}}" }),
+            NotifyFormat::Slack => json!({ "text": "*{{title}}*\n{{message}}" }),
+            NotifyFormat::Generic => json!({
+                "event": "{{event}}",
+                "title": "{{title}}",
+                "message": "{{message}}",
+                "timestamp": "{{timestamp}}",
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub event: String,
+    pub title: String,
+    pub message: String,
+    // Only set for alert notifications
+    pub symbol: Option<String>,
+    pub action: Option<String>,
+    pub price: Option<f64>,
+}
+
+impl Notification {
+    pub fn new(event: &str, title: &str, message: String) -> Self {
+        Self {
+            event: event.to_string(),
+            title: title.to_string(),
+            message,
+            symbol: None,
+            action: None,
+            price: None,
+        }
+    }
+
+    fn value(&self, placeholder: &str) -> Value {
+        match placeholder {
+            "event" => json!(self.event),
+            "title" => json!(self.title),
+            "message" => json!(self.message),
+            "symbol" => json!(self.symbol),
+            "action" => json!(self.action),
+            "price" => json!(self.price),
+            _ => json!(Utc::now().timestamp()),
+        }
+    }
+}
+
+// Names a template may use as {{name}}
+pub const PLACEHOLDERS: &[&str] = &["event", "title", "message", "symbol", "action", "price", "timestamp"];
+
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+// Splits "a {{symbol}} b" into literal text and placeholder names
+fn parts(text: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| format!("Unclosed placeholder in '{}'", text))?
+            + start;
+        parts.push(Part::Text(&rest[..start]));
+        parts.push(Part::Placeholder(rest[start + 2..end].trim()));
+        rest = &rest[end + 2..];
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+fn check_placeholders(value: &Value) -> Result<(), String> {
+    match value {
+        Value::String(text) => {
+            for part in parts(text)? {
+                if let Part::Placeholder(name) = part {
+                    if !PLACEHOLDERS.contains(&name) {
+                        return Err(format!(
+                            "Unknown placeholder {{{{{}}}}}. Use {}.",
+                            name,
+                            PLACEHOLDERS.join(", ")
+                        ));
+                    }
+                }
+            }
+            Ok(())
+        }
+        Value::Array(items) => items.iter().try_for_each(check_placeholders),
+        Value::Object(fields) => fields.values().try_for_each(check_placeholders),
+        _ => Ok(()),
+    }
+}
+
+// NOTIFY_TEMPLATE: a JSON body whose string values may contain placeholders
+pub fn parse_template(raw: &str) -> Result<Value, String> {
+    let template: Value =
+        serde_json::from_str(raw).map_err(|e| format!("NOTIFY_TEMPLATE is not valid JSON: {}", e))?;
+    check_placeholders(&template).map_err(|e| format!("Invalid NOTIFY_TEMPLATE: {}", e))?;
+    Ok(template)
+}
+
+// A string that is just one placeholder takes the value's JSON type (a number for
+// price, null when unset); placeholders inside other text are written as text.
+fn render(template: &Value, notification: &Notification) -> Value {
+    match template {
+        Value::String(text) => {
+            let Ok(parts) = parts(text) else {
+                return template.clone();
+            };
+            if let [Part::Text(""), Part::Placeholder(name), Part::Text("")] = parts.as_slice() {
+                return notification.value(name);
+            }
+            let rendered: String = parts
+                .iter()
+                .map(|part| match part {
+                    Part::Text(text) => text.to_string(),
+                    Part::Placeholder(name) => match notification.value(name) {
+                        Value::String(s) => s,
+                        Value::Null => String::new(),
+                        other => other.to_string(),
+                    },
+                })
+                .collect();
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| render(v, notification)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, v)| (key.clone(), render(v, notification)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn payload(notification: &Notification) -> Value {
+    let config = config::get();
+    match &config.notify_template {
+        Some(template) => render(template, notification),
+        None => render(&config.notify_format.preset(), notification),
+    }
+}
+
+// Fire-and-forget: delivery failures are logged and never reach the caller.
+pub fn notify(notification: Notification) {
+    let config = config::get();
+    let Some(url) = config.notify_webhook_url.clone() else {
+        return;
+    };
+    let body = payload(&notification);
+
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                println!("❌ Notifier client error: {}", e);
+                return;
+            }
+        };
+
+        match client.post(&url).json(&body).send().await {
+            Ok(response) if response.status().is_success() => {
+                println!("📣 Sent '{}' notification", notification.event);
+            }
+            Ok(response) => println!(
+                "❌ Notification '{}' rejected: {}",
+                notification.event,
+                response.status()
+            ),
+            Err(e) => println!("❌ Notification '{}' failed: {}", notification.event, e),
+        }
+    });
+}
+
+// Sends at most one alert notification per symbol per throttle window. Alerts arriving
+// inside the window are either collapsed into one summary sent when it closes, or
+// dropped when summaries are disabled.
+pub fn notify_alert(symbol: &str, action: Option<&str>, price: f64, summary: String) {
+    let config = config::get();
+    if config.notify_webhook_url.is_none() {
+        return;
+    }
+    let window = Duration::from_secs(config.notify_symbol_interval_seconds);
+
+    let mut throttle = ALERT_THROTTLE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap();
+    let state = throttle.entry(symbol.to_string()).or_default();
+
+    let last_sent = match state.last_sent {
+        Some(last_sent) if last_sent.elapsed() < window => last_sent,
+        _ => {
+            state.last_sent = Some(Instant::now());
+            notify(Notification {
+                symbol: Some(symbol.to_string()),
+                action: action.map(|a| a.to_string()),
+                price: Some(price),
+                ..Notification::new("alert", &format!("{} alert", symbol), summary)
+            });
+            return;
+        }
+    };
+
+    if !config.notify_summarize {
+        println!("🔇 Throttled {} alert notification: {}", symbol, summary);
+        return;
+    }
+
+    state.pending.push(summary);
+    state.action = action.map(|a| a.to_string());
+    state.price = Some(price);
+    if state.pending.len() == 1 {
+        let symbol = symbol.to_string();
+        let flush_in = window.saturating_sub(last_sent.elapsed());
+        tokio::spawn(async move {
+            tokio::time::sleep(flush_in).await;
+            flush_pending(&symbol, window);
+        });
+    }
+}
+
+fn flush_pending(symbol: &str, window: Duration) {
+    let mut throttle = ALERT_THROTTLE.get().unwrap().lock().unwrap();
+    let Some(state) = throttle.get_mut(symbol) else {
+        return;
+    };
+    let pending = std::mem::take(&mut state.pending);
+    state.last_sent = Some(Instant::now());
+
+    let message = match pending.len() {
+        0 => return,
+        1 => pending[0].clone(),
+        n => format!(
+            "{} signals for {} in last {} min:\n{}",
+            n,
+            symbol,
+            (window.as_secs() / 60).max(1),
+            pending.join("\n")
+        ),
+    };
+    notify(Notification {
+        symbol: Some(symbol.to_string()),
+        action: state.action.take(),
+        price: state.price.take(),
+        ..Notification::new("alert", &format!("{} alert", symbol), message)
+    });
+}
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::coingecko::PriceData;
+use crate::config;
+use crate::format;
+use crate::prices;
+use crate::strategy::{generate_signal, get_action_from_signal};
+
+static PORTFOLIO: OnceLock<Mutex<Portfolio>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub quantity: f64,
+    pub entry_price: f64,
+    pub opened_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Entry,
+    Exit,
+}
+
+// One simulated fill, as saved in the state file; /paper/trades formats the symbol and time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    // The portfolio's trade count after this trade, so ids only ever increase
+    pub id: u64,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub price: f64,
+    pub quantity: f64,
+    // Signal whose change triggered the trade
+    pub signal: String,
+    // Realized on exits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pnl: Option<f64>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub starting_balance: f64,
+    pub cash: f64,
+    pub realized_pnl: f64,
+    pub trades: u64,
+    pub positions: HashMap<String, Position>,
+    // Signal seen at the previous refresh, so trades only happen when it changes
+    pub last_signals: HashMap<String, String>,
+    // Oldest first, trimmed to PAPER_TRADE_LOG_RETENTION; absent in state files from before the log
+    #[serde(default)]
+    pub trade_log: Vec<Trade>,
+}
+
+impl Portfolio {
+    fn new(starting_balance: f64) -> Self {
+        Portfolio {
+            starting_balance,
+            cash: starting_balance,
+            realized_pnl: 0.0,
+            trades: 0,
+            positions: HashMap::new(),
+            last_signals: HashMap::new(),
+            trade_log: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, trade: Trade) {
+        let retention = config::get().paper_trade_log_retention;
+        self.trade_log.push(trade);
+        if self.trade_log.len() > retention {
+            let excess = self.trade_log.len() - retention;
+            self.trade_log.drain(..excess);
+        }
+    }
+}
+
+fn portfolio() -> &'static Mutex<Portfolio> {
+    PORTFOLIO.get_or_init(|| Mutex::new(load()))
+}
+
+// Restores the portfolio from PAPER_STATE_FILE, or starts fresh with the configured balance.
+fn load() -> Portfolio {
+    let config = config::get();
+    let path = Path::new(&config.paper_state_file);
+
+    match fs::read_to_string(path) {
+        Ok(raw) => match serde_json::from_str::<Portfolio>(&raw) {
+            Ok(portfolio) => {
+                println!(
+                    "📒 Loaded paper portfolio from {} ({} open positions)",
+                    path.display(),
+                    portfolio.positions.len()
+                );
+                portfolio
+            }
+            Err(e) => {
+                println!(
+                    "⚠️ Ignoring unreadable paper portfolio {}: {}; starting fresh",
+                    path.display(),
+                    e
+                );
+                Portfolio::new(config.paper_starting_balance)
+            }
+        },
+        Err(_) => Portfolio::new(config.paper_starting_balance),
+    }
+}
+
+// Writes to a temp file and renames it, so a crash mid-write can't corrupt the state.
+fn save(portfolio: &Portfolio) {
+    let path = &config::get().paper_state_file;
+    let tmp = format!("{}.tmp", path);
+    let result = serde_json::to_string_pretty(portfolio)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| fs::write(&tmp, raw).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&tmp, path).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        println!("❌ Failed to save paper portfolio to {}: {}", path, e);
+    }
+}
+
+// Acts on the symbol's signal when it changes: opens a long of PAPER_POSITION_SIZE
+// on a buy/strong_buy, closes it on a sell/strong_sell. Holds and weak signals
+// leave the position alone.
+pub fn on_price(price: &PriceData) {
+    let config = config::get();
+    if !config.paper_trading {
+        return;
+    }
+
+    let (signal, _) = generate_signal(price);
+    let mut portfolio = portfolio().lock().unwrap();
+    let previous = portfolio
+        .last_signals
+        .insert(price.symbol.clone(), signal.clone());
+    if previous.as_ref() == Some(&signal) {
+        return;
+    }
+
+    let action = get_action_from_signal(&signal);
+    let holding = portfolio.positions.contains_key(&price.symbol);
+
+    if action.starts_with("ENTER_LONG") && !holding {
+        let size = config.paper_position_size.min(portfolio.cash);
+        if size <= 0.0 || price.price <= 0.0 {
+            println!("📒 Paper: no cash left to buy {}", price.symbol);
+        } else {
+            portfolio.cash -= size;
+            portfolio.trades += 1;
+            let quantity = size / price.price;
+            portfolio.positions.insert(
+                price.symbol.clone(),
+                Position {
+                    quantity,
+                    entry_price: price.price,
+                    opened_at: price.timestamp,
+                },
+            );
+            let id = portfolio.trades;
+            portfolio.record(Trade {
+                id,
+                symbol: price.symbol.clone(),
+                side: TradeSide::Entry,
+                price: price.price,
+                quantity,
+                signal: signal.clone(),
+                pnl: None,
+                timestamp: price.timestamp,
+            });
+            println!("📒 Paper: bought ${:.2} of {} at {} ({})", size, price.symbol, price.price, signal);
+        }
+    } else if action.starts_with("ENTER_SHORT") && holding {
+        let position = portfolio.positions.remove(&price.symbol).unwrap();
+        let proceeds = position.quantity * price.price;
+        let pnl = proceeds - position.quantity * position.entry_price;
+        portfolio.cash += proceeds;
+        portfolio.realized_pnl += pnl;
+        portfolio.trades += 1;
+        let id = portfolio.trades;
+        portfolio.record(Trade {
+            id,
+            symbol: price.symbol.clone(),
+            side: TradeSide::Exit,
+            price: price.price,
+            quantity: position.quantity,
+            signal: signal.clone(),
+            pnl: Some(pnl),
+            timestamp: price.timestamp,
+        });
+        println!("📒 Paper: sold {} at {} for {:+.2} ({})", price.symbol, price.price, pnl, signal);
+    }
+
+    save(&portfolio);
+}
+
+// Current portfolio valued at the latest cached prices.
+pub fn summary() -> serde_json::Value {
+    let portfolio = portfolio().lock().unwrap().clone();
+
+    let mut positions_value = 0.0;
+    let mut unrealized_pnl = 0.0;
+    let mut positions: Vec<serde_json::Value> = portfolio
+        .positions
+        .iter()
+        .map(|(symbol, position)| {
+            let current_price = prices::cached_price(symbol)
+                .map(|p| p.price)
+                .unwrap_or(position.entry_price);
+            let value = position.quantity * current_price;
+            let pnl = value - position.quantity * position.entry_price;
+            positions_value += value;
+            unrealized_pnl += pnl;
+
+            serde_json::json!({
+                "symbol": format::symbol(symbol),
+                "quantity": position.quantity,
+                "entry_price": position.entry_price,
+                "current_price": current_price,
+                "market_value": value,
+                "unrealized_pnl": pnl,
+                "opened_at": format::timestamp(position.opened_at),
+            })
+        })
+        .collect();
+    positions.sort_by(|a, b| a["symbol"].as_str().cmp(&b["symbol"].as_str()));
+
+    let equity = portfolio.cash + positions_value;
+    let total_pnl = equity - portfolio.starting_balance;
+
+    serde_json::json!({
+        "starting_balance": portfolio.starting_balance,
+        "cash": portfolio.cash,
+        "positions": positions,
+        "positions_value": positions_value,
+        "equity": equity,
+        "realized_pnl": portfolio.realized_pnl,
+        "unrealized_pnl": unrealized_pnl,
+        "total_pnl": total_pnl,
+        "total_return_pct": if portfolio.starting_balance > 0.0 {
+            total_pnl / portfolio.starting_balance * 100.0
+        } else {
+            0.0
+        },
+        "trades": portfolio.trades,
+    })
+}
+
+// Logged trades newest first, optionally for one symbol, with ids below `before`;
+// also returns how many the log holds in total
+pub fn trades(symbol: Option<&str>, limit: usize, before: Option<u64>) -> (Vec<Trade>, usize) {
+    let portfolio = portfolio().lock().unwrap();
+    let page = portfolio
+        .trade_log
+        .iter()
+        .rev()
+        .filter(|trade| symbol.is_none_or(|symbol| trade.symbol == symbol))
+        .filter(|trade| before.is_none_or(|before| trade.id < before))
+        .take(limit)
+        .cloned()
+        .collect();
+    (page, portfolio.trade_log.len())
+}
@@ -0,0 +1,89 @@
+use futures_util::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config;
+use crate::prices;
+use crate::routes::ai_explanation::{self, AIExplainer, Verbosity};
+use crate::strategy::{generate_signal, signal_reasons};
+
+static LAST_SIGNALS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub fn forget(symbol: &str) {
+    if let Some(last_signals) = LAST_SIGNALS.get() {
+        last_signals.lock().unwrap().remove(&symbol.to_uppercase());
+    }
+}
+
+// Generates explanations in the background after a refresh so /explain-signal is
+// served from cache. Symbols whose signal just changed go first; every call still
+// goes through the OpenAI semaphore and daily budget.
+pub fn trigger() {
+    if !config::get().explanation_prewarm || RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async {
+        run().await;
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+async fn run() {
+    let explainer = AIExplainer::new();
+    if !explainer.uses_openai() {
+        return;
+    }
+
+    let mut items = Vec::new();
+    {
+        let mut last_signals = LAST_SIGNALS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap();
+        for symbol in config::get().supported_symbols() {
+            let Some(price_data) = prices::cached_price(&symbol) else {
+                continue;
+            };
+            let (signal, _) = generate_signal(&price_data);
+            let changed =
+                last_signals.insert(symbol.clone(), signal.clone()).as_ref() != Some(&signal);
+            if ai_explanation::is_cached(&symbol, &signal, price_data.price, Verbosity::Normal) {
+                continue;
+            }
+            items.push((changed, signal, price_data));
+        }
+    }
+    // Stable sort keeps the configured coin order within each group
+    items.sort_by_key(|(changed, _, _)| !changed);
+
+    let count = items.len();
+    if count == 0 {
+        return;
+    }
+
+    stream::iter(items)
+        .for_each_concurrent(
+            config::get().explanation_prewarm_concurrency,
+            |(_, signal, price_data)| {
+                let explainer = &explainer;
+                async move {
+                    let reasons = signal_reasons(&price_data);
+                    explainer
+                        .explain_signal(
+                            &price_data.symbol,
+                            &signal,
+                            price_data.price,
+                            price_data.change_24h,
+                            &reasons,
+                            Verbosity::Normal,
+                        )
+                        .await;
+                }
+            },
+        )
+        .await;
+    println!("🔥 Pre-warmed {} explanations", count);
+}
@@ -0,0 +1,405 @@
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::coingecko::{self, FetchError, PriceData, PriceSource, SourceType};
+use crate::config;
+use crate::history;
+
+// Cache ages use the monotonic clock so a wall-clock correction can't make an
+// entry look negative-aged or expire it early.
+type PriceCache = HashMap<String, (PriceData, Instant)>;
+type InFlightFetch = Shared<BoxFuture<'static, Result<PriceData, String>>>;
+
+static PRICE_CACHE: OnceLock<Arc<Mutex<PriceCache>>> = OnceLock::new();
+static LAST_SUCCESSFUL_REFRESH: OnceLock<Mutex<Instant>> = OnceLock::new();
+// Bumped on every cache update so streams can wake and read the latest prices
+static UPDATES: OnceLock<watch::Sender<u64>> = OnceLock::new();
+// Upstream fetches currently running, shared by every caller asking for the same symbol
+static IN_FLIGHT: OnceLock<Mutex<HashMap<String, InFlightFetch>>> = OnceLock::new();
+// Callers that joined an in-flight fetch instead of starting their own
+static COALESCED_FETCHES: AtomicU64 = AtomicU64::new(0);
+// Upstream prices held back by the outlier guard, waiting for the next refresh to confirm them
+static PENDING_OUTLIERS: OnceLock<Mutex<HashMap<String, f64>>> = OnceLock::new();
+// Consecutive successful responses that did not include the coin
+static MISSING_COUNTS: OnceLock<Mutex<HashMap<String, u32>>> = OnceLock::new();
+
+fn cache() -> &'static Arc<Mutex<PriceCache>> {
+    PRICE_CACHE.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
+}
+
+// Serves the cached price while it is younger than the refresh interval,
+// otherwise fetches from CoinGecko.
+pub async fn fetch_live_price(symbol: &str) -> Result<PriceData, String> {
+    let symbol_upper = symbol.to_uppercase();
+
+    let max_age = Duration::from_secs(config::get().refresh_interval_seconds);
+    if let Some(data) = fresh_cached(&symbol_upper, max_age) {
+        return Ok(data);
+    }
+
+    refresh_price(&symbol_upper).await
+}
+
+// ?no_cache=true: goes upstream unless the cached price is younger than
+// FORCED_FETCH_MIN_INTERVAL_SECONDS, so forced requests can't hammer CoinGecko.
+// The bool says whether an upstream fetch actually happened.
+pub async fn fetch_price_forced(symbol: &str) -> Result<(PriceData, bool), String> {
+    let symbol_upper = symbol.to_uppercase();
+    let min_interval = Duration::from_secs(config::get().forced_fetch_min_interval_seconds);
+
+    if let Some(data) = fresh_cached(&symbol_upper, min_interval) {
+        return Ok((data, false));
+    }
+
+    refresh_price(&symbol_upper).await.map(|data| (data, true))
+}
+
+// The cached price, marked as such, while it is younger than `max_age`. The age comes
+// from the monotonic clock, never from the price's wall-clock timestamp.
+fn fresh_cached(symbol_upper: &str, max_age: Duration) -> Option<PriceData> {
+    let cache_lock = cache().lock().unwrap();
+    let (data, fetched_at) = cache_lock.get(symbol_upper)?;
+    if fetched_at.elapsed() >= max_age {
+        return None;
+    }
+    let mut data = data.clone();
+    data.source = PriceSource::Cache;
+    Some(data)
+}
+
+// Always goes upstream and updates the cache on success. With SINGLE_FLIGHT_FETCHES,
+// concurrent calls for the same symbol share one upstream request and its result.
+pub async fn refresh_price(symbol: &str) -> Result<PriceData, String> {
+    let symbol_upper = symbol.to_uppercase();
+    if !config::get().single_flight_fetches {
+        return fetch_and_store(symbol_upper).await;
+    }
+
+    let fetch_symbol = symbol_upper.clone();
+    coalesce(&symbol_upper, move || fetch_and_store(fetch_symbol).boxed()).await
+}
+
+// Joins the fetch already running for the symbol, or starts one with `fetch`
+async fn coalesce<F>(symbol_upper: &str, fetch: F) -> Result<PriceData, String>
+where
+    F: FnOnce() -> BoxFuture<'static, Result<PriceData, String>>,
+{
+    let fetch = {
+        let mut in_flight = IN_FLIGHT
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap();
+        match in_flight.get(symbol_upper) {
+            Some(fetch) => {
+                COALESCED_FETCHES.fetch_add(1, Ordering::Relaxed);
+                fetch.clone()
+            }
+            None => {
+                let fetch = fetch().shared();
+                in_flight.insert(symbol_upper.to_string(), fetch.clone());
+                fetch
+            }
+        }
+    };
+
+    let result = fetch.clone().await;
+
+    // Whoever finishes first clears the entry; a newer fetch for the symbol stays
+    let mut in_flight = IN_FLIGHT.get().unwrap().lock().unwrap();
+    if in_flight
+        .get(symbol_upper)
+        .is_some_and(|current| current.ptr_eq(&fetch))
+    {
+        in_flight.remove(symbol_upper);
+    }
+
+    result
+}
+
+pub fn coalesced_fetches() -> u64 {
+    COALESCED_FETCHES.load(Ordering::Relaxed)
+}
+
+async fn fetch_and_store(symbol_upper: String) -> Result<PriceData, String> {
+    let config = config::get();
+
+    let coin_id = config
+        .coingecko_id(&symbol_upper)
+        .ok_or_else(|| format!("Unknown symbol: {}", symbol_upper))?;
+
+    let mut price_data =
+        match coingecko::fetch_price(config.coingecko_endpoint_for(&symbol_upper), &symbol_upper, &coin_id).await {
+            Ok(price_data) => price_data,
+            Err(FetchError::Missing(msg)) => {
+                record_missing(&symbol_upper, &coin_id);
+                return Err(msg);
+            }
+            Err(FetchError::Failed(msg)) => return Err(msg),
+        };
+    record_present(&symbol_upper);
+    reject_outlier(&mut price_data);
+    history::record(&symbol_upper, price_data.timestamp, price_data.price);
+
+    cache()
+        .lock()
+        .unwrap()
+        .insert(symbol_upper, (price_data.clone(), Instant::now()));
+    record_successful_refresh();
+    updates().send_modify(|generation| *generation += 1);
+
+    Ok(price_data)
+}
+
+// Caches the price as if it had just been fetched, for tests that mustn't go upstream
+#[cfg(test)]
+pub fn insert_cached(price_data: PriceData) {
+    cache()
+        .lock()
+        .unwrap()
+        .insert(price_data.symbol.to_uppercase(), (price_data, Instant::now()));
+}
+
+// Last fetched price regardless of age, without going upstream.
+pub fn cached_price(symbol: &str) -> Option<PriceData> {
+    cache()
+        .lock()
+        .unwrap()
+        .get(&symbol.to_uppercase())
+        .map(|(data, _)| data.clone())
+}
+
+fn updates() -> &'static watch::Sender<u64> {
+    UPDATES.get_or_init(|| watch::channel(0).0)
+}
+
+// A receiver only keeps the latest generation, so a slow consumer skips straight
+// to the current prices instead of queueing every update.
+pub fn subscribe_updates() -> watch::Receiver<u64> {
+    updates().subscribe()
+}
+
+// Age of the least recently fetched cached price
+pub fn oldest_cache_age() -> Option<Duration> {
+    cache()
+        .lock()
+        .unwrap()
+        .values()
+        .map(|(_, fetched_at)| fetched_at.elapsed())
+        .max()
+}
+
+pub fn clear_cache() {
+    cache().lock().unwrap().clear();
+}
+
+pub fn cached_symbols() -> Vec<String> {
+    cache().lock().unwrap().keys().cloned().collect()
+}
+
+// Drops the cached price and per-coin refresh state; true if a price was cached
+pub fn forget(symbol: &str) -> bool {
+    let symbol = symbol.to_uppercase();
+    if let Some(pending) = PENDING_OUTLIERS.get() {
+        pending.lock().unwrap().remove(&symbol);
+    }
+    missing_counts().lock().unwrap().remove(&symbol);
+    cache().lock().unwrap().remove(&symbol).is_some()
+}
+
+fn last_refresh() -> &'static Mutex<Instant> {
+    // Seeded with the startup time so a fresh instance is not immediately stale
+    LAST_SUCCESSFUL_REFRESH.get_or_init(|| Mutex::new(Instant::now()))
+}
+
+pub fn record_successful_refresh() {
+    *last_refresh().lock().unwrap() = Instant::now();
+}
+
+pub fn time_since_successful_refresh() -> Duration {
+    last_refresh().lock().unwrap().elapsed()
+}
+
+fn percent_change(from: f64, to: f64) -> f64 {
+    if from == 0.0 {
+        return 0.0;
+    }
+    (to - from) / from * 100.0
+}
+
+// PRICE_OUTLIER_PERCENT guard: a price that jumps further than the threshold from the
+// one being served is held back for one refresh. If the next refresh lands near it
+// the move is real and accepted; otherwise the previous price keeps being served.
+fn reject_outlier(price_data: &mut PriceData) {
+    let threshold = config::get().price_outlier_percent;
+    if threshold <= 0.0 {
+        return;
+    }
+
+    let mut pending = PENDING_OUTLIERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap();
+    let served = cached_price(&price_data.symbol).map(|p| p.price);
+    let raw = price_data.price;
+
+    let jump = match served {
+        Some(served) => percent_change(served, raw),
+        None => 0.0,
+    };
+    if jump.abs() <= threshold {
+        pending.remove(&price_data.symbol);
+        return;
+    }
+
+    let confirmed = pending
+        .remove(&price_data.symbol)
+        .is_some_and(|held| percent_change(held, raw).abs() <= threshold);
+    if confirmed {
+        println!("📈 {} move of {:+.2}% confirmed, serving {}", price_data.symbol, jump, raw);
+        return;
+    }
+
+    let served = served.unwrap_or(raw);
+    println!(
+        "🧪 Holding back {} price {} ({:+.2}% from {}), over PRICE_OUTLIER_PERCENT={}",
+        price_data.symbol, raw, jump, served, threshold
+    );
+    pending.insert(price_data.symbol.clone(), raw);
+    price_data.price = served;
+    price_data.raw_price = Some(raw);
+    price_data.source_type = SourceType::Fallback;
+    price_data.adjustment = Some(format!(
+        "outlier_rejected: upstream moved {:+.2}% (limit {}%), previous price served until the next refresh confirms it",
+        jump, threshold
+    ));
+}
+
+fn missing_counts() -> &'static Mutex<HashMap<String, u32>> {
+    MISSING_COUNTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn record_missing(symbol: &str, coin_id: &str) {
+    let threshold = config::get().delisted_after_refreshes;
+    let mut counts = missing_counts().lock().unwrap();
+    let count = counts.entry(symbol.to_string()).or_insert(0);
+    *count += 1;
+
+    if *count == threshold {
+        println!("🚨🚨🚨 ==================================================");
+        println!(
+            "🚨 {} (CoinGecko id '{}') missing from the last {} CoinGecko responses.",
+            symbol, coin_id, threshold
+        );
+        println!("🚨 It may have been delisted or renamed - check SUPPORTED_COINS.");
+        println!("🚨🚨🚨 ==================================================");
+    }
+}
+
+fn record_present(symbol: &str) {
+    if let Some(count) = missing_counts().lock().unwrap().remove(symbol) {
+        if count >= config::get().delisted_after_refreshes {
+            println!("✅ {} is back in CoinGecko responses", symbol);
+        }
+    }
+}
+
+// Consecutive refreshes the coin has been missing from CoinGecko's response
+pub fn missing_refreshes(symbol: &str) -> u32 {
+    missing_counts()
+        .lock()
+        .unwrap()
+        .get(&symbol.to_uppercase())
+        .copied()
+        .unwrap_or(0)
+}
+
+pub fn is_delisted(symbol: &str) -> bool {
+    missing_counts()
+        .lock()
+        .unwrap()
+        .get(&symbol.to_uppercase())
+        .is_some_and(|count| *count >= config::get().delisted_after_refreshes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::price_data;
+    use std::sync::atomic::AtomicUsize;
+
+    // An upstream fetch that counts its calls and takes long enough for every caller to join
+    fn counting_fetch(
+        calls: &Arc<AtomicUsize>,
+        symbol: &str,
+    ) -> impl FnOnce() -> BoxFuture<'static, Result<PriceData, String>> {
+        let calls = calls.clone();
+        let data = price_data(symbol, 42.0, 0.0);
+        move || {
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(data)
+            }
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_misses_share_one_upstream_fetch() {
+        const CALLERS: usize = 8;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let coalesced_before = coalesced_fetches();
+
+        let results = futures_util::future::join_all(
+            (0..CALLERS).map(|_| coalesce("SINGLEFLIGHT", counting_fetch(&calls, "SINGLEFLIGHT"))),
+        )
+        .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(coalesced_fetches() - coalesced_before, CALLERS as u64 - 1);
+        assert!(results.iter().all(|r| r.as_ref().is_ok_and(|p| p.price == 42.0)));
+        // Finished fetches don't linger, so the next miss goes upstream again
+        assert!(!IN_FLIGHT.get().unwrap().lock().unwrap().contains_key("SINGLEFLIGHT"));
+        coalesce("SINGLEFLIGHT", counting_fetch(&calls, "SINGLEFLIGHT")).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn different_symbols_fetch_separately() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (a, b) = tokio::join!(
+            coalesce("SINGLEFLIGHT_A", counting_fetch(&calls, "SINGLEFLIGHT_A")),
+            coalesce("SINGLEFLIGHT_B", counting_fetch(&calls, "SINGLEFLIGHT_B")),
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(a.unwrap().symbol, "SINGLEFLIGHT_A");
+        assert_eq!(b.unwrap().symbol, "SINGLEFLIGHT_B");
+    }
+
+    #[test]
+    fn cache_age_ignores_a_backward_wall_clock_jump() {
+        // Fetched just now, but the wall clock has since been set back an hour, so the
+        // price's timestamp lies in the future
+        let mut jumped = price_data("CLOCKJUMP", 10.0, 0.0);
+        jumped.timestamp = chrono::Utc::now().timestamp() + 3600;
+        cache().lock().unwrap().insert("CLOCKJUMP".to_string(), (jumped, Instant::now()));
+
+        let served = fresh_cached("CLOCKJUMP", Duration::from_secs(60)).expect("still fresh");
+        assert_eq!(served.source, PriceSource::Cache);
+        assert!(oldest_cache_age().is_some_and(|age| age < Duration::from_secs(3600)));
+
+        // Fetched two minutes ago by the monotonic clock: expired, whatever the timestamp says
+        let mut stale = price_data("CLOCKJUMP_OLD", 10.0, 0.0);
+        stale.timestamp = chrono::Utc::now().timestamp() + 3600;
+        let two_minutes_ago = Instant::now() - Duration::from_secs(120);
+        cache().lock().unwrap().insert("CLOCKJUMP_OLD".to_string(), (stale, two_minutes_ago));
+        assert!(fresh_cached("CLOCKJUMP_OLD", Duration::from_secs(60)).is_none());
+
+        forget("CLOCKJUMP");
+        forget("CLOCKJUMP_OLD");
+    }
+}
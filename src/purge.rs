@@ -0,0 +1,141 @@
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::audit;
+use crate::config;
+use crate::history;
+use crate::prewarm;
+use crate::prices;
+use crate::routes::ai_explanation;
+use crate::scheduler;
+use crate::signal_tracker;
+use crate::watch_rules;
+
+// Symbols that have state but are no longer tracked, and since when
+static UNTRACKED: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+
+// SYMBOL_PURGE: drops the cached price, history, signal state and explanations of
+// coins that left the tracked set (e.g. through TRACK_TOP_N churn), once they have
+// been gone for SYMBOL_PURGE_GRACE_SECONDS. A coin that comes back in time keeps
+// everything.
+pub fn start() {
+    let config = config::get();
+    if !config.symbol_purge {
+        return;
+    }
+    let grace = Duration::from_secs(config.symbol_purge_grace_seconds);
+    let check_every = (grace / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+
+    scheduler::register("symbol_purge", check_every, move || async move {
+        run(grace);
+        Ok(())
+    });
+}
+
+fn run(grace: Duration) {
+    let tracked: HashSet<String> = config::get().supported_symbols().into_iter().collect();
+    let held: HashSet<String> = prices::cached_symbols()
+        .into_iter()
+        .chain(history::symbols())
+        .chain(signal_tracker::symbols())
+        .filter(|symbol| !tracked.contains(symbol))
+        .collect();
+    sweep(held, grace);
+}
+
+// `held`: every untracked symbol that still has state
+fn sweep(held: HashSet<String>, grace: Duration) {
+    let mut untracked = UNTRACKED
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap();
+    // Back in the tracked set, or already gone
+    untracked.retain(|symbol, _| held.contains(symbol));
+    for symbol in held {
+        untracked.entry(symbol).or_insert_with(Instant::now);
+    }
+
+    let due: Vec<(String, Duration)> = untracked
+        .iter()
+        .filter(|(_, since)| since.elapsed() >= grace)
+        .map(|(symbol, since)| (symbol.clone(), since.elapsed()))
+        .collect();
+    for (symbol, untracked_for) in due {
+        untracked.remove(&symbol);
+        purge(&symbol, untracked_for);
+    }
+}
+
+fn purge(symbol: &str, untracked_for: Duration) {
+    let had_price = prices::forget(symbol);
+    let samples = history::forget(symbol);
+    let changes = signal_tracker::forget(symbol);
+    let explanations = ai_explanation::forget(symbol);
+    prewarm::forget(symbol);
+    watch_rules::forget(symbol);
+
+    println!(
+        "🧹 Purged {} after {}s untracked: {} history samples, {} signal changes, {} explanations",
+        symbol,
+        untracked_for.as_secs(),
+        samples,
+        changes,
+        explanations
+    );
+    audit::record(
+        "symbol_purged",
+        symbol,
+        json!({
+            "untracked_seconds": untracked_for.as_secs(),
+            "cached_price": had_price,
+            "history_samples": samples,
+            "signal_changes": changes,
+            "explanations": explanations,
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::init_config;
+
+    const SYMBOL: &str = "PURGETEST";
+
+    fn held(symbol: &str) -> HashSet<String> {
+        HashSet::from([symbol.to_string()])
+    }
+
+    // One test, as every sweep also resets the timers of symbols it isn't given
+    #[test]
+    fn a_removed_symbol_is_purged_after_the_grace_period() {
+        init_config();
+        let grace = Duration::from_millis(100);
+        // Added: tracked, so none of its state counts as untracked
+        for n in 0..3 {
+            history::record(SYMBOL, 1_700_000_000 + n, 1.0 + n as f64);
+        }
+        signal_tracker::observe(SYMBOL, "buy", 0.5, 1.0, 1_700_000_000);
+        sweep(HashSet::new(), grace);
+        assert_eq!(history::len(SYMBOL), 3);
+
+        // Removed, then back within the grace period: the clock starts over
+        sweep(held(SYMBOL), grace);
+        std::thread::sleep(grace / 2);
+        sweep(HashSet::new(), grace);
+        sweep(held(SYMBOL), grace);
+        std::thread::sleep(grace / 2);
+        sweep(held(SYMBOL), grace);
+        assert_eq!(history::len(SYMBOL), 3);
+        assert!(signal_tracker::symbols().contains(&SYMBOL.to_string()));
+
+        // Removed for the whole grace period
+        std::thread::sleep(grace);
+        sweep(held(SYMBOL), grace);
+        assert_eq!(history::len(SYMBOL), 0);
+        assert!(!signal_tracker::symbols().contains(&SYMBOL.to_string()));
+        assert!(audit::events().iter().any(|e| e.event == "symbol_purged" && e.symbol == SYMBOL));
+    }
+}
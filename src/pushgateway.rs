@@ -0,0 +1,67 @@
+use reqwest::Url;
+use std::time::Duration;
+
+use crate::config;
+use crate::routes::metrics;
+use crate::scheduler;
+
+// PUSHGATEWAY_URL: pushes everything /metrics serves to a Prometheus Pushgateway every
+// PUSHGATEWAY_INTERVAL_SECONDS, for deployments Prometheus can't reach to scrape. Each
+// push replaces the previous one for the same job and instance. A failed push is
+// logged and shows on /diagnostics; the next one goes ahead as scheduled.
+pub fn start() {
+    let config = config::get();
+    let Some(base) = &config.pushgateway_url else {
+        return;
+    };
+    let url = match group_url(base, &config.pushgateway_job, &config.pushgateway_instance) {
+        Ok(url) => url,
+        Err(e) => {
+            println!("❌ Metrics push disabled: {}", e);
+            return;
+        }
+    };
+    let interval = Duration::from_secs(config.pushgateway_interval_seconds);
+    // Host only: the URL may carry basic auth credentials
+    println!(
+        "📤 Pushing metrics to {} every {}s",
+        url.host_str().unwrap_or_default(),
+        interval.as_secs()
+    );
+
+    scheduler::register("metrics_push", interval, move || {
+        let url = url.clone();
+        async move { push(url, interval).await }
+    });
+}
+
+// <base>/metrics/job/<job>/instance/<instance>
+fn group_url(base: &str, job: &str, instance: &str) -> Result<Url, String> {
+    let mut url = Url::parse(base).map_err(|e| format!("Invalid PUSHGATEWAY_URL {}: {}", base, e))?;
+    url.path_segments_mut()
+        .map_err(|_| format!("Invalid PUSHGATEWAY_URL {}: not a base URL", base))?
+        .pop_if_empty()
+        .extend(["metrics", "job", job, "instance", instance]);
+    Ok(url)
+}
+
+async fn push(url: Url, interval: Duration) -> Result<(), String> {
+    // A push still outstanding when the next is due has nothing left to add
+    let client = reqwest::Client::builder()
+        .timeout(interval.min(Duration::from_secs(10)))
+        .build()
+        .map_err(|e| format!("Client error: {}", e))?;
+    let response = client
+        .put(url)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .body(metrics::render())
+        .send()
+        .await
+        .map_err(|e| format!("Pushgateway unreachable: {}", e.without_url()))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!("Pushgateway answered {}: {}", status, detail.trim()));
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::coingecko::{PriceData, PriceSource, SourceType};
+use crate::config;
+use crate::history;
+use crate::prices;
+
+// Factors the data-quality score is built from, in the order they are reported.
+pub const FACTORS: &[&str] = &["staleness", "source", "upstream", "warmup", "outlier"];
+
+// DATA_QUALITY_WEIGHTS: how much each factor can take off the 0-100 score. Only the
+// ratios matter; the defaults add up to 100 so each reads as points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityWeights {
+    pub staleness: f64,
+    pub source: f64,
+    pub upstream: f64,
+    pub warmup: f64,
+    pub outlier: f64,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self {
+            staleness: 30.0,
+            source: 20.0,
+            upstream: 20.0,
+            warmup: 15.0,
+            outlier: 15.0,
+        }
+    }
+}
+
+impl QualityWeights {
+    pub fn set(&mut self, factor: &str, weight: f64) -> Result<(), String> {
+        let slot = match factor.to_lowercase().as_str() {
+            "staleness" => &mut self.staleness,
+            "source" => &mut self.source,
+            "upstream" => &mut self.upstream,
+            "warmup" => &mut self.warmup,
+            "outlier" => &mut self.outlier,
+            other => {
+                return Err(format!(
+                    "Unknown DATA_QUALITY_WEIGHTS factor: {}. Use {}.",
+                    other,
+                    FACTORS.join(", ")
+                ))
+            }
+        };
+        *slot = weight;
+        Ok(())
+    }
+
+    fn get(&self, factor: &str) -> f64 {
+        match factor {
+            "staleness" => self.staleness,
+            "source" => self.source,
+            "upstream" => self.upstream,
+            "warmup" => self.warmup,
+            _ => self.outlier,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        FACTORS.iter().map(|factor| self.get(factor)).sum()
+    }
+}
+
+// One factor's share of the score: penalty runs from 0 (fine) to 1 (worst)
+struct Factor {
+    name: &'static str,
+    penalty: f64,
+    detail: String,
+}
+
+// Price age beyond one refresh interval, reaching full penalty at
+// STALE_DATA_ALERT_SECONDS (or ten intervals when that alert is off)
+fn staleness(price_data: &PriceData) -> Factor {
+    let config = config::get();
+    let age = (Utc::now().timestamp() - price_data.timestamp).max(0) as f64;
+    let fresh = config.refresh_interval_seconds as f64;
+    let stale = match config.stale_data_alert_seconds {
+        0 => fresh * 10.0,
+        seconds => (seconds as f64).max(fresh * 2.0),
+    };
+    Factor {
+        name: "staleness",
+        penalty: ((age - fresh) / (stale - fresh)).clamp(0.0, 1.0),
+        detail: format!("price is {}s old", age as i64),
+    }
+}
+
+// A placeholder or a fallback price; an outlier hold is scored by its own factor
+fn source(price_data: &PriceData) -> Factor {
+    let (penalty, detail) = match (price_data.source, price_data.source_type) {
+        (PriceSource::None, _) => (1.0, "no price available"),
+        (_, SourceType::Fallback) if price_data.adjustment.is_none() => {
+            (1.0, "served from fallback")
+        }
+        _ => (0.0, "live"),
+    };
+    Factor {
+        name: "source",
+        penalty,
+        detail: detail.to_string(),
+    }
+}
+
+// Consecutive refreshes the coin was missing from CoinGecko's response,
+// up to DELISTED_AFTER_REFRESHES
+fn upstream(symbol: &str) -> Factor {
+    let missing = prices::missing_refreshes(symbol);
+    let threshold = config::get().delisted_after_refreshes;
+    Factor {
+        name: "upstream",
+        penalty: (missing as f64 / threshold as f64).min(1.0),
+        detail: format!("missing from {} of the last upstream responses (limit {})", missing, threshold),
+    }
+}
+
+// Share of MIN_HISTORY_SAMPLES still to collect
+fn warmup(symbol: &str) -> Factor {
+    let required = config::get().min_history_samples;
+    let samples = history::len(symbol);
+    let penalty = if history::is_ready(symbol) {
+        0.0
+    } else {
+        1.0 - samples as f64 / required as f64
+    };
+    Factor {
+        name: "warmup",
+        penalty,
+        detail: format!("{} of {} samples", samples, required),
+    }
+}
+
+// The upstream price is currently held back by PRICE_OUTLIER_PERCENT
+fn outlier(price_data: &PriceData) -> Factor {
+    let held = price_data
+        .adjustment
+        .as_deref()
+        .is_some_and(|a| a.starts_with("outlier_rejected"));
+    Factor {
+        name: "outlier",
+        penalty: if held { 1.0 } else { 0.0 },
+        detail: if held { "upstream price held back" } else { "none" }.to_string(),
+    }
+}
+
+fn factors(symbol: &str, price_data: &PriceData) -> Vec<Factor> {
+    vec![
+        staleness(price_data),
+        source(price_data),
+        upstream(symbol),
+        warmup(symbol),
+        outlier(price_data),
+    ]
+}
+
+fn score_of(factors: &[Factor], weights: &QualityWeights) -> u8 {
+    let lost: f64 = factors.iter().map(|f| f.penalty * weights.get(f.name)).sum();
+    (100.0 * (1.0 - lost / weights.total())).round().clamp(0.0, 100.0) as u8
+}
+
+// 0-100, higher is more trustworthy
+pub fn score(symbol: &str, price_data: &PriceData) -> u8 {
+    score_of(&factors(symbol, price_data), &config::get().data_quality_weights)
+}
+
+// The score plus every factor's penalty, weight and the points it cost
+pub fn breakdown(symbol: &str, price_data: &PriceData) -> Value {
+    let weights = &config::get().data_quality_weights;
+    let factors = factors(symbol, price_data);
+    let total = weights.total();
+    let detail: serde_json::Map<String, Value> = factors
+        .iter()
+        .map(|f| {
+            let weight = weights.get(f.name);
+            let points = 100.0 * f.penalty * weight / total;
+            (
+                f.name.to_string(),
+                json!({
+                    "penalty": (f.penalty * 100.0).round() / 100.0,
+                    "weight": weight,
+                    "points_lost": (points * 10.0).round() / 10.0,
+                    "detail": f.detail,
+                }),
+            )
+        })
+        .collect();
+    json!({
+        "score": score_of(&factors, weights),
+        "factors": detail,
+    })
+}
@@ -0,0 +1,163 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::coingecko::{self, PriceData};
+use crate::config;
+use crate::format;
+use crate::history;
+use crate::paper;
+use crate::prewarm;
+use crate::prices;
+use crate::scheduler;
+use crate::signal_tracker;
+use crate::strategy::generate_signal;
+use crate::watch_rules;
+
+// When the last refresh cycle started, scheduled or triggered via /admin/refresh
+static LAST_CYCLE: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
+
+#[derive(Debug, Serialize)]
+pub struct Refreshed {
+    #[serde(serialize_with = "format::serialize_symbol")]
+    pub symbol: String,
+    pub price: f64,
+    pub signal: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Failed {
+    #[serde(serialize_with = "format::serialize_symbol")]
+    pub symbol: String,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CycleSummary {
+    pub refreshed: Vec<Refreshed>,
+    pub failed: Vec<Failed>,
+}
+
+fn last_cycle() -> &'static Mutex<Option<Instant>> {
+    LAST_CYCLE.get_or_init(|| Mutex::new(None))
+}
+
+// Keeps the price cache warm so requests rarely wait on CoinGecko.
+pub fn start() {
+    let interval = Duration::from_secs(config::get().refresh_interval_seconds);
+    scheduler::register("price_refresh", interval, run_cycle);
+}
+
+async fn run_cycle() -> Result<(), String> {
+    *last_cycle().lock().unwrap() = Some(Instant::now());
+    let summary = refresh_all().await;
+
+    if summary.failed.is_empty() {
+        Ok(())
+    } else {
+        let failed: Vec<String> = summary
+            .failed
+            .iter()
+            .map(|f| format!("{} ({})", f.symbol, f.error))
+            .collect();
+        Err(format!(
+            "refreshed {}/{} prices, failed: {}",
+            summary.refreshed.len(),
+            summary.refreshed.len() + failed.len(),
+            failed.join(", ")
+        ))
+    }
+}
+
+// Runs the per-coin hooks for a freshly refreshed price and returns its signal. A coin
+// still short of MIN_HISTORY_SAMPLES has no valid signal yet, so nothing is observed or
+// traded on until it is `ready`; price rules only need the price.
+pub fn on_refreshed(symbol: &str, price: &PriceData, ready: bool) -> String {
+    watch_rules::evaluate(price);
+    if !ready {
+        return "insufficient_data".to_string();
+    }
+    let (signal, confidence) = generate_signal(price);
+    signal_tracker::observe(symbol, &signal, confidence, price.price, price.timestamp);
+    watch_rules::check_confidence_drop(price, &signal, confidence);
+    paper::on_price(price);
+    signal
+}
+
+async fn refresh_all() -> CycleSummary {
+    let mut summary = CycleSummary::default();
+
+    for symbol in &config::get().supported_symbols() {
+        match prices::refresh_price(symbol).await {
+            Ok(price) => {
+                let signal = on_refreshed(symbol, &price, history::is_ready(symbol));
+                summary.refreshed.push(Refreshed {
+                    symbol: symbol.clone(),
+                    price: price.price,
+                    signal,
+                });
+            }
+            Err(error) => summary.failed.push(Failed {
+                symbol: symbol.clone(),
+                error,
+            }),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    prewarm::trigger();
+
+    summary
+}
+
+// POST /admin/refresh: one cycle now, through the same path as the background
+// refresher. Refused with the seconds to wait while the last cycle of either kind
+// started less than the minimum safe refresh interval ago, so manual refreshes
+// can't push the service past the CoinGecko budget.
+pub async fn refresh_now() -> Result<CycleSummary, u64> {
+    let min_interval = Duration::from_secs(config::get().min_refresh_interval_seconds);
+    {
+        let mut last = last_cycle().lock().unwrap();
+        if let Some(since) = last.map(|at| at.elapsed()) {
+            if since < min_interval {
+                return Err((min_interval - since).as_secs().max(1));
+            }
+        }
+        *last = Some(Instant::now());
+    }
+    println!("🔄 Manual refresh triggered");
+    Ok(refresh_all().await)
+}
+
+// Seconds until prices are next refreshed: the refresher's next run, or with
+// BACKGROUND_REFRESH off, when the oldest cached price expires and the next request
+// fetches it. Zero or negative when that is overdue; None before anything is cached.
+pub fn next_refresh_in_seconds() -> Option<i64> {
+    if let Some(next_run) = scheduler::next_run("price_refresh") {
+        return Some(next_run - Utc::now().timestamp());
+    }
+    let interval = config::get().refresh_interval_seconds as i64;
+    prices::oldest_cache_age().map(|age| interval - age.as_secs() as i64)
+}
+
+// One-off backfill so indicators and the MIN_HISTORY_SAMPLES gate don't have to wait
+// for the refresher to accumulate samples after a deploy.
+pub fn seed_history() {
+    let days = config::get().history_seed_days;
+    if days == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        for coin in &config::get().coins() {
+            match coingecko::fetch_market_chart(&coin.coingecko_id, days).await {
+                Ok(samples) => {
+                    println!("📚 Seeded {} history with {} samples", coin.symbol, samples.len());
+                    history::seed(&coin.symbol, samples);
+                }
+                Err(e) => println!("⚠️ Failed to seed {} history: {}", coin.symbol, e),
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    });
+}
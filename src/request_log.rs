@@ -0,0 +1,88 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use chrono::Utc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::config;
+
+// Requests seen so far; the n-th request's sampling decision depends only on n and the seed
+static SEEN: AtomicU64 = AtomicU64::new(0);
+static SEED: OnceLock<u64> = OnceLock::new();
+
+fn seed() -> u64 {
+    *SEED.get_or_init(|| {
+        config::get()
+            .request_log_seed
+            .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64)
+    })
+}
+
+// splitmix64: spreads consecutive counters evenly, so 1 in N is sampled without a
+// fixed stride that could line up with a client's request pattern
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+// Whether the n-th request (from 0) is logged in detail at a rate of 1 in `rate`
+fn sampled(n: u64, seed: u64, rate: u64) -> bool {
+    mix(seed ^ n).is_multiple_of(rate)
+}
+
+// Middleware: REQUEST_LOG_SAMPLE_RATE logs 1 in N requests in detail, and every request
+// that ends in an error whatever the sample says. The same REQUEST_LOG_SEED picks the
+// same requests (by arrival order) on every run.
+pub async fn log_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let rate = config::get().request_log_sample_rate;
+    if rate == 0 {
+        return next.call(req).await;
+    }
+    let n = SEEN.fetch_add(1, Ordering::Relaxed);
+    let sample = sampled(n, seed(), rate);
+
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let target = match req.query_string() {
+        "" => req.path().to_string(),
+        query => format!("{}?{}", req.path(), query),
+    };
+    let client = req
+        .connection_info()
+        .realip_remote_addr()
+        .unwrap_or("unknown")
+        .to_string();
+    let agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-")
+        .to_string();
+
+    let response = next.call(req).await;
+    let elapsed = started.elapsed().as_millis();
+    let status = match &response {
+        Ok(response) => response.status().as_u16(),
+        Err(e) => e.as_response_error().status_code().as_u16(),
+    };
+    if status >= 400 {
+        println!(
+            "❗ #{} {} {} -> {} in {}ms (client {}, agent \"{}\")",
+            n, method, target, status, elapsed, client, agent
+        );
+    } else if sample {
+        println!(
+            "📝 #{} {} {} -> {} in {}ms (client {}, agent \"{}\", 1 in {})",
+            n, method, target, status, elapsed, client, agent, rate
+        );
+    }
+    response
+}
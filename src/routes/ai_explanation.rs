@@ -1,7 +1,262 @@
+use chrono::{NaiveDate, Utc};
+use futures_util::future::{BoxFuture, FutureExt, Shared};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::config;
+use crate::format;
+use crate::latency;
+use crate::liquidity;
+
+// Appended to a coin's explanation inside one of LOW_LIQUIDITY_WINDOWS
+const LOW_LIQUIDITY_NOTE: &str = " Liquidity is typically thin at this time, so this signal is less reliable than usual.";
+
+// (symbol, signal, price bucket, verbosity, format the model was asked for)
+type CacheKey = (String, String, i64, Verbosity, TextFormat);
+type InFlightExplanation = Shared<BoxFuture<'static, SignalExplanation>>;
+
+static EXPLANATION_CACHE: OnceLock<Mutex<ExplanationCache>> = OnceLock::new();
+// Model explanations being generated, shared by every request for the same cache key
+static IN_FLIGHT: OnceLock<Mutex<HashMap<CacheKey, InFlightExplanation>>> = OnceLock::new();
+// Requests that joined an in-flight explanation instead of calling the model themselves
+static COALESCED_EXPLANATIONS: AtomicU64 = AtomicU64::new(0);
+static OPENAI_SEMAPHORE: OnceLock<Semaphore> = OnceLock::new();
+static OPENAI_BUDGET: OnceLock<Mutex<(NaiveDate, u32)>> = OnceLock::new();
+// Per provider: when it last rejected its key (401), and whether that has been logged loudly yet
+static AUTH_FAILURES: OnceLock<Mutex<HashMap<String, (Instant, bool)>>> = OnceLock::new();
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_MODEL: &str = "gpt-4o-mini";
+
+// An OpenAI-compatible chat completions endpoint. Providers are tried in AI_PROVIDERS
+// order, and the template is served once every one has failed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiProvider {
+    pub name: String,
+    pub base_url: String,
+    pub model: String,
+    // Empty for endpoints that don't need one
+    #[serde(skip_serializing)]
+    pub api_key: String,
+}
+
+// AI_PROVIDERS=local,openai reads AI_PROVIDER_<NAME>_BASE_URL, _MODEL and _API_KEY for each
+// name. `openai` falls back to OPENAI_BASE_URL, OPENAI_MODEL and OPENAI_API_KEY. Without
+// AI_PROVIDERS, OpenAI is the only provider, used when OPENAI_API_KEY is set.
+pub fn parse_providers() -> Result<Vec<AiProvider>, String> {
+    let env = |name: &str| std::env::var(name).ok().filter(|s| !s.trim().is_empty());
+    let openai = |base_url: Option<String>, model: Option<String>, api_key: Option<String>| AiProvider {
+        name: "openai".to_string(),
+        base_url: base_url.or_else(|| env("OPENAI_BASE_URL")).unwrap_or_else(|| OPENAI_BASE_URL.to_string()),
+        model: model.or_else(|| env("OPENAI_MODEL")).unwrap_or_else(|| OPENAI_MODEL.to_string()),
+        api_key: api_key.or_else(|| env("OPENAI_API_KEY")).unwrap_or_default(),
+    };
+
+    let Some(raw) = env("AI_PROVIDERS") else {
+        return Ok(env("OPENAI_API_KEY").map(|_| openai(None, None, None)).into_iter().collect());
+    };
+
+    let mut providers: Vec<AiProvider> = Vec::new();
+    for name in raw.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()) {
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Invalid AI_PROVIDERS name '{}': use letters, digits and _", name));
+        }
+        if providers.iter().any(|p| p.name == name) {
+            return Err(format!("AI_PROVIDERS lists '{}' twice", name));
+        }
+        let prefix = format!("AI_PROVIDER_{}", name.to_uppercase());
+        let base_url = env(&format!("{}_BASE_URL", prefix));
+        let model = env(&format!("{}_MODEL", prefix));
+        let api_key = env(&format!("{}_API_KEY", prefix));
+
+        let provider = if name == "openai" {
+            openai(base_url, model, api_key)
+        } else {
+            AiProvider {
+                base_url: base_url.ok_or_else(|| format!("{}_BASE_URL is required", prefix))?,
+                model: model.ok_or_else(|| format!("{}_MODEL is required", prefix))?,
+                api_key: api_key.unwrap_or_default(),
+                name,
+            }
+        };
+        if !provider.base_url.starts_with("http://") && !provider.base_url.starts_with("https://") {
+            return Err(format!("{}_BASE_URL must be an http(s) URL", prefix));
+        }
+        providers.push(provider);
+    }
+    Ok(providers)
+}
+
+// real: call OpenAI when a key is set, falling back to the template on any failure.
+// template: never call OpenAI.
+// deterministic: fixed output derived only from the inputs, for testing the endpoint plumbing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiMode {
+    Real,
+    Template,
+    Deterministic,
+}
+
+impl AiMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "real" => Ok(AiMode::Real),
+            "template" => Ok(AiMode::Template),
+            "deterministic" => Ok(AiMode::Deterministic),
+            other => Err(format!(
+                "Invalid AI_MODE: {}. Use real, template or deterministic.",
+                other
+            )),
+        }
+    }
+}
+
+// How much text an explanation carries. short: just the headline. normal: headline
+// plus the top reasons. detailed: every reason plus the advice line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    Short,
+    #[default]
+    Normal,
+    Detailed,
+}
+
+impl Verbosity {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "short" => Ok(Verbosity::Short),
+            "normal" => Ok(Verbosity::Normal),
+            "detailed" => Ok(Verbosity::Detailed),
+            other => Err(format!(
+                "Invalid verbosity: {}. Use short, normal or detailed.",
+                other
+            )),
+        }
+    }
+
+    // Keeps explanations concise no matter how many indicators fired
+    fn max_reasons(self) -> usize {
+        match self {
+            Verbosity::Short => 0,
+            Verbosity::Normal => 3,
+            Verbosity::Detailed => 6,
+        }
+    }
+
+    fn max_chars(self) -> usize {
+        match self {
+            Verbosity::Short => 120,
+            Verbosity::Normal => 280,
+            Verbosity::Detailed => 600,
+        }
+    }
+
+    fn prompt_length(self) -> &'static str {
+        match self {
+            Verbosity::Short => "In one short sentence",
+            Verbosity::Normal => "In two short sentences",
+            Verbosity::Detailed => "In a short paragraph of four to five sentences that walks through the reasoning",
+        }
+    }
+
+    fn max_tokens(self) -> u32 {
+        match self {
+            Verbosity::Short => 60,
+            Verbosity::Normal => 120,
+            Verbosity::Detailed => 300,
+        }
+    }
+}
+
+// How explanation text is rendered: plain text as generated, Markdown, or HTML with
+// everything generated escaped. Reasons become a list in the latter two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    #[default]
+    Plain,
+    Markdown,
+    Html,
+}
+
+impl TextFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "plain" => Ok(TextFormat::Plain),
+            "markdown" => Ok(TextFormat::Markdown),
+            "html" => Ok(TextFormat::Html),
+            other => Err(format!("Invalid format: {}. Use plain, markdown or html.", other)),
+        }
+    }
+
+    // What the model is asked to write. HTML is rendered from plain text here rather
+    // than written by the model, so nothing it says reaches a page unescaped.
+    fn model_format(self) -> TextFormat {
+        match self {
+            TextFormat::Markdown => TextFormat::Markdown,
+            TextFormat::Plain | TextFormat::Html => TextFormat::Plain,
+        }
+    }
+
+    fn prompt_instruction(self) -> &'static str {
+        match self.model_format() {
+            TextFormat::Markdown => " Format the answer as Markdown, using bold for the key facts; no headings.",
+            _ => "",
+        }
+    }
+
+    pub fn render(self, text: &str, reasons: &[String]) -> String {
+        match self {
+            TextFormat::Plain => text.to_string(),
+            TextFormat::Markdown => {
+                let mut out = text.to_string();
+                if !reasons.is_empty() {
+                    out.push_str("\n\n");
+                    let items: Vec<String> = reasons.iter().map(|r| format!("- {}", r)).collect();
+                    out.push_str(&items.join("\n"));
+                }
+                out
+            }
+            TextFormat::Html => {
+                let mut out = format!("<p>{}</p>", escape_html(text));
+                if !reasons.is_empty() {
+                    out.push_str("<ul>");
+                    for reason in reasons {
+                        out.push_str(&format!("<li>{}</li>", escape_html(reason)));
+                    }
+                    out.push_str("</ul>");
+                }
+                out
+            }
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignalExplanation {
+    #[serde(serialize_with = "format::serialize_symbol")]
     pub symbol: String,
     pub current_signal: String,
     pub explanation: String,
@@ -10,27 +265,538 @@ pub struct SignalExplanation {
     pub vibe: String,
     pub simple_advice: String,
     pub risk_level: String,
+    pub source: String,
+    pub reasons: Vec<String>,
+    // Set while the coin is inside one of LOW_LIQUIDITY_WINDOWS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_liquidity_period: Option<bool>,
+}
+
+// A coin in a group explanation, with what its own explanation would be built from
+#[derive(Debug, Clone)]
+pub struct GroupMember {
+    pub symbol: String,
+    pub price: f64,
+    pub change_24h: f64,
+    pub reasons: Vec<String>,
+}
+
+// One explanation covering every coin that currently has `signal`
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupExplanation {
+    pub signal: String,
+    #[serde(serialize_with = "format::serialize_symbols")]
+    pub symbols: Vec<String>,
+    pub explanation: String,
+    pub emoji: String,
+    pub vibe: String,
+    pub risk_level: String,
+    pub source: String,
+}
+
+// Prices within ~0.5% of each other share a bucket, so small moves reuse an explanation.
+fn price_bucket(price: f64) -> i64 {
+    if price <= 0.0 {
+        return 0;
+    }
+    (price.ln() / 1.005_f64.ln()).floor() as i64
+}
+
+fn cache_key(symbol: &str, signal: &str, price: f64, verbosity: Verbosity, format: TextFormat) -> CacheKey {
+    (symbol.to_string(), signal.to_string(), price_bucket(price), verbosity, format.model_format())
 }
 
+// Model-written explanations, at most EXPLANATION_CACHE_MAX_ENTRIES of them; the least
+// recently used goes first when a new one doesn't fit.
+#[derive(Default)]
+struct ExplanationCache {
+    // Explanation, when it was cached, and the tick it was last used at
+    entries: HashMap<CacheKey, (SignalExplanation, Instant, u64)>,
+    tick: u64,
+    evictions: u64,
+}
+
+impl ExplanationCache {
+    fn get(&mut self, key: &CacheKey, ttl: Duration) -> Option<SignalExplanation> {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.entries.get_mut(key) {
+            Some((explanation, cached_at, used)) if cached_at.elapsed() < ttl => {
+                *used = tick;
+                Some(explanation.clone())
+            }
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, explanation: SignalExplanation) {
+        let config = config::get();
+        let max = config.explanation_cache_max_entries;
+        if !self.entries.contains_key(&key) && self.entries.len() >= max {
+            // Expired entries make room before anything still valid is evicted
+            let ttl = Duration::from_secs(config.explanation_cache_ttl_seconds);
+            self.entries.retain(|_, (_, cached_at, _)| cached_at.elapsed() < ttl);
+            while self.entries.len() >= max {
+                let Some(oldest) = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, (_, _, used))| *used)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, (explanation, Instant::now(), self.tick));
+    }
+}
+
+fn explanation_cache() -> &'static Mutex<ExplanationCache> {
+    EXPLANATION_CACHE.get_or_init(|| Mutex::new(ExplanationCache::default()))
+}
+
+fn cached_explanation(key: &CacheKey) -> Option<SignalExplanation> {
+    let ttl = Duration::from_secs(config::get().explanation_cache_ttl_seconds);
+    explanation_cache().lock().unwrap().get(key, ttl)
+}
+
+// For /cache-stats
+pub fn cache_stats() -> serde_json::Value {
+    let cache = explanation_cache().lock().unwrap();
+    json!({
+        "entries": cache.entries.len(),
+        "max_entries": config::get().explanation_cache_max_entries,
+        "evictions": cache.evictions,
+        "coalesced": COALESCED_EXPLANATIONS.load(Ordering::Relaxed),
+    })
+}
+
+pub fn is_cached(symbol: &str, signal: &str, price: f64, verbosity: Verbosity) -> bool {
+    cached_explanation(&cache_key(symbol, signal, price, verbosity, TextFormat::Plain)).is_some()
+}
+
+// Drops every cached explanation for the symbol; returns how many there were
+pub fn forget(symbol: &str) -> usize {
+    let mut cache = explanation_cache().lock().unwrap();
+    let before = cache.entries.len();
+    cache.entries.retain(|(cached, _, _, _, _), _| !cached.eq_ignore_ascii_case(symbol));
+    before - cache.entries.len()
+}
+
+fn openai_semaphore() -> &'static Semaphore {
+    OPENAI_SEMAPHORE.get_or_init(|| Semaphore::new(config::get().openai_max_concurrency))
+}
+
+// Counts one OpenAI call against the daily budget (UTC day); false once exhausted.
+fn take_openai_budget() -> bool {
+    let limit = config::get().openai_daily_budget;
+    if limit == 0 {
+        return true;
+    }
+
+    let today = Utc::now().date_naive();
+    let mut budget = OPENAI_BUDGET
+        .get_or_init(|| Mutex::new((today, 0)))
+        .lock()
+        .unwrap();
+    if budget.0 != today {
+        *budget = (today, 0);
+    }
+    if budget.1 >= limit {
+        return false;
+    }
+    budget.1 += 1;
+    true
+}
+
+fn auth_failures() -> &'static Mutex<HashMap<String, (Instant, bool)>> {
+    AUTH_FAILURES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn record_auth_failure(provider: &str, status: reqwest::StatusCode) {
+    let cooldown = config::get().openai_auth_cooldown_seconds;
+    let mut failures = auth_failures().lock().unwrap();
+    let logged = failures.get(provider).is_some_and(|(_, logged)| *logged);
+    failures.insert(provider.to_string(), (Instant::now(), true));
+
+    if !logged {
+        println!("🚨🚨🚨 ==================================================");
+        println!("🚨 AI provider '{}' rejected its API key ({}).", provider, status);
+        if cooldown == 0 {
+            println!("🚨 '{}' is skipped until restart.", provider);
+        } else {
+            println!("🚨 '{}' is skipped for {}s.", provider, cooldown);
+        }
+        println!("🚨 Check the key and restart once it is fixed.");
+        println!("🚨🚨🚨 ==================================================");
+    } else {
+        println!("🔑 AI provider '{}' still rejects its API key ({}), skipping again", provider, status);
+    }
+}
+
+// True while a 401 from the provider is within OPENAI_AUTH_COOLDOWN_SECONDS (0 = until restart).
+pub fn auth_failed(provider: &str) -> bool {
+    let cooldown = config::get().openai_auth_cooldown_seconds;
+    auth_failures()
+        .lock()
+        .unwrap()
+        .get(provider)
+        .is_some_and(|(at, _)| cooldown == 0 || at.elapsed() < Duration::from_secs(cooldown))
+}
+
+// For /health: disabled (no provider or not in real mode), auth_failed (every provider
+// rejected its key), or ok.
+pub fn openai_status() -> &'static str {
+    let explainer = AIExplainer::new();
+    if !explainer.uses_openai() {
+        "disabled"
+    } else if explainer.providers.iter().all(|p| auth_failed(&p.name)) {
+        "auth_failed"
+    } else {
+        "ok"
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[derive(Clone)]
 pub struct AIExplainer {
-    api_key: String,
+    providers: Vec<AiProvider>,
+    mode: AiMode,
+    format: TextFormat,
 }
 
 impl AIExplainer {
     pub fn new() -> Self {
         Self {
-            api_key: std::env::var("OPENAI_API_KEY").unwrap_or_default(),
+            providers: config::get().ai_providers.clone(),
+            mode: config::get().ai_mode,
+            format: TextFormat::Plain,
         }
     }
 
+    // Renders every explanation in `format` (plain by default)
+    pub fn with_format(mut self, format: TextFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // Whether explanations can come from a model rather than the template
+    pub fn uses_openai(&self) -> bool {
+        self.mode == AiMode::Real && !self.providers.is_empty()
+    }
+
     pub async fn explain_signal(
         &self,
         symbol: &str,
         signal: &str,
         price: f64,
         change_24h: f64,
+        reasons: &[String],
+        verbosity: Verbosity,
+    ) -> SignalExplanation {
+        let mut explanation = self
+            .explanation_text(symbol, signal, price, change_24h, reasons, verbosity)
+            .await;
+        // The template lists its reasons inline; rendered, they get a list of their own
+        if self.format != TextFormat::Plain && explanation.source == "template" {
+            explanation.explanation = self
+                .template_explanation(symbol, signal, price, change_24h, &[], verbosity)
+                .explanation;
+        }
+        // Added after the cache so a cached explanation doesn't carry the note past the window
+        if self.mode != AiMode::Deterministic && liquidity::is_low_liquidity(symbol) {
+            explanation.explanation.push_str(LOW_LIQUIDITY_NOTE);
+            explanation.confidence *= config::get().low_liquidity_confidence_factor;
+            explanation.low_liquidity_period = Some(true);
+        }
+        if self.format != TextFormat::Plain {
+            explanation.explanation = self.format.render(&explanation.explanation, &explanation.reasons);
+        }
+        explanation
+    }
+
+    async fn explanation_text(
+        &self,
+        symbol: &str,
+        signal: &str,
+        price: f64,
+        change_24h: f64,
+        reasons: &[String],
+        verbosity: Verbosity,
     ) -> SignalExplanation {
-        let (explanation, emoji, vibe, risk_level) = match signal {
+        let reasons = &reasons[..reasons.len().min(verbosity.max_reasons())];
+        
+        match self.mode {
+            AiMode::Deterministic => self.deterministic_explanation(symbol, signal, price, change_24h),
+            AiMode::Template => self.template_explanation(symbol, signal, price, change_24h, reasons, verbosity),
+            AiMode::Real => {
+                let key = cache_key(symbol, signal, price, verbosity, self.format);
+                if let Some(cached) = cached_explanation(&key) {
+                    return cached;
+                }
+                
+                // Stablecoins have nothing to explain beyond the template.
+                if signal == "stable" {
+                    return self.template_explanation(symbol, signal, price, change_24h, reasons, verbosity);
+                }
+                if !config::get().single_flight_explanations {
+                    return self.clone().generate(key, price, change_24h, reasons.to_vec()).await;
+                }
+
+                let generation = {
+                    let mut in_flight = IN_FLIGHT
+                        .get_or_init(|| Mutex::new(HashMap::new()))
+                        .lock()
+                        .unwrap();
+                    match in_flight.get(&key) {
+                        Some(generation) => {
+                            COALESCED_EXPLANATIONS.fetch_add(1, Ordering::Relaxed);
+                            generation.clone()
+                        }
+                        None => {
+                            let generation = self
+                                .clone()
+                                .generate(key.clone(), price, change_24h, reasons.to_vec())
+                                .boxed()
+                                .shared();
+                            in_flight.insert(key.clone(), generation.clone());
+                            generation
+                        }
+                    }
+                };
+
+                let explanation = generation.clone().await;
+
+                // Whoever finishes first clears the entry; a newer generation for the key stays
+                let mut in_flight = IN_FLIGHT.get().unwrap().lock().unwrap();
+                if in_flight.get(&key).is_some_and(|current| current.ptr_eq(&generation)) {
+                    in_flight.remove(&key);
+                }
+                explanation
+            }
+        }
+    }
+
+    // Asks the model for the explanation behind `key` and caches it, or falls back to the
+    // template. Owns everything it needs so concurrent requests can share it
+    // (SINGLE_FLIGHT_EXPLANATIONS).
+    async fn generate(self, key: CacheKey, price: f64, change_24h: f64, reasons: Vec<String>) -> SignalExplanation {
+        let (symbol, signal, verbosity) = (key.0.as_str(), key.1.as_str(), key.3);
+        let mut explanation = self.template_explanation(symbol, signal, price, change_24h, &reasons, verbosity);
+        let facts = if reasons.is_empty() {
+            String::new()
+        } else {
+            format!("Cite these facts specifically: {}. ", reasons.join("; "))
+        };
+        let prompt = format!(
+            "{}, explain a '{}' signal for {} trading at ${:.2} with a 24h change of {:.2}%. \
+             {}Plain language, no financial advice disclaimers.{}",
+            verbosity.prompt_length(), signal, symbol, price, change_24h, facts,
+            self.format.prompt_instruction()
+        );
+        if let Some((text, provider)) = self.model_text(symbol, &prompt, verbosity).await {
+            explanation.explanation = truncate_chars(&text, verbosity.max_chars());
+            explanation.source = provider;
+            explanation_cache().lock().unwrap().insert(key, explanation.clone());
+        }
+        explanation
+    }
+
+    // One explanation for coins sharing a signal, naming each of them. Not cached:
+    // the member list changes too often for a cached answer to be reused.
+    pub async fn explain_group(
+        &self,
+        signal: &str,
+        members: &[GroupMember],
+        verbosity: Verbosity,
+    ) -> GroupExplanation {
+        let mut explanation = self.group_template(signal, members, verbosity);
+        match self.mode {
+            AiMode::Deterministic => {
+                let symbols: Vec<&str> = members.iter().map(|m| m.symbol.as_str()).collect();
+                explanation.explanation = format!("{} signal for {}", signal, symbols.join(", "));
+                explanation.source = "deterministic".to_string();
+            }
+            AiMode::Template => {}
+            AiMode::Real if signal == "stable" => {}
+            AiMode::Real => {
+                let coins: Vec<String> = members
+                    .iter()
+                    .map(|m| {
+                        let mut coin = format!("{} at ${:.2} ({:+.2}% 24h)", m.symbol, m.price, m.change_24h);
+                        if !m.reasons.is_empty() {
+                            coin.push_str(&format!(": {}", m.reasons.join("; ")));
+                        }
+                        coin
+                    })
+                    .collect();
+                let prompt = format!(
+                    "{}, explain why these coins all have a '{}' signal, naming each coin: {}. \
+                     Plain language, no financial advice disclaimers.{}",
+                    verbosity.prompt_length(), signal, coins.join(". "),
+                    self.format.prompt_instruction()
+                );
+                let subject = format!("{} group", signal);
+                if let Some((text, provider)) = self.model_text(&subject, &prompt, verbosity).await {
+                    explanation.explanation = truncate_chars(&text, verbosity.max_chars() * members.len().max(1));
+                    explanation.source = provider;
+                }
+            }
+        }
+        if self.mode != AiMode::Deterministic {
+            let thin: Vec<&str> = members
+                .iter()
+                .map(|m| m.symbol.as_str())
+                .filter(|symbol| liquidity::is_low_liquidity(symbol))
+                .collect();
+            if !thin.is_empty() {
+                explanation.explanation.push_str(&format!(
+                    " {} {} in a low-liquidity period, so {} signal is less reliable.",
+                    thin.join(", "),
+                    if thin.len() == 1 { "is" } else { "are" },
+                    if thin.len() == 1 { "its" } else { "their" }
+                ));
+            }
+        }
+        explanation.explanation = self.format.render(&explanation.explanation, &[]);
+        explanation
+    }
+
+    // Tries each provider in turn; the text and the provider that wrote it, or None once
+    // every provider failed or the daily budget ran out. `subject` is for the log.
+    async fn model_text(&self, subject: &str, prompt: &str, verbosity: Verbosity) -> Option<(String, String)> {
+        for provider in &self.providers {
+            // A rejected key won't start working on its own; skip the round trip.
+            if auth_failed(&provider.name) {
+                continue;
+            }
+            if !take_openai_budget() {
+                println!("💸 OpenAI daily budget exhausted, using template for {}", subject);
+                return None;
+            }
+            let result = {
+                let _permit = openai_semaphore().acquire().await;
+                let budget = Duration::from_millis(config::get().ai_latency_budget_ms);
+                let started = Instant::now();
+                let call = self.provider_explanation(provider, prompt, verbosity);
+                // Dropping the call cancels it, however far the response has got
+                let result = tokio::time::timeout(budget, call).await;
+                latency::observe_ai_call(started.elapsed(), result.is_err());
+                result.unwrap_or_else(|_| {
+                    println!("⏱️ '{}' explanation for {} cut off after {}ms (AI_LATENCY_BUDGET_MS)", provider.name, subject, budget.as_millis());
+                    Err(format!("No answer within {}ms", budget.as_millis()))
+                })
+            };
+            match result {
+                Ok(text) => {
+                    println!("🤖 {} explanation served by '{}' ({})", subject, provider.name, provider.model);
+                    return Some((text, provider.name.clone()));
+                }
+                Err(e) => println!("⚠️ '{}' explanation failed for {}: {}", provider.name, subject, e),
+            }
+        }
+        None
+    }
+
+    async fn provider_explanation(
+        &self,
+        provider: &AiProvider,
+        prompt: &str,
+        verbosity: Verbosity,
+    ) -> Result<String, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .map_err(|e| format!("Client error: {}", e))?;
+        
+        let url = format!("{}/chat/completions", provider.base_url.trim_end_matches('/'));
+        let mut request = client.post(&url);
+        if !provider.api_key.is_empty() {
+            request = request.bearer_auth(&provider.api_key);
+        }
+        let response = request
+            .json(&json!({
+                "model": provider.model,
+                "messages": [
+                    {"role": "system", "content": "You explain crypto trading signals to beginners."},
+                    {"role": "user", "content": prompt}
+                ],
+                "max_tokens": verbosity.max_tokens(),
+                "temperature": 0.7
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+        
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            record_auth_failure(&provider.name, response.status());
+            return Err(format!("API error: {}", response.status()));
+        }
+        if !response.status().is_success() {
+            return Err(format!("API error: {}", response.status()));
+        }
+        
+        let data: serde_json::Value = response.json()
+            .await
+            .map_err(|e| format!("JSON error: {}", e))?;
+        
+        data.pointer("/choices/0/message/content")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| "No content in response".to_string())
+    }
+
+    fn deterministic_explanation(
+        &self,
+        symbol: &str,
+        signal: &str,
+        price: f64,
+        change_24h: f64,
+    ) -> SignalExplanation {
+        SignalExplanation {
+            symbol: symbol.to_string(),
+            current_signal: signal.to_string(),
+            explanation: format!(
+                "{} signal for {} at ${:.2} (24h change {:.2}%)",
+                signal, symbol, price, change_24h
+            ),
+            confidence: 0.5,
+            emoji: "🧪".to_string(),
+            vibe: "Deterministic vibes".to_string(),
+            simple_advice: "Test mode output".to_string(),
+            risk_level: "None".to_string(),
+            source: "deterministic".to_string(),
+            reasons: Vec::new(),
+            low_liquidity_period: None,
+        }
+    }
+
+    fn template_explanation(
+        &self,
+        symbol: &str,
+        signal: &str,
+        price: f64,
+        change_24h: f64,
+        reasons: &[String],
+        verbosity: Verbosity,
+    ) -> SignalExplanation {
+        let (mut explanation, emoji, vibe, risk_level) = match signal {
             "strong_buy" | "buy" | "weak_buy" => (
                 format!("{} is showing bullish momentum at ${:.2}. 24h change: {:.2}%", symbol, price, change_24h),
                 "🚀",
@@ -49,6 +815,12 @@ impl AIExplainer {
                 "Neutral vibes",
                 "Low"
             ),
+            "stable" => (
+                format!("{} is a stablecoin at ${:.4}, designed to hold its peg, so momentum signals don't apply. 24h change: {:.2}%", symbol, price, change_24h),
+                "🪙",
+                "Stable vibes",
+                "Low"
+            ),
             _ => (
                 format!("{} at ${:.2}: Market sentiment is mixed. 24h change: {:.2}%", symbol, price, change_24h),
                 "🤔",
@@ -57,7 +829,9 @@ impl AIExplainer {
             ),
         };
 
-        let simple_advice = if change_24h > 10.0 {
+        let simple_advice = if signal == "stable" {
+            "🪙 Pegged asset - use it to park value, not to trade momentum"
+        } else if change_24h > 10.0 {
             "🚨 Very strong trend - High risk opportunity"
         } else if change_24h > 5.0 {
             "🔥 Strong trend - Consider position sizing"
@@ -69,6 +843,33 @@ impl AIExplainer {
             "📊 Stable range - Good for swing trading"
         };
 
+        let headline = match signal {
+            "strong_buy" | "buy" | "weak_buy" => "is showing bullish momentum",
+            "strong_sell" | "sell" | "weak_sell" => "might be overbought",
+            "hold" => "is in consolidation phase",
+            "stable" => "is a stablecoin holding its peg",
+            _ => "has mixed sentiment",
+        };
+        match verbosity {
+            Verbosity::Short => {
+                explanation = format!("{} {} at ${:.2}.", symbol, headline, price);
+            }
+            Verbosity::Normal if !reasons.is_empty() => {
+                explanation = format!("{} {} at ${:.2}: {}.", symbol, headline, price, reasons.join("; "));
+            }
+            Verbosity::Normal => {}
+            Verbosity::Detailed => {
+                let mut parts = vec![format!(
+                    "{} {} at ${:.2} (24h change {:+.2}%)",
+                    symbol, headline, price, change_24h
+                )];
+                parts.extend(reasons.iter().cloned());
+                parts.push(simple_advice.to_string());
+                explanation = format!("{}.", parts.join(". "));
+            }
+        }
+        let explanation = truncate_chars(&explanation, verbosity.max_chars());
+
         SignalExplanation {
             symbol: symbol.to_string(),
             current_signal: signal.to_string(),
@@ -78,6 +879,95 @@ impl AIExplainer {
             vibe: vibe.to_string(),
             simple_advice: simple_advice.to_string(),
             risk_level: risk_level.to_string(),
+            source: "template".to_string(),
+            reasons: reasons.to_vec(),
+            low_liquidity_period: None,
+        }
+    }
+
+    // Names the coins, then (beyond `short`) gives each one's own template line, one
+    // verbosity step down so the group doesn't read as N full explanations.
+    fn group_template(&self, signal: &str, members: &[GroupMember], verbosity: Verbosity) -> GroupExplanation {
+        let symbols: Vec<String> = members.iter().map(|m| m.symbol.clone()).collect();
+        let mut explanation = match symbols.as_slice() {
+            [only] => format!("{} is the only coin with a '{}' signal.", only, signal),
+            [rest @ .., last] => format!("{} and {} share a '{}' signal.", rest.join(", "), last, signal),
+            [] => format!("No coin has a '{}' signal.", signal),
+        };
+        let member_verbosity = match verbosity {
+            Verbosity::Short => None,
+            Verbosity::Normal => Some(Verbosity::Short),
+            Verbosity::Detailed => Some(Verbosity::Normal),
+        };
+        let lines: Vec<SignalExplanation> = members
+            .iter()
+            .map(|m| {
+                let member_verbosity = member_verbosity.unwrap_or(Verbosity::Short);
+                let reasons = &m.reasons[..m.reasons.len().min(member_verbosity.max_reasons())];
+                self.template_explanation(&m.symbol, signal, m.price, m.change_24h, reasons, member_verbosity)
+            })
+            .collect();
+        if member_verbosity.is_some() {
+            for line in &lines {
+                explanation.push(' ');
+                explanation.push_str(&line.explanation);
+            }
+        }
+
+        let first = lines.first();
+        GroupExplanation {
+            signal: signal.to_string(),
+            symbols,
+            explanation: truncate_chars(&explanation, verbosity.max_chars() * members.len().max(1)),
+            emoji: first.map(|l| l.emoji.clone()).unwrap_or_default(),
+            vibe: first.map(|l| l.vibe.clone()).unwrap_or_default(),
+            risk_level: first.map(|l| l.risk_level.clone()).unwrap_or_default(),
+            source: "template".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{init_config, stub_server};
+
+    #[tokio::test]
+    async fn concurrent_identical_requests_make_one_model_call() {
+        const REQUESTS: usize = 8;
+        init_config();
+        let body = json!({"choices": [{"message": {"content": "Stub explanation"}}]}).to_string();
+        let stub = stub_server(200, body, Duration::from_millis(200)).await;
+        let explainer = AIExplainer {
+            providers: vec![AiProvider {
+                name: "stub".to_string(),
+                base_url: stub.base_url.clone(),
+                model: "stub-model".to_string(),
+                api_key: String::new(),
+            }],
+            mode: AiMode::Real,
+            format: TextFormat::Plain,
+        };
+        let coalesced_before = COALESCED_EXPLANATIONS.load(Ordering::SeqCst);
+
+        let explanations = futures_util::future::join_all((0..REQUESTS).map(|_| {
+            explainer.explain_signal("SINGLEFLIGHT", "buy", 123.0, 4.2, &[], Verbosity::Normal)
+        }))
+        .await;
+
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            COALESCED_EXPLANATIONS.load(Ordering::SeqCst) - coalesced_before,
+            REQUESTS as u64 - 1
+        );
+        for explanation in &explanations {
+            assert_eq!(explanation.source, "stub");
+            assert_eq!(explanation.explanation, "Stub explanation");
         }
+
+        // Served from the cache from now on
+        explainer.explain_signal("SINGLEFLIGHT", "buy", 123.0, 4.2, &[], Verbosity::Normal).await;
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 1);
+        assert_eq!(forget("SINGLEFLIGHT"), 1);
     }
 }
@@ -0,0 +1,52 @@
+use actix_web::{get, HttpResponse, Responder};
+use serde_json::json;
+
+use crate::coins;
+use crate::config;
+use crate::format;
+
+// Effective runtime settings. Secrets (tokens, API keys, webhook URLs) are only
+// reported as set or not.
+#[get("/config")]
+pub async fn get_config() -> impl Responder {
+    let config = config::get();
+
+    HttpResponse::Ok().json(json!({
+        "supported_coins": config.coins(),
+        "track_top_n": {
+            "enabled": config.track_top_n > 0,
+            "n": config.track_top_n,
+            "hysteresis": config.track_top_n_hysteresis,
+            "interval_seconds": config.track_top_n_interval_seconds,
+        },
+        "coingecko": {
+            "endpoint": config.coingecko_endpoint,
+            "markets_symbols": config.coingecko_markets_symbols,
+            "calls_per_minute_budget": config.coingecko_calls_per_minute,
+            "estimated_calls_per_minute": coins::estimated_calls_per_minute(
+                config.max_tracked_coins(),
+                config.refresh_interval_seconds,
+            ),
+        },
+        "refresh": {
+            "interval_seconds": config.refresh_interval_seconds,
+            "configured_interval_seconds": config.configured_refresh_interval_seconds,
+            "min_safe_interval_seconds": config.min_refresh_interval_seconds,
+            "policy": config.refresh_interval_policy,
+            "background": config.background_refresh,
+        },
+        "history": {
+            "capacity": config.history_capacity,
+            "seed_days": config.history_seed_days,
+            "min_samples": config.min_history_samples,
+        },
+        "watch_rules": config.watch_rules.iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+        "number_format": config.number_format,
+        "ai_mode": config.ai_mode,
+        "ai_providers": config.ai_providers,
+        "notifications_enabled": config.notify_webhook_url.is_some(),
+        "notify_format": if config.notify_template.is_some() { json!("template") } else { json!(config.notify_format) },
+        "admin_token_set": config.admin_token.is_some(),
+        "timestamp": format::now()
+    }))
+}
@@ -0,0 +1,124 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use super::signals::parse_duration;
+use crate::config;
+use crate::format;
+use crate::history;
+
+// Fewest returns two coins must share before their correlation is reported
+const MIN_OVERLAP: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct CorrelationQuery {
+    pub window: Option<String>,
+    // Return interval; defaults to the refresh interval
+    pub step: Option<String>,
+}
+
+// Simple returns between consecutive steps, keyed by step index. A step uses the last
+// sample inside it, and a return needs both its step and the one before to have one.
+fn step_returns(symbol: &str, since: i64, step: i64) -> BTreeMap<i64, f64> {
+    let mut closes: BTreeMap<i64, f64> = BTreeMap::new();
+    for sample in history::samples(symbol) {
+        if sample.timestamp >= since {
+            closes.insert(sample.timestamp.div_euclid(step), sample.price);
+        }
+    }
+
+    closes
+        .iter()
+        .filter_map(|(index, price)| {
+            let previous = closes.get(&(index - 1))?;
+            (*previous > 0.0).then(|| (*index, price / previous - 1.0))
+        })
+        .collect()
+}
+
+// Pearson correlation over the steps both coins have a return for
+fn correlation(a: &BTreeMap<i64, f64>, b: &BTreeMap<i64, f64>) -> (Option<f64>, usize) {
+    let pairs: Vec<(f64, f64)> = a
+        .iter()
+        .filter_map(|(index, x)| b.get(index).map(|y| (*x, *y)))
+        .collect();
+    let n = pairs.len();
+    if n < MIN_OVERLAP {
+        return (None, n);
+    }
+
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n as f64;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n as f64;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in &pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    // A coin that never moved (e.g. a stablecoin) has no defined correlation
+    if var_x == 0.0 || var_y == 0.0 {
+        return (None, n);
+    }
+    let r = cov / (var_x.sqrt() * var_y.sqrt());
+    (Some((r.clamp(-1.0, 1.0) * 1000.0).round() / 1000.0), n)
+}
+
+// ========== RETURNS CORRELATION ==========
+#[get("/signals/correlation")]
+pub async fn get_correlation(query: web::Query<CorrelationQuery>) -> impl Responder {
+    let bad_request = |message: String| {
+        HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": message,
+        }))
+    };
+
+    let window_raw = query.window.clone().unwrap_or_else(|| "24h".to_string());
+    let Some(window) = parse_duration(&window_raw) else {
+        return bad_request(format!("Invalid window '{}', expected e.g. 6h or 7d", window_raw));
+    };
+    let step = match &query.step {
+        Some(raw) => match parse_duration(raw) {
+            Some(step) => step,
+            None => return bad_request(format!("Invalid step '{}', expected e.g. 30s or 5m", raw)),
+        },
+        None => config::get().refresh_interval_seconds.max(1) as i64,
+    };
+    if step > window {
+        return bad_request("step must not be longer than window".to_string());
+    }
+
+    let symbols = config::get().supported_symbols();
+    let since = Utc::now().timestamp() - window;
+    let returns: Vec<BTreeMap<i64, f64>> = symbols
+        .iter()
+        .map(|symbol| step_returns(symbol, since, step))
+        .collect();
+
+    let mut matrix = Vec::new();
+    let mut overlap = Vec::new();
+    for a in &returns {
+        let mut row = Vec::new();
+        let mut counts = Vec::new();
+        for b in &returns {
+            let (r, n) = correlation(a, b);
+            row.push(r);
+            counts.push(n);
+        }
+        matrix.push(row);
+        overlap.push(counts);
+    }
+
+    format::json(HttpResponse::Ok(), &json!({
+        "symbols": format::symbols(&symbols),
+        "window": window_raw,
+        "window_seconds": window,
+        "step_seconds": step,
+        "min_overlap": MIN_OVERLAP,
+        "matrix": matrix,
+        "overlap": overlap,
+        "timestamp": format::now()
+    }))
+}
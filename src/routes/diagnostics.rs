@@ -0,0 +1,43 @@
+use actix_web::{get, HttpResponse, Responder};
+use serde_json::json;
+
+use crate::alert_store;
+use crate::api_keys;
+use crate::audit;
+use crate::auth::Admin;
+use crate::coin_list;
+use crate::format;
+use crate::monitor;
+use crate::scheduler;
+
+#[get("/diagnostics")]
+pub async fn get_diagnostics() -> impl Responder {
+    HttpResponse::Ok().json(json!({
+        "scheduled_tasks": scheduler::statuses(),
+        "clock_jumps": monitor::clock_jumps_snapshot(),
+        "coin_list": coin_list::status(),
+        "alert_store": alert_store::load_status(),
+        "timestamp": format::now()
+    }))
+}
+
+// Newest first
+#[get("/audit-log")]
+pub async fn get_audit_log(_admin: Admin) -> impl Responder {
+    let events = audit::events();
+    HttpResponse::Ok().json(json!({
+        "events": events,
+        "count": events.len(),
+        "retention": audit::retention(),
+        "timestamp": format::now()
+    }))
+}
+
+// Limits and usage of every API_KEYS client, anonymous clients rolled up
+#[get("/admin/api-keys")]
+pub async fn get_api_key_usage(_admin: Admin) -> impl Responder {
+    let mut report = api_keys::usage_report();
+    report["enabled"] = json!(!crate::config::get().api_keys.is_empty());
+    report["timestamp"] = format::now();
+    HttpResponse::Ok().json(report)
+}
@@ -0,0 +1,130 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{get, HttpResponse, Responder};
+use std::time::Instant;
+
+use crate::config;
+use crate::latency::{self, Histogram};
+use crate::load;
+use crate::streams;
+
+// Prometheus text exposition format
+fn metric(out: &mut String, name: &str, help: &str, kind: &str, samples: &[(&str, f64)]) {
+    out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+    for (labels, value) in samples {
+        out.push_str(&format!("{}{} {}\n", name, labels, value));
+    }
+}
+
+// Cumulative `_bucket` lines per METRICS_HISTOGRAM_BUCKETS_MS bound (in seconds), then `+Inf`,
+// `_sum` and `_count`
+fn histogram(out: &mut String, name: &str, help: &str, histogram: &Histogram) {
+    out.push_str(&format!("# HELP {} {}\n# TYPE {} histogram\n", name, help, name));
+    let mut cumulative = 0;
+    for (bound, count) in config::get().metrics_histogram_buckets_ms.iter().zip(&histogram.counts) {
+        cumulative += count;
+        out.push_str(&format!("{}_bucket{{le=\"{}\"}} {}\n", name, bound / 1000.0, cumulative));
+    }
+    out.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, histogram.count));
+    out.push_str(&format!("{}_sum {}\n", name, histogram.sum_seconds));
+    out.push_str(&format!("{}_count {}\n", name, histogram.count));
+}
+
+// Middleware: times every request up to its response head. Streams are timed to the
+// start of the stream, not its end.
+pub async fn record_latency(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let started = Instant::now();
+    let response = next.call(req).await;
+    latency::observe_request(started.elapsed());
+    response
+}
+
+#[get("/metrics")]
+pub async fn get_metrics() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(render())
+}
+
+// Every metric in the text exposition format, as served on /metrics and pushed to
+// PUSHGATEWAY_URL
+pub fn render() -> String {
+    let streams = streams::counts();
+    let mut out = String::new();
+
+    metric(
+        &mut out,
+        "in_flight_requests",
+        "Requests being handled, health and readiness probes excluded",
+        "gauge",
+        &[("", load::in_flight() as f64)],
+    );
+    metric(
+        &mut out,
+        "stream_connections",
+        "Open streaming connections by kind",
+        "gauge",
+        &[
+            ("{kind=\"sse\"}", streams.sse as f64),
+            ("{kind=\"websocket\"}", streams.websocket as f64),
+        ],
+    );
+    metric(
+        &mut out,
+        "stream_connections_max",
+        "MAX_STREAM_CONNECTIONS (0 = unlimited)",
+        "gauge",
+        &[("", config::get().max_stream_connections as f64)],
+    );
+    metric(
+        &mut out,
+        "stream_evictions_total",
+        "Idle streams closed to admit a new connection",
+        "counter",
+        &[("", streams.evictions as f64)],
+    );
+    metric(
+        &mut out,
+        "stream_rejections_total",
+        "Streaming connections turned away at the cap",
+        "counter",
+        &[("", streams.rejections as f64)],
+    );
+    histogram(
+        &mut out,
+        "http_request_duration_seconds",
+        "Time to handle a request, up to the response head",
+        &latency::request_histogram(),
+    );
+    histogram(
+        &mut out,
+        "coingecko_fetch_duration_seconds",
+        "Time taken by CoinGecko price fetches, successful or not",
+        &latency::upstream_fetch_histogram(),
+    );
+
+    let ai = latency::ai_call_summary();
+    let name = "ai_explanation_duration_seconds";
+    out.push_str(&format!(
+        "# HELP {} AI provider call times over the last 500 calls, timeouts included\n# TYPE {} summary\n",
+        name, name
+    ));
+    for (quantile, seconds) in &ai.quantiles {
+        let value = seconds.map_or("NaN".to_string(), |s| s.to_string());
+        out.push_str(&format!("{}{{quantile=\"{}\"}} {}\n", name, quantile, value));
+    }
+    out.push_str(&format!("{}_sum {}\n{}_count {}\n", name, ai.sum_seconds, name, ai.count));
+    metric(
+        &mut out,
+        "ai_explanation_timeouts_total",
+        "AI provider calls cut off by AI_LATENCY_BUDGET_MS",
+        "counter",
+        &[("", ai.timeouts as f64)],
+    );
+
+    out
+}
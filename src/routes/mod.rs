@@ -1,2 +1,139 @@
 pub mod signals;
 pub mod ai_explanation;
+pub mod config;
+pub mod correlation;
+pub mod diagnostics;
+pub mod metrics;
+pub mod paper;
+pub mod selftest;
+pub mod signals_ws;
+pub mod stream;
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ResourceDef, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{get, HttpRequest, HttpResponse, Responder};
+use serde_json::json;
+
+use crate::error::ApiError;
+use crate::format;
+
+// Can't be disabled, so load balancers can always reach the service
+pub const HEALTH_ENDPOINT: &str = "/_health";
+// Readiness probe; like /_health, never rate limited
+pub const READY_ENDPOINT: &str = "/ready";
+
+// Every (method, path pattern) the app serves, used to answer 405s with an Allow header.
+pub const ROUTES: &[(&str, &str)] = &[
+    ("GET", "/"),
+    ("GET", "/_health"),
+    ("GET", "/ready"),
+    ("GET", "/endpoints"),
+    ("GET", "/health"),
+    ("GET", "/prices"),
+    ("GET", "/prices/stream"),
+    ("GET", "/signals"),
+    ("GET", "/signals/ws"),
+    ("GET", "/signals/correlation"),
+    ("GET", "/signals/changes"),
+    ("GET", "/signals/raw"),
+    ("GET", "/tradingview-alerts"),
+    ("GET", "/alerts/{symbol}"),
+    ("GET", "/alerts/{symbol}/histogram"),
+    ("GET", "/cache-stats"),
+    ("GET", "/diagnostics"),
+    ("GET", "/audit-log"),
+    ("GET", "/admin/api-keys"),
+    ("GET", "/metrics"),
+    ("GET", "/config"),
+    ("GET", "/selftest"),
+    ("GET", "/paper/portfolio"),
+    ("GET", "/paper/trades"),
+    ("GET", "/explain-signal"),
+    ("GET", "/explain-signals"),
+    ("GET", "/explain-all-signals"),
+    ("POST", "/tradingview-webhook"),
+    ("POST", "/clear-alerts"),
+    ("POST", "/clear-cache"),
+    ("POST", "/admin/refresh"),
+];
+
+pub fn is_enabled(pattern: &str) -> bool {
+    !crate::config::get().disabled_endpoints.iter().any(|p| p == pattern)
+}
+
+// Every path with at least one enabled method, in ROUTES order
+pub fn enabled_paths() -> Vec<&'static str> {
+    let mut paths: Vec<&'static str> = Vec::new();
+    for (_, pattern) in ROUTES {
+        if is_enabled(pattern) && !paths.contains(pattern) {
+            paths.push(pattern);
+        }
+    }
+    paths
+}
+
+// Methods served on a path, leaving out endpoints disabled in the config
+pub fn allowed_methods(path: &str) -> Vec<&'static str> {
+    ROUTES
+        .iter()
+        .filter(|(_, pattern)| is_enabled(pattern) && ResourceDef::new(*pattern).is_match(path))
+        .map(|(method, _)| *method)
+        .collect()
+}
+
+fn is_disabled_path(path: &str) -> bool {
+    ROUTES
+        .iter()
+        .any(|(_, pattern)| !is_enabled(pattern) && ResourceDef::new(*pattern).is_match(path))
+}
+
+// Middleware: disabled endpoints answer 404 exactly like paths that don't exist.
+pub async fn endpoint_gate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    if is_disabled_path(req.path()) {
+        let response = HttpResponse::NotFound().finish().map_into_right_body();
+        return Ok(req.into_response(response));
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+// Every endpoint and whether this deployment serves it
+#[get("/endpoints")]
+pub async fn list_endpoints() -> impl Responder {
+    let endpoints: Vec<_> = ROUTES
+        .iter()
+        .map(|(method, pattern)| {
+            json!({
+                "method": method,
+                "path": pattern,
+                "enabled": is_enabled(pattern),
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "endpoints": endpoints,
+        "enabled": ROUTES.iter().filter(|(_, pattern)| is_enabled(pattern)).count(),
+        "timestamp": format::now()
+    }))
+}
+
+// Default service. Routes are registered per method, so actix falls through to
+// here for a known path with the wrong method; answer those with a 405 in the
+// ApiError envelope plus an Allow header, and everything else with a 404.
+pub async fn unmatched(req: HttpRequest) -> HttpResponse {
+    let allowed = allowed_methods(req.path());
+    if allowed.is_empty() {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let error = ApiError::method_not_allowed(req.method().as_str(), req.path(), &allowed);
+    error
+        .response_builder()
+        .insert_header((header::ALLOW, allowed.join(", ")))
+        .json(error.body())
+}
@@ -0,0 +1,72 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config;
+use crate::format;
+use crate::paper;
+
+// Most trades /paper/trades returns per page
+const MAX_TRADES_PAGE: usize = 500;
+
+fn disabled() -> HttpResponse {
+    HttpResponse::NotFound().json(json!({
+        "status": "error",
+        "message": "Paper trading is disabled. Set PAPER_TRADING=true to enable it.",
+    }))
+}
+
+#[get("/paper/portfolio")]
+pub async fn get_portfolio() -> impl Responder {
+    if !config::get().paper_trading {
+        return disabled();
+    }
+
+    let mut body = paper::summary();
+    body["timestamp"] = format::now();
+    format::json(HttpResponse::Ok(), &body)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TradesQuery {
+    pub symbol: Option<String>,
+    pub limit: Option<usize>,
+    pub before: Option<u64>,
+}
+
+// Simulated entries and exits, newest first
+#[get("/paper/trades")]
+pub async fn get_trades(query: web::Query<TradesQuery>) -> impl Responder {
+    if !config::get().paper_trading {
+        return disabled();
+    }
+
+    let symbol = query.symbol.as_deref().map(|s| s.trim().to_uppercase());
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_TRADES_PAGE);
+    let (trades, retained) = paper::trades(symbol.as_deref(), limit, query.before);
+    // A full page may have more behind it
+    let next_before = if trades.len() == limit {
+        trades.last().map(|trade| trade.id)
+    } else {
+        None
+    };
+
+    let entries: Vec<serde_json::Value> = trades
+        .iter()
+        .map(|trade| {
+            let mut entry = json!(trade);
+            entry["symbol"] = json!(format::symbol(&trade.symbol));
+            entry["timestamp"] = format::timestamp(trade.timestamp);
+            entry
+        })
+        .collect();
+
+    format::json(HttpResponse::Ok(), &json!({
+        "trades": entries,
+        "count": trades.len(),
+        "next_before": next_before,
+        "retained": retained,
+        "retention": config::get().paper_trade_log_retention,
+        "timestamp": format::now()
+    }))
+}
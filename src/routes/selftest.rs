@@ -0,0 +1,129 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Instant;
+
+use super::ai_explanation::{AIExplainer, Verbosity};
+use crate::auth::Admin;
+use crate::coingecko;
+use crate::config;
+use crate::format;
+use crate::history;
+use crate::indicators;
+use crate::strategy::{generate_signal, signal_reasons};
+
+#[derive(Debug, Deserialize)]
+pub struct SelfTestQuery {
+    pub symbol: Option<String>,
+    // The explanation stage may spend an OpenAI call, so it is opt-in
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct StageResult {
+    stage: &'static str,
+    ok: bool,
+    duration_ms: u64,
+    detail: String,
+}
+
+impl StageResult {
+    fn new(stage: &'static str, started: Instant, result: Result<String, String>) -> Self {
+        let ok = result.is_ok();
+        StageResult {
+            stage,
+            ok,
+            duration_ms: started.elapsed().as_millis() as u64,
+            detail: result.unwrap_or_else(|e| e),
+        }
+    }
+}
+
+// Runs one symbol through fetch -> indicators -> signal -> (optionally) explanation.
+// The fetch goes straight to CoinGecko, bypassing the price cache and history, so
+// nothing the service serves is changed by a self-test.
+#[get("/selftest")]
+pub async fn run_selftest(_admin: Admin, query: web::Query<SelfTestQuery>) -> impl Responder {
+    let config = config::get();
+    let symbol = query
+        .symbol
+        .clone()
+        .map(|s| s.trim().to_uppercase())
+        .unwrap_or_else(|| config.supported_symbols()[0].clone());
+    let Some(coin_id) = config.coingecko_id(&symbol) else {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": format!("Unsupported symbol: {}", symbol),
+        }));
+    };
+
+    let total = Instant::now();
+    let mut stages = Vec::new();
+
+    let started = Instant::now();
+    let endpoint = config.coingecko_endpoint_for(&symbol);
+    let price = coingecko::fetch_price(endpoint, &symbol, &coin_id).await;
+    stages.push(StageResult::new(
+        "fetch",
+        started,
+        price
+            .as_ref()
+            .map(|p| format!("{} = {} ({:?} endpoint)", symbol, p.price, endpoint))
+            .map_err(|e| e.to_string()),
+    ));
+
+    let started = Instant::now();
+    let prices = history::prices(&symbol);
+    let snapshot = indicators::compute(&prices, &config.indicator_settings());
+    stages.push(StageResult::new(
+        "indicators",
+        started,
+        match (snapshot.rsi, snapshot.non_finite.is_empty()) {
+            (_, false) => Err(format!("non-finite indicator values: {}", snapshot.non_finite.join(", "))),
+            (Some(rsi), true) => Ok(format!("{} samples, RSI {:.1}", prices.len(), rsi)),
+            (None, true) => Ok(format!("{} samples, not enough history for RSI yet", prices.len())),
+        },
+    ));
+
+    if let Ok(price) = &price {
+        let started = Instant::now();
+        let (signal, confidence) = generate_signal(price);
+        let reasons = signal_reasons(price);
+        stages.push(StageResult::new(
+            "signal",
+            started,
+            Ok(format!("{} ({:.0}% confidence)", signal, confidence * 100.0)),
+        ));
+
+        if query.explain {
+            let started = Instant::now();
+            let explainer = AIExplainer::new();
+            let explanation = explainer
+                .explain_signal(&symbol, &signal, price.price, price.change_24h, &reasons, Verbosity::Normal)
+                .await;
+            // A template answer means every provider failed and it was papered over
+            let result = if explainer.uses_openai() && explanation.source == "template" {
+                Err("Every AI provider failed, fell back to template".to_string())
+            } else {
+                Ok(format!("{} explanation", explanation.source))
+            };
+            stages.push(StageResult::new("explanation", started, result));
+        }
+    }
+
+    let passed = price.is_ok() && stages.iter().all(|s| s.ok);
+    let body = json!({
+        "status": if passed { "pass" } else { "fail" },
+        "symbol": format::symbol(&symbol),
+        "stages": stages,
+        "duration_ms": total.elapsed().as_millis() as u64,
+        "timestamp": format::now()
+    });
+
+    if passed {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
@@ -1,298 +1,924 @@
-use actix_web::{get, HttpResponse, Responder, web};
+use actix_web::http::{header, StatusCode};
+use actix_web::{get, HttpRequest, HttpResponse, Responder, web};
+use futures_util::stream;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use chrono::Utc;
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::time::{SystemTime, Duration};
+use std::time::{Duration, Instant};
 
 // Import AI module
-use super::ai_explanation::{AIExplainer, SignalExplanation};
+use super::ai_explanation::{self, AIExplainer, GroupMember, SignalExplanation, TextFormat, Verbosity};
+use crate::alert_store;
+use crate::alerts::{self, ConflictResolution, Severity, UNKNOWN_ACTION};
+use crate::auth::{self, Admin};
+use crate::coin_list;
+use crate::coingecko::{PriceData, PriceSource, SourceType};
+use crate::config;
+use crate::error::ApiError;
+use crate::format;
+use crate::history::{self, WarmupMode};
+use crate::indicators;
+use crate::liquidity;
+use crate::notifier;
+use crate::prices::{self, fetch_live_price};
+use crate::quality;
+use crate::refresher;
+use crate::routes;
+use crate::signal_tracker;
+use crate::strategy::{self, generate_signal, get_action_from_signal, signal_reasons};
 
-// Import AI module
+// Last full /explain-all-signals run, reused while EXPLAIN_ALL_MIN_INTERVAL_SECONDS
+// hasn't passed and no signal has changed
+static LAST_SWEEP: std::sync::OnceLock<Mutex<Option<Sweep>>> = std::sync::OnceLock::new();
+
+struct Sweep {
+    at: Instant,
+    generated_at: i64,
+    verbosity: Verbosity,
+    text_format: TextFormat,
+    group_by: GroupBy,
+    // (symbol, signal) for every coin when the sweep ran
+    signature: Vec<(String, String)>,
+    // Serialized explanations, or groups with group_by=signal
+    entries: Vec<Value>,
+}
+
+// How /explain-all-signals splits its explanations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GroupBy {
+    // One per coin
+    Symbol,
+    // One per distinct signal, naming the coins that have it
+    Signal,
+}
+
+impl GroupBy {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "symbol" => Ok(GroupBy::Symbol),
+            "signal" => Ok(GroupBy::Signal),
+            other => Err(format!("Invalid group_by: {}. Use symbol or signal.", other)),
+        }
+    }
+
+    // Key the entries are listed under
+    fn key(self) -> &'static str {
+        match self {
+            GroupBy::Symbol => "explanations",
+            GroupBy::Signal => "groups",
+        }
+    }
+}
 
-// Store to keep alerts in memory
+// Store to keep alerts in memory, backed by ALERT_STORE_FILE when set
 static ALERTS: std::sync::OnceLock<Arc<Mutex<Vec<TradingViewAlert>>>> = std::sync::OnceLock::new();
-static PRICE_CACHE: std::sync::OnceLock<Arc<Mutex<HashMap<String, (PriceData, SystemTime)>>>> = std::sync::OnceLock::new();
 
-#[derive(Debug, Serialize, Clone, Deserialize)]
-pub struct PriceData {
-    pub symbol: String,
-    pub price: f64,
-    pub timestamp: i64,
-    pub change_24h: f64,
-    pub market_cap: Option<f64>,
-    pub volume_24h: Option<f64>,
+fn alerts_store() -> &'static Arc<Mutex<Vec<TradingViewAlert>>> {
+    ALERTS.get_or_init(|| Arc::new(Mutex::new(alert_store::load())))
+}
+
+// Loads persisted alerts at startup, before the first one arrives
+pub fn load_alerts() {
+    alerts_store();
+}
+
+// Rewrites the alert file down to the alerts still in memory. Holds the store's lock
+// throughout so no alert is appended to a file that is about to be replaced.
+pub fn compact_alerts() -> Result<(), String> {
+    let _write = alert_store::write_lock();
+    let alerts = alerts_store().lock().unwrap().clone();
+    let (before, after) = alert_store::rewrite(&alerts)?;
+    if before > after {
+        println!(
+            "🗜️ Compacted alert file: {} -> {} bytes ({} reclaimed, {} alerts kept)",
+            before,
+            after,
+            before - after,
+            alerts.len()
+        );
+    }
+    Ok(())
 }
 
 #[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct TradingViewAlert {
+    #[serde(serialize_with = "format::serialize_symbol")]
     pub symbol: String,
     pub price: f64,
     pub alert_name: String,
+    // Normalized through ALERT_ACTION_MAP; `unknown` when the sent action isn't mapped
+    pub action: Option<String>,
+    // The action as sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_action: Option<String>,
+    pub severity: Severity,
+    // Facts behind an alert the service raised itself, e.g. a confidence drop's before and after
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<serde_json::Value>,
+    // Our cached price when the alert arrived, with ALERT_PRICE_ENRICHMENT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed: Option<ObservedPrice>,
+    #[serde(serialize_with = "format::serialize_timestamp")]
     pub timestamp: i64,
 }
 
+// The cached price next to an alert's own, and how far the alert's is from it
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct ObservedPrice {
+    pub price: f64,
+    // When the cached price was fetched
+    #[serde(serialize_with = "format::serialize_timestamp")]
+    pub at: i64,
+    // Alert price minus observed price
+    pub difference: f64,
+    pub difference_percent: f64,
+}
+
+impl ObservedPrice {
+    pub fn new(alert_price: f64, price: f64, at: i64) -> Self {
+        let difference = alert_price - price;
+        let difference_percent = if price > 0.0 { difference / price * 100.0 } else { 0.0 };
+        ObservedPrice {
+            price,
+            at,
+            difference: (difference * 1e8).round() / 1e8,
+            difference_percent: (difference_percent * 10_000.0).round() / 10_000.0,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TradingViewWebhook {
     pub symbol: String,
     pub price: f64,
     pub alert_name: Option<String>,
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignalsQuery {
+    // Admin-only: fetch from CoinGecko instead of serving the cache
+    #[serde(default)]
+    pub no_cache: bool,
+    // Add the thresholds the signals were computed with
+    #[serde(default)]
+    pub include_thresholds: bool,
+    // Add each entry's signal strength and every factor's signed share of it
+    #[serde(default)]
+    pub include_contributions: bool,
+    // json (default) or ndjson
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PricesQuery {
+    #[serde(default)]
+    pub no_cache: bool,
+    // Attach the signal and its score to each price, without the rest of /signals
+    #[serde(default)]
+    pub include_signal: bool,
+    // Add `age_human` ("12s ago") next to each timestamp
+    #[serde(default)]
+    pub include_age: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlertsQuery {
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub include_age: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistogramQuery {
+    pub bucket: Option<String>,
+    pub window: Option<String>,
+    pub severity: Option<String>,
+    // split=action adds per-action counts to every bucket
+    pub split: Option<String>,
 }
 
 // ========== HEALTH CHECK ==========
 #[get("/health")]
 pub async fn health_check() -> impl Responder {
+    let symbols = config::get().supported_symbols();
+    let delisted: Vec<&String> = symbols.iter().filter(|s| prices::is_delisted(s)).collect();
+    let coin_status: serde_json::Map<String, serde_json::Value> = symbols.iter()
+        .map(|s| {
+            let status = if prices::is_delisted(s) { "delisted_or_missing" } else { "ok" };
+            (format::symbol(s), json!(status))
+        })
+        .collect();
+    
     HttpResponse::Ok().json(json!({
-        "status": "healthy",
+        "status": if delisted.is_empty() { "healthy" } else { "degraded" },
+        "coin_status": coin_status,
+        "openai": ai_explanation::openai_status(),
         "service": "trading-signals-backend",
-        "timestamp": Utc::now().timestamp(),
+        "timestamp": format::now(),
         "version": "1.0.0",
-        "supported_coins": ["BTC", "ETH", "SOL", "PAXG"],
-        "endpoints": [
-            "/health",
-            "/prices", 
-            "/signals",
-            "/explain-signal",
-            "/explain-all-signals",
-            "/tradingview-webhook",
-            "/tradingview-alerts",
-            "/alerts/{symbol}"
-        ]
+        "supported_coins": format::symbols(&config::get().supported_symbols()),
+        "endpoints": routes::enabled_paths(),
     }))
 }
 
-// ========== REAL PRICE FETCHING ==========
-fn get_coingecko_id(symbol: &str) -> Option<&'static str> {
-    match symbol.to_uppercase().as_str() {
-        "BTC" => Some("bitcoin"),
-        "ETH" => Some("ethereum"),
-        "SOL" => Some("solana"),
-        "PAXG" => Some("pax-gold"),
-        _ => None,
+// Cached price, or with no_cache a forced upstream fetch recorded in `forced`.
+async fn load_price(symbol: &str, no_cache: bool, forced: &mut Vec<String>) -> Result<PriceData, String> {
+    if !no_cache {
+        return fetch_live_price(symbol).await;
     }
+    let (price_data, fetched) = prices::fetch_price_forced(symbol).await?;
+    if fetched {
+        forced.push(format::symbol(symbol));
+    }
+    Ok(price_data)
 }
 
-async fn fetch_live_price(symbol: &str) -> Result<PriceData, String> {
-    let symbol_upper = symbol.to_uppercase();
-    
-    // Check cache
-    if let Some(cache) = PRICE_CACHE.get() {
-        let cache_lock = cache.lock().unwrap();
-        if let Some((data, timestamp)) = cache_lock.get(&symbol_upper) {
-            if SystemTime::now().duration_since(*timestamp)
-                .unwrap_or(Duration::from_secs(0))
-                .as_secs() < 30 {
-                return Ok(data.clone());
-            }
-        }
+// Adds "forced_fetch" to no_cache responses: the symbols actually fetched upstream.
+fn add_forced_fetch(body: &mut serde_json::Value, no_cache: bool, forced: &[String]) {
+    if no_cache {
+        body["forced_fetch"] = json!({
+            "symbols": forced,
+            "min_interval_seconds": config::get().forced_fetch_min_interval_seconds,
+        });
     }
-    
-    let coin_id = get_coingecko_id(&symbol_upper)
-        .ok_or_else(|| format!("Unknown symbol: {}", symbol))?;
-    
-    let url = format!("https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true&include_market_cap=true&include_24hr_vol=true", 
-        coin_id);
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Client error: {}", e))?;
-    
-    let response = client.get(&url)
-        .header("User-Agent", "TradingSignalsBot/1.0")
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
-    }
-    
-    let data: serde_json::Value = response.json()
-        .await
-        .map_err(|e| format!("JSON error: {}", e))?;
-    
-    let coin_data = data.get(coin_id)
-        .ok_or_else(|| format!("No data for {}", symbol))?;
-    
-    let price = coin_data.get("usd")
-        .and_then(|v| v.as_f64())
-        .ok_or("No price in response")?;
-    
-    let change_24h = coin_data.get("usd_24h_change")
-        .and_then(|v| v.as_f64())
-        .unwrap_or(0.0);
-    
-    let market_cap = coin_data.get("usd_market_cap")
-        .and_then(|v| v.as_f64());
-    
-    let volume_24h = coin_data.get("usd_24h_vol")
-        .and_then(|v| v.as_f64());
-    
-    let price_data = PriceData {
-        symbol: symbol_upper.clone(),
-        price,
-        timestamp: Utc::now().timestamp(),
-        change_24h,
-        market_cap,
-        volume_24h,
-    };
-    
-    // Update cache
-    if let Some(cache) = PRICE_CACHE.get() {
-        let mut cache_lock = cache.lock().unwrap();
-        cache_lock.insert(symbol_upper, (price_data.clone(), SystemTime::now()));
-    } else {
-        let mut new_cache = HashMap::new();
-        new_cache.insert(symbol_upper, (price_data.clone(), SystemTime::now()));
-        let _ = PRICE_CACHE.set(Arc::new(Mutex::new(new_cache)));
-    }
-    
-    Ok(price_data)
 }
 
 #[get("/prices")]
-pub async fn get_prices() -> impl Responder {
+pub async fn get_prices(req: HttpRequest, query: web::Query<PricesQuery>) -> impl Responder {
+    if query.no_cache {
+        if let Err(e) = auth::authorize(&req) {
+            return e.error_response();
+        }
+    }
     println!("🚀 Fetching live prices from CoinGecko...");
     
-    let symbols = vec!["BTC", "ETH", "SOL", "PAXG"];
+    let symbols = config::get().supported_symbols();
     let mut prices = Vec::new();
+    let mut forced = Vec::new();
     
-    for symbol in symbols {
-        match fetch_live_price(symbol).await {
+    for symbol in &symbols {
+        match load_price(symbol, query.no_cache, &mut forced).await {
             Ok(price_data) => {
                 println!("✅ {}: ${:.2} ({:.2}%)", symbol, price_data.price, price_data.change_24h);
-                prices.push(price_data);
+                let timestamp = price_data.timestamp;
+                let entry = with_age(with_signal(price_data, query.include_signal), timestamp, query.include_age);
+                prices.push(with_name(entry, symbol));
             },
             Err(e) => {
                 println!("❌ Failed {}: {}", symbol, e);
-                prices.push(PriceData {
+                let mut placeholder = json!(PriceData {
                     symbol: symbol.to_string(),
                     price: 0.0,
                     timestamp: Utc::now().timestamp(),
                     change_24h: 0.0,
                     market_cap: None,
                     volume_24h: None,
+                    market_cap_rank: None,
+                    ath: None,
+                    ath_change_percentage: None,
+                    change_7d: None,
+                    change_30d: None,
+                    raw_price: None,
+                    adjustment: None,
+                    source: PriceSource::None,
+                    source_type: SourceType::Fallback,
                 });
+                // The placeholder's timestamp is not a trade time
+                if query.include_age {
+                    placeholder["age_human"] = serde_json::Value::Null;
+                }
+                prices.push(placeholder);
             }
         }
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
     
-    HttpResponse::Ok().json(json!({
+    let mut body = json!({
         "prices": prices,
         "count": prices.len(),
-        "timestamp": Utc::now().timestamp(),
+        "timestamp": format::now(),
         "source": "CoinGecko API"
+    });
+    add_forced_fetch(&mut body, query.no_cache, &forced);
+    format::json(HttpResponse::Ok(), &body)
+}
+
+// Adds the coin's `name` once the CoinGecko coin list has been fetched
+fn with_name(mut entry: serde_json::Value, symbol: &str) -> serde_json::Value {
+    let listed = config::get().coingecko_id(symbol).and_then(|id| coin_list::lookup(&id));
+    if let Some(listed) = listed {
+        entry["name"] = json!(listed.name);
+    }
+    entry
+}
+
+// With include_age, adds `age_human` for `timestamp` to an entry
+fn with_age(mut entry: serde_json::Value, timestamp: i64, include_age: bool) -> serde_json::Value {
+    if include_age {
+        entry["age_human"] = json!(format::age_human(timestamp));
+    }
+    entry
+}
+
+// Alerts as listed, each with `age_human` when asked for
+fn alert_entries(alerts: Vec<TradingViewAlert>, include_age: bool) -> Vec<serde_json::Value> {
+    alerts
+        .into_iter()
+        .map(|alert| {
+            let timestamp = alert.timestamp;
+            with_age(json!(alert), timestamp, include_age)
+        })
+        .collect()
+}
+
+// With include_signal, the price plus just `signal` and `score`, gated on history like
+// /signals. Read-only: the signal isn't recorded as observed, so /prices never shows up
+// in signal-change history.
+fn with_signal(price_data: PriceData, include_signal: bool) -> serde_json::Value {
+    if !include_signal {
+        return json!(price_data);
+    }
+    let symbol = price_data.symbol.to_uppercase();
+    let (signal, score) = if history::is_ready(&symbol) {
+        let (signal, confidence) = generate_signal(&price_data);
+        let (confidence, _) = liquidity::adjust_confidence(&symbol, confidence);
+        (signal, Some((confidence * 100.0).round() / 100.0))
+    } else {
+        ("insufficient_data".to_string(), None)
+    };
+    let mut entry = json!(price_data);
+    entry["signal"] = json!(signal);
+    entry["score"] = json!(score);
+    entry
+}
+
+// Newest TradingView alert for the symbol within EXTERNAL_SIGNAL_MAX_AGE_SECONDS whose
+// action is a signal (not `unknown`)
+fn latest_external_signal(symbol: &str) -> Option<TradingViewAlert> {
+    let oldest = Utc::now().timestamp() - config::get().external_signal_max_age_seconds as i64;
+    alerts_store()
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|alert| {
+            alert.timestamp >= oldest
+                && alert.symbol.eq_ignore_ascii_case(symbol)
+                && alert.action.as_deref().is_some_and(|a| alerts::SIGNAL_VOCABULARY.contains(&a))
+        })
+        .cloned()
+}
+
+// Adds a recent TradingView alert to a /signals entry per SIGNAL_CONFLICT_RESOLUTION,
+// with `conflict` saying whether it disagrees with the internal signal. Entries for
+// coins without one are left as they are.
+fn resolve_external_signal(entry: &mut serde_json::Value, symbol: &str, internal: &str) {
+    let Some(alert) = latest_external_signal(symbol) else {
+        return;
+    };
+    let external = alert.action.unwrap_or_default();
+    let conflict = alerts::signals_conflict(internal, &external);
+    let resolved = match config::get().signal_conflict_resolution {
+        ConflictResolution::PreferInternal => "internal",
+        ConflictResolution::PreferExternal => {
+            entry["signal"] = json!(external);
+            entry["action"] = json!(get_action_from_signal(&external));
+            "external"
+        }
+        ConflictResolution::ShowBoth if conflict => {
+            entry["signal"] = json!(alerts::CONFLICT_SIGNAL);
+            entry["action"] = json!(get_action_from_signal(alerts::CONFLICT_SIGNAL));
+            "none"
+        }
+        ConflictResolution::ShowBoth => "internal",
+    };
+    entry["internal_signal"] = json!(internal);
+    entry["external_signal"] = json!(external);
+    entry["external_signal_at"] = format::timestamp(alert.timestamp);
+    entry["signal_source"] = json!(resolved);
+    entry["conflict"] = json!(conflict);
+}
+
+// SIGNAL_HOLD_SECONDS: while a coin's price can't be fetched, or its history has a gap
+// that would turn it into `insufficient_data`, keeps reporting the last signal it had as
+// long as the price behind it is recent enough, marked `held`. Its confidence fades
+// linearly to zero over the hold. Past that the coin degrades as usual.
+fn held_signal(symbol: &str, hold: u64) -> Option<serde_json::Value> {
+    if hold == 0 {
+        return None;
+    }
+    let state = signal_tracker::recent(symbol, hold)?;
+    let age = (Utc::now().timestamp() - state.observed_at).max(0);
+    let confidence = state.confidence * (1.0 - age as f64 / hold as f64).max(0.0);
+    Some(json!({
+        "symbol": format::symbol(symbol),
+        "price": state.price,
+        "signal": state.signal,
+        "confidence": (confidence * 100.0).round() / 100.0,
+        "action": get_action_from_signal(&state.signal),
+        "status": "held",
+        "held": true,
+        "data_as_of": format::timestamp(state.observed_at),
+        "data_age_seconds": age,
+        "hold_seconds": hold,
+        "timestamp": format::now(),
     }))
 }
 
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+// ?format=ndjson or an Accept header asking for it; the JSON document otherwise. An
+// explicit ?format= wins over the header.
+fn wants_ndjson(req: &HttpRequest, format: Option<&str>) -> Result<bool, String> {
+    let enabled = config::get().signals_ndjson;
+    match format.map(|f| f.trim().to_lowercase()).as_deref() {
+        Some("json") => Ok(false),
+        Some("ndjson") if enabled => Ok(true),
+        Some("ndjson") => Err("ndjson output is disabled (SIGNALS_NDJSON=false)".to_string()),
+        Some(other) => Err(format!("Invalid format: {}. Use json or ndjson.", other)),
+        None => Ok(enabled
+            && req
+                .headers()
+                .get(header::ACCEPT)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|accept| {
+                    accept
+                        .split(',')
+                        .any(|t| matches!(t.split(';').next().unwrap_or("").trim(), NDJSON_CONTENT_TYPE | "application/ndjson"))
+                })),
+    }
+}
+
 // ========== SIGNAL GENERATION ==========
 #[get("/signals")]
-pub async fn get_signals() -> impl Responder {
+pub async fn get_signals(req: HttpRequest, query: web::Query<SignalsQuery>) -> impl Responder {
+    if query.no_cache {
+        if let Err(e) = auth::authorize(&req) {
+            return e.error_response();
+        }
+    }
+    let ndjson = match wants_ndjson(&req, query.format.as_deref()) {
+        Ok(ndjson) => ndjson,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
     println!("📈 Generating trading signals...");
     
-    let symbols = vec!["BTC", "ETH", "SOL", "PAXG"];
+    let symbols = config::get().supported_symbols();
+    if ndjson {
+        return ndjson_signals(symbols, &query);
+    }
+    format::json(HttpResponse::Ok(), &signals_document(&symbols, &query).await)
+}
+
+async fn signals_document(symbols: &[String], query: &SignalsQuery) -> serde_json::Value {
     let mut signals = Vec::new();
+    let mut forced = Vec::new();
     
     for symbol in symbols {
-        match fetch_live_price(symbol).await {
-            Ok(price_data) => {
-                let (signal, confidence) = generate_signal(&price_data);
-                
-                signals.push(json!({
-                    "symbol": symbol,
-                    "price": price_data.price,
-                    "change_24h": price_data.change_24h,
-                    "signal": signal,
-                    "confidence": (confidence * 100.0).round() / 100.0,
-                    "action": get_action_from_signal(&signal),
-                    "timestamp": Utc::now().timestamp(),
-                }));
-            },
-            Err(e) => {
-                signals.push(json!({
-                    "symbol": symbol,
-                    "error": e,
-                    "signal": "error",
-                    "confidence": 0.0,
-                    "timestamp": Utc::now().timestamp(),
-                }));
-            }
-        }
+        signals.push(signal_entry(symbol, query.no_cache, query.include_contributions, &mut forced).await);
     }
     
-    HttpResponse::Ok().json(json!({
-        "signals": signals,
-        "count": signals.len(),
-        "timestamp": Utc::now().timestamp(),
-    }))
+    let mut body = document_fields(signals.len(), query, &forced);
+    body["signals"] = json!(signals);
+    body
+}
+
+// Everything in the /signals document besides the entries themselves
+fn document_fields(count: usize, query: &SignalsQuery, forced: &[String]) -> serde_json::Value {
+    let mut body = json!({
+        "count": count,
+        "next_refresh_in_seconds": refresher::next_refresh_in_seconds(),
+        "timestamp": format::now(),
+    });
+    if query.include_thresholds {
+        body["thresholds"] = strategy::thresholds();
+    }
+    add_forced_fetch(&mut body, query.no_cache, forced);
+    body
 }
 
-fn generate_signal(price_data: &PriceData) -> (String, f64) {
-    match price_data.change_24h {
-        c if c > 10.0 => ("strong_sell".to_string(), 0.85),
-        c if c > 5.0 => ("sell".to_string(), 0.75),
-        c if c > 2.0 => ("weak_sell".to_string(), 0.65),
-        c if c < -10.0 => ("strong_buy".to_string(), 0.85),
-        c if c < -5.0 => ("buy".to_string(), 0.75),
-        c if c < -2.0 => ("weak_buy".to_string(), 0.65),
-        _ => ("hold".to_string(), 0.8),
+// One coin's /signals entry: its signal, or why there isn't one
+async fn signal_entry(
+    symbol: &str,
+    no_cache: bool,
+    include_contributions: bool,
+    forced: &mut Vec<String>,
+) -> serde_json::Value {
+    match load_price(symbol, no_cache, forced).await {
+        Ok(price_data) if !history::is_ready(symbol) && config::get().signals_warmup == WarmupMode::Strict => {
+            warming_up_entry(symbol, &price_data, config::get().signal_hold_seconds)
+        },
+        Ok(price_data) => {
+            let (signal, confidence) = generate_signal(&price_data);
+            let (confidence, low_liquidity) = liquidity::adjust_confidence(symbol, confidence);
+            let state = signal_tracker::observe(symbol, &signal, confidence, price_data.price, price_data.timestamp);
+            let settings = config::get().indicator_settings();
+            
+            let mut entry = json!({
+                "symbol": format::symbol(symbol),
+                "price": price_data.price,
+                "change_24h": price_data.change_24h,
+                "signal": signal,
+                "confidence": (confidence * 100.0).round() / 100.0,
+                "action": get_action_from_signal(&signal),
+                "source": price_data.source,
+                "source_type": price_data.source_type,
+                "data_quality": quality::score(symbol, &price_data),
+                "timestamp": format::now(),
+            });
+            if low_liquidity {
+                entry["low_liquidity_period"] = json!(true);
+            }
+            if let Some(change) = price_data.change_7d {
+                entry["change_7d"] = json!(change);
+            }
+            if let Some(change) = price_data.change_30d {
+                entry["change_30d"] = json!(change);
+            }
+            if settings.divergence_lookback > 0 {
+                entry["divergence"] = json!(indicators::compute(&history::prices(symbol), &settings).divergence);
+            }
+            // SIGNALS_WARMUP=best_effort: the signal stands on what has warmed up; with
+            // SIGNAL_BASIS=composite the pending indicators are left out of its score
+            if !history::is_ready(symbol) {
+                let prices = history::prices(symbol);
+                let pending = indicators::compute(&prices, &settings).pending(&settings, prices.len());
+                entry["status"] = json!("partial");
+                entry["partial"] = json!(true);
+                entry["pending_indicators"] = json!(pending);
+                entry["samples"] = json!(prices.len());
+                entry["required_samples"] = json!(config::get().min_history_samples);
+            }
+            if include_contributions {
+                let (strength, contributions) = strategy::strength(&price_data);
+                entry["strength"] = json!(strength);
+                let contributions: serde_json::Map<String, serde_json::Value> = contributions
+                    .into_iter()
+                    .map(|(factor, share)| (factor.to_string(), json!(share)))
+                    .collect();
+                entry["contributions"] = json!(contributions);
+            }
+            // Only once the signal has changed at least once since startup
+            if let (Some(previous), Some(changed_at)) = (state.previous_signal, state.changed_at) {
+                entry["previous_signal"] = json!(previous);
+                entry["changed_at"] = format::timestamp(changed_at);
+            }
+            resolve_external_signal(&mut entry, symbol, &signal);
+            entry
+        },
+        Err(e) if prices::is_delisted(symbol) => {
+            json!({
+                "symbol": format::symbol(symbol),
+                "error": e,
+                "signal": "delisted_or_missing",
+                "status": "delisted_or_missing",
+                "message": "Coin has been missing from CoinGecko responses; it may have been delisted or renamed",
+                "confidence": 0.0,
+                "timestamp": format::now(),
+            })
+        }
+        Err(e) => match held_signal(symbol, config::get().signal_hold_seconds) {
+            Some(mut entry) => {
+                entry["held_reason"] = json!("fetch_failed");
+                entry["error"] = json!(e);
+                entry
+            }
+            None => json!({
+                "symbol": format::symbol(symbol),
+                "error": e,
+                "signal": "error",
+                "confidence": 0.0,
+                "timestamp": format::now(),
+            }),
+        },
     }
+
 }
 
-fn get_action_from_signal(signal: &str) -> &'static str {
-    match signal {
-        "strong_buy" => "ENTER_LONG_NOW",
-        "buy" => "ENTER_LONG",
-        "strong_sell" => "ENTER_SHORT_NOW",
-        "sell" => "ENTER_SHORT",
-        _ => "HOLD_POSITION"
+// SIGNALS_WARMUP=strict entry for a coin short of MIN_HISTORY_SAMPLES: `insufficient_data`,
+// unless a signal it had once it was ready is still within the `hold`
+fn warming_up_entry(symbol: &str, price_data: &PriceData, hold: u64) -> serde_json::Value {
+    if let Some(mut entry) = held_signal(symbol, hold) {
+        entry["held_reason"] = json!("insufficient_data");
+        entry["samples"] = json!(history::len(symbol));
+        entry["required_samples"] = json!(config::get().min_history_samples);
+        return entry;
     }
+    json!({
+        "symbol": format::symbol(symbol),
+        "price": price_data.price,
+        "change_24h": price_data.change_24h,
+        "signal": "insufficient_data",
+        "status": "insufficient_data",
+        "samples": history::len(symbol),
+        "required_samples": config::get().min_history_samples,
+        "source": price_data.source,
+        "source_type": price_data.source_type,
+        "data_quality": quality::score(symbol, price_data),
+        "timestamp": format::now(),
+    })
+}
+
+// ?format=ndjson: one entry per line, each written as soon as it is ready and the same
+// as in the JSON array. A last line `{"status": "ok", ...}` carries the rest of the JSON
+// document. If an entry can't be written, the last line is `{"status": "error", "message"}`
+// naming the coin instead, and the stream ends there.
+fn ndjson_signals(symbols: Vec<String>, query: &SignalsQuery) -> HttpResponse {
+    let number_format = config::get().number_format;
+    let query = query.clone();
+    let state = Some((symbols.into_iter(), Vec::new(), 0));
+    let body = stream::unfold(state, move |state| {
+        let query = query.clone();
+        async move {
+            let (mut remaining, mut forced, count) = state?;
+            let (mut line, state) = match remaining.next() {
+                Some(symbol) => {
+                    let entry = signal_entry(&symbol, query.no_cache, query.include_contributions, &mut forced).await;
+                    match format::try_to_json_string(&entry, number_format) {
+                        Ok(line) => (line, Some((remaining, forced, count + 1))),
+                        Err(e) => {
+                            println!("❌ /signals ndjson stream aborted at {}: {}", symbol, e);
+                            let error = json!({
+                                "status": "error",
+                                "message": format!("Failed to write the signal for {}: {}", format::symbol(&symbol), e),
+                            });
+                            (error.to_string(), None)
+                        }
+                    }
+                }
+                None => {
+                    let mut summary = document_fields(count, &query, &forced);
+                    summary["status"] = json!("ok");
+                    (format::to_json_string(&summary, number_format), None)
+                }
+            };
+            line.push('\n');
+            Some((Ok::<_, actix_web::Error>(web::Bytes::from(line)), state))
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(NDJSON_CONTENT_TYPE)
+        .streaming(body)
+}
+
+// Cached prices exactly as held, with the data-quality breakdown behind each score.
+// Never goes upstream; coins not fetched yet have no price.
+#[get("/signals/raw")]
+pub async fn get_signals_raw() -> impl Responder {
+    let symbols = config::get().supported_symbols();
+    let entries: Vec<serde_json::Value> = symbols
+        .iter()
+        .map(|symbol| match prices::cached_price(symbol) {
+            Some(price_data) => json!({
+                "symbol": format::symbol(symbol),
+                "price_data": price_data,
+                "samples": history::len(symbol),
+                "data_quality": quality::breakdown(symbol, &price_data),
+            }),
+            None => json!({
+                "symbol": format::symbol(symbol),
+                "price_data": null,
+                "samples": history::len(symbol),
+                "data_quality": null,
+            }),
+        })
+        .collect();
+
+    format::json(HttpResponse::Ok(), &json!({
+        "symbols": entries,
+        "count": entries.len(),
+        "weights": config::get().data_quality_weights,
+        "timestamp": format::now(),
+    }))
+}
+
+// Most changes /signals/changes returns per page
+const MAX_CHANGES_PAGE: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    pub limit: Option<usize>,
+    pub before: Option<u64>,
+}
+
+// Signal transitions across every coin, newest first
+#[get("/signals/changes")]
+pub async fn get_signal_changes(query: web::Query<ChangesQuery>) -> impl Responder {
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_CHANGES_PAGE);
+    let (changes, retained) = signal_tracker::changes(limit, query.before);
+    // A full page may have more behind it
+    let next_before = if changes.len() == limit {
+        changes.last().map(|change| change.id)
+    } else {
+        None
+    };
+
+    HttpResponse::Ok().json(json!({
+        "changes": changes,
+        "count": changes.len(),
+        "next_before": next_before,
+        "retained": retained,
+        "retention": config::get().signal_changes_retention,
+        "timestamp": format::now()
+    }))
 }
 
 // ========== TRADINGVIEW WEBHOOK ==========
-pub async fn tradingview_webhook(data: web::Json<TradingViewWebhook>) -> impl Responder {
+pub async fn tradingview_webhook(body: web::Bytes) -> impl Responder {
     println!("📈 TradingView webhook received!");
+    if config::get().log_webhook_bodies {
+        println!("🐛 Webhook body ({} bytes): {}", body.len(), redacted_body(&body));
+    }
+    // Parsed here rather than by web::Json so a rejected body has been logged first
+    let data: TradingViewWebhook = match serde_json::from_slice(&body) {
+        Ok(data) => data,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": format!("Invalid webhook body: {}", e),
+            }));
+        }
+    };
     
     let symbol = clean_symbol(&data.symbol);
-    let valid_symbols = ["BTC", "ETH", "SOL", "PAXG"];
     
-    if !valid_symbols.contains(&symbol.as_str()) {
+    if !config::get().is_supported(&symbol) {
         return HttpResponse::BadRequest().json(json!({
             "status": "error",
-            "message": format!("Unsupported symbol: {}. Only BTC, ETH, SOL, PAXG.", symbol),
+            "message": format!(
+                "Unsupported symbol: {}. Only {}.",
+                symbol,
+                config::get().supported_symbols().join(", ")
+            ),
+        }));
+    }
+    
+    let raw_action = data.action.as_deref().map(str::trim).filter(|a| !a.is_empty());
+    let action = raw_action.map(|raw| match config::get().alert_action_map.normalize(raw) {
+        Some(signal) => signal.to_string(),
+        None => {
+            println!("❓ Unmapped TradingView action '{}' for {} stored as {} (see ALERT_ACTION_MAP)", raw, symbol, UNKNOWN_ACTION);
+            UNKNOWN_ACTION.to_string()
+        }
+    });
+    if alerts::is_duplicate(&symbol, action.as_deref(), data.price) {
+        println!("🔁 Duplicate TradingView alert for {} dropped", symbol);
+        return HttpResponse::Ok().json(json!({
+            "status": "duplicate",
+            "message": "An equivalent alert was received recently; not stored",
+            "scope": config::get().alert_dedup_scope,
+            "timestamp": format::now()
         }));
     }
+    let severity = config::get().alert_severity.classify(&symbol, action.as_deref());
     
     let alert = TradingViewAlert {
         symbol: symbol.clone(),
         price: data.price,
         alert_name: data.alert_name.clone().unwrap_or_else(|| "Unknown".to_string()),
+        action,
+        raw_action: raw_action.map(str::to_string),
+        severity,
+        detail: None,
+        observed: observed_price(&symbol, data.price),
         timestamp: Utc::now().timestamp(),
     };
     
-    let alerts_store = ALERTS.get_or_init(|| Arc::new(Mutex::new(Vec::new())));
-    let mut alerts = alerts_store.lock().unwrap();
-    alerts.push(alert.clone());
-    
-    // FIXED: Store length in variable before using it
-    let alerts_len = alerts.len();
-    if alerts_len > 50 {
-        alerts.drain(0..alerts_len - 50);
-    }
+    store_alert(alert.clone());
     
     HttpResponse::Ok().json(json!({
         "status": "success",
         "alert": alert,
-        "timestamp": Utc::now().timestamp()
+        "timestamp": format::now()
     }))
 }
 
+// ALERT_PRICE_ENRICHMENT: our cached price for the symbol as the alert arrives. Never
+// fetches, so a coin without a cached price gets none.
+fn observed_price(symbol: &str, alert_price: f64) -> Option<ObservedPrice> {
+    if !config::get().alert_price_enrichment {
+        return None;
+    }
+    let cached = prices::cached_price(symbol)?;
+    let observed = ObservedPrice::new(alert_price, cached.price, cached.timestamp);
+    println!(
+        "🔍 {} alert price ${} is {:+.4}% from our ${} ({}s old)",
+        symbol,
+        alert_price,
+        observed.difference_percent,
+        cached.price,
+        (Utc::now().timestamp() - cached.timestamp).max(0)
+    );
+    Some(observed)
+}
+
+// Keys whose values never reach the log, matched case-insensitively as substrings
+const SECRET_KEYS: &[&str] = &["passphrase", "password", "secret", "token", "api_key"];
+
+// Most of a webhook body LOG_WEBHOOK_BODIES prints
+const MAX_LOGGED_BODY: usize = 4096;
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+fn redact_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) {
+                    *value = json!("[redacted]");
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+// The body as sent, with secret values masked. JSON is redacted by key; anything else
+// (a template typo is the usual reason to look) has the text after each secret key cut
+// up to the next `,`, `}` or line break.
+fn redacted_body(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    let redacted = match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => {
+            let lower = text.to_ascii_lowercase();
+            let mut out = String::new();
+            let mut pos = 0;
+            while let Some((start, secret)) = SECRET_KEYS
+                .iter()
+                .filter_map(|secret| lower[pos..].find(secret).map(|i| (pos + i, secret)))
+                .min_by_key(|(start, _)| *start)
+            {
+                let value_start = start + secret.len();
+                let value_end = lower[value_start..]
+                    .find([',', '}', '\n'])
+                    .map_or(lower.len(), |i| value_start + i);
+                out.push_str(&text[pos..value_start]);
+                out.push_str("=[redacted]");
+                pos = value_end;
+            }
+            out.push_str(&text[pos..]);
+            out
+        }
+    };
+    match redacted.char_indices().nth(MAX_LOGGED_BODY) {
+        Some((cut, _)) => format!("{}... (truncated)", &redacted[..cut]),
+        None => redacted,
+    }
+}
+
+// Keeps the newest ALERT_RETENTION alerts (appending each to ALERT_STORE_FILE) and
+// sends a throttled alert notification.
+pub fn store_alert(alert: TradingViewAlert) {
+    {
+        let _write = alert_store::write_lock();
+        {
+            let mut alerts = alerts_store().lock().unwrap();
+            alerts.push(alert.clone());
+            
+            // FIXED: Store length in variable before using it
+            let alerts_len = alerts.len();
+            let retention = config::get().alert_retention;
+            if alerts_len > retention {
+                alerts.drain(0..alerts_len - retention);
+            }
+        }
+        alert_store::append(&alert);
+    }
+    
+    notifier::notify_alert(&alert.symbol, alert.action.as_deref(), alert.price, format!(
+        "[{:?}] {} {} at ${:.2} ({})",
+        alert.severity,
+        alert.symbol,
+        alert.action.as_deref().unwrap_or("alert"),
+        alert.price,
+        alert.alert_name
+    ));
+}
+
 fn clean_symbol(raw_symbol: &str) -> String {
+    // Upper-case first so quote suffixes are stripped whatever casing TradingView sends
+    let raw_symbol = raw_symbol.to_uppercase();
+    let raw_symbol = raw_symbol.as_str();
     let cleaned = if raw_symbol.contains(":") {
-        raw_symbol.split(':').last().unwrap_or(raw_symbol)
+        raw_symbol.split(':').next_back().unwrap_or(raw_symbol)
             .replace("USDT", "")
             .replace("USD", "")
     } else {
@@ -306,80 +932,253 @@ fn clean_symbol(raw_symbol: &str) -> String {
 }
 
 // ========== ALERTS ENDPOINTS ==========
+fn parse_severity_filter(query: &AlertsQuery) -> Result<Option<Severity>, String> {
+    match &query.severity {
+        None => Ok(None),
+        Some(raw) => Severity::parse(raw)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid severity: {}. Use info, warning or critical.", raw)),
+    }
+}
+
 #[get("/tradingview-alerts")]
-pub async fn get_tradingview_alerts() -> impl Responder {
+pub async fn get_tradingview_alerts(query: web::Query<AlertsQuery>) -> impl Responder {
+    let severity = match parse_severity_filter(&query) {
+        Ok(severity) => severity,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
+    
     let alerts = ALERTS.get()
-        .map(|store| store.lock().unwrap().clone())
-        .unwrap_or_else(Vec::new);
+        .map(|store| {
+            let store_lock = store.lock().unwrap();
+            store_lock.iter()
+                .filter(|a| severity.is_none_or(|s| a.severity == s))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let alerts = alert_entries(alerts, query.include_age);
     
     HttpResponse::Ok().json(json!({
         "alerts": alerts,
         "count": alerts.len(),
-        "timestamp": Utc::now().timestamp()
+        "timestamp": format::now()
     }))
 }
 
 #[get("/alerts/{symbol}")]
-pub async fn get_symbol_alerts(symbol: web::Path<String>) -> impl Responder {
+pub async fn get_symbol_alerts(
+    symbol: web::Path<String>,
+    query: web::Query<AlertsQuery>,
+) -> impl Responder {
     let symbol_str = symbol.into_inner().to_uppercase();
+    let severity = match parse_severity_filter(&query) {
+        Ok(severity) => severity,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
     
     let alerts = ALERTS.get()
         .map(|store| {
             let store_lock = store.lock().unwrap();
             store_lock.iter()
                 .filter(|a| a.symbol == symbol_str)
+                .filter(|a| severity.is_none_or(|s| a.severity == s))
                 .cloned()
                 .collect::<Vec<_>>()
         })
-        .unwrap_or_else(Vec::new);
+        .unwrap_or_default();
+    let alerts = alert_entries(alerts, query.include_age);
     
     HttpResponse::Ok().json(json!({
-        "symbol": symbol_str,
+        "symbol": format::symbol(&symbol_str),
         "alerts": alerts,
         "count": alerts.len(),
-        "timestamp": Utc::now().timestamp()
+        "timestamp": format::now()
+    }))
+}
+
+// Most buckets a histogram may return, so a tiny bucket over a long window can't
+// build a huge response.
+const MAX_HISTOGRAM_BUCKETS: i64 = 1000;
+
+// "90s", "15m", "1h", "7d"; None as well for a duration too long to count in seconds
+pub fn parse_duration(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let unit_at = raw.find(|c: char| !c.is_ascii_digit())?;
+    let value: i64 = raw[..unit_at].parse().ok()?;
+    let unit = match &raw[unit_at..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+    value.checked_mul(unit).filter(|seconds| *seconds > 0)
+}
+
+#[get("/alerts/{symbol}/histogram")]
+pub async fn get_symbol_alert_histogram(
+    symbol: web::Path<String>,
+    query: web::Query<HistogramQuery>,
+) -> impl Responder {
+    let symbol_str = symbol.into_inner().to_uppercase();
+    let bad_request = |message: String| {
+        HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": message,
+        }))
+    };
+    
+    let bucket_raw = query.bucket.as_deref().unwrap_or("1h");
+    let Some(bucket) = parse_duration(bucket_raw) else {
+        return bad_request(format!("Invalid bucket: {}. Use e.g. 15m, 1h or 1d.", bucket_raw));
+    };
+    let window_raw = query.window.as_deref().unwrap_or("24h");
+    let Some(window) = parse_duration(window_raw) else {
+        return bad_request(format!("Invalid window: {}. Use e.g. 24h or 7d.", window_raw));
+    };
+    let split_by_action = match query.split.as_deref() {
+        None => false,
+        Some("action") => true,
+        Some(other) => return bad_request(format!("Invalid split: {}. Use action.", other)),
+    };
+    let severity = match parse_severity_filter(&AlertsQuery { severity: query.severity.clone(), include_age: false }) {
+        Ok(severity) => severity,
+        Err(message) => return bad_request(message),
+    };
+    
+    // Buckets are aligned to multiples of the bucket size; the last one holds now
+    let Some(bucket_count) = (window - 1).checked_add(bucket).map(|span| span / bucket) else {
+        return bad_request(format!("Window {} with bucket {} is too long", window_raw, bucket_raw));
+    };
+    if bucket_count > MAX_HISTOGRAM_BUCKETS {
+        return bad_request(format!(
+            "window/bucket gives {} buckets; the maximum is {}",
+            bucket_count, MAX_HISTOGRAM_BUCKETS
+        ));
+    }
+    let last_start = Utc::now().timestamp().div_euclid(bucket) * bucket;
+    let first_start = last_start - (bucket_count - 1) * bucket;
+    
+    let mut counts = vec![0u64; bucket_count as usize];
+    let mut by_action = vec![std::collections::BTreeMap::<String, u64>::new(); bucket_count as usize];
+    if let Some(store) = ALERTS.get() {
+        for alert in store.lock().unwrap().iter() {
+            if alert.symbol != symbol_str || !severity.is_none_or(|s| alert.severity == s) {
+                continue;
+            }
+            if alert.timestamp < first_start || alert.timestamp >= last_start + bucket {
+                continue;
+            }
+            let index = ((alert.timestamp - first_start) / bucket) as usize;
+            counts[index] += 1;
+            let action = alert.action.clone().unwrap_or_else(|| "none".to_string());
+            *by_action[index].entry(action).or_insert(0) += 1;
+        }
+    }
+    
+    // Every bucket is listed, empty ones with a zero count, so chart axes stay continuous
+    let buckets: Vec<serde_json::Value> = counts.iter()
+        .zip(by_action)
+        .enumerate()
+        .map(|(i, (count, actions))| {
+            let mut entry = json!({
+                "start": format::timestamp(first_start + i as i64 * bucket),
+                "count": count,
+            });
+            if split_by_action {
+                entry["by_action"] = json!(actions);
+            }
+            entry
+        })
+        .collect();
+    
+    HttpResponse::Ok().json(json!({
+        "symbol": format::symbol(&symbol_str),
+        "bucket_seconds": bucket,
+        "window_seconds": bucket_count * bucket,
+        "buckets": buckets,
+        "total": counts.iter().sum::<u64>(),
+        "timestamp": format::now()
     }))
 }
 
 // ========== UTILITY ENDPOINTS ==========
-pub async fn clear_alerts() -> impl Responder {
+pub async fn clear_alerts(_admin: Admin) -> impl Responder {
     if let Some(alerts_store) = ALERTS.get() {
-        let mut alerts = alerts_store.lock().unwrap();
-        alerts.clear();
+        let _write = alert_store::write_lock();
+        alerts_store.lock().unwrap().clear();
+        if let Err(e) = alert_store::rewrite(&[]) {
+            println!("❌ {}", e);
+        }
     }
     
     HttpResponse::Ok().json(json!({
         "status": "success",
         "message": "All alerts cleared",
-        "timestamp": Utc::now().timestamp()
+        "timestamp": format::now()
     }))
 }
 
-pub async fn clear_cache() -> impl Responder {
-    if let Some(cache) = PRICE_CACHE.get() {
-        let mut cache_lock = cache.lock().unwrap();
-        cache_lock.clear();
-    }
+pub async fn clear_cache(_admin: Admin) -> impl Responder {
+    prices::clear_cache();
     
     HttpResponse::Ok().json(json!({
         "status": "success",
         "message": "Price cache cleared",
-        "timestamp": Utc::now().timestamp()
+        "timestamp": format::now()
     }))
 }
 
+// Runs a refresh cycle now; 429 with Retry-After while one ran too recently
+pub async fn admin_refresh(_admin: Admin) -> impl Responder {
+    match refresher::refresh_now().await {
+        Ok(summary) => HttpResponse::Ok().json(json!({
+            "status": match (summary.refreshed.is_empty(), summary.failed.is_empty()) {
+                (_, true) => "success",
+                (false, false) => "partial",
+                (true, false) => "failed",
+            },
+            "refreshed": summary.refreshed,
+            "failed": summary.failed,
+            "timestamp": format::now()
+        })),
+        Err(retry_after) => {
+            let error = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "refresh_too_soon",
+                format!(
+                    "Prices were refreshed less than {}s ago; try again in {}s",
+                    config::get().min_refresh_interval_seconds, retry_after
+                ),
+            );
+            error
+                .response_builder()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(error.body())
+        }
+    }
+}
+
 // ========== CACHE STATS ==========
 #[get("/cache-stats")]
 pub async fn get_cache_stats() -> impl Responder {
-    let cache_info = if let Some(cache) = PRICE_CACHE.get() {
-        let cache_lock = cache.lock().unwrap();
-        json!({
-            "entries": cache_lock.len(),
-            "symbols": cache_lock.keys().cloned().collect::<Vec<_>>()
-        })
-    } else {
-        json!({"entries": 0, "symbols": []})
-    };
+    let cached_symbols = prices::cached_symbols();
+    let cache_info = json!({
+        "entries": cached_symbols.len(),
+        "symbols": format::symbols(&cached_symbols),
+        "coalesced_fetches": prices::coalesced_fetches()
+    });
     
     let alerts_info = if let Some(alerts_store) = ALERTS.get() {
         let alerts = alerts_store.lock().unwrap();
@@ -392,9 +1191,10 @@ pub async fn get_cache_stats() -> impl Responder {
     
     HttpResponse::Ok().json(json!({
         "price_cache": cache_info,
+        "explanation_cache": ai_explanation::cache_stats(),
         "alerts_store": alerts_info,
-        "timestamp": Utc::now().timestamp(),
-        "supported_coins": ["BTC", "ETH", "SOL", "PAXG"]
+        "timestamp": format::now(),
+        "supported_coins": format::symbols(&config::get().supported_symbols())
     }))
 }
 
@@ -402,23 +1202,60 @@ pub async fn get_cache_stats() -> impl Responder {
 #[derive(Deserialize)]
 pub struct ExplainQuery {
     pub symbol: Option<String>,
+    pub verbosity: Option<String>,
+    // plain (default), markdown or html
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExplainAllQuery {
+    pub verbosity: Option<String>,
+    pub format: Option<String>,
+    // symbol (default) or signal
+    pub group_by: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExplainBatchQuery {
+    pub symbols: Option<String>,
+    pub verbosity: Option<String>,
+    pub format: Option<String>,
+}
+
+fn parse_verbosity(raw: Option<&str>) -> Result<Verbosity, String> {
+    raw.map(Verbosity::parse).unwrap_or(Ok(Verbosity::Normal))
+}
+
+fn parse_text_format(raw: Option<&str>) -> Result<TextFormat, String> {
+    raw.map(TextFormat::parse).unwrap_or(Ok(TextFormat::Plain))
 }
 
 // Regular async function (NOT #[get] macro)
 pub async fn explain_signal(query: web::Query<ExplainQuery>) -> impl Responder {
-    let explainer = AIExplainer::new();
+    let parsed = parse_verbosity(query.verbosity.as_deref())
+        .and_then(|verbosity| Ok((verbosity, parse_text_format(query.format.as_deref())?)));
+    let (verbosity, text_format) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
+    
+    let explainer = AIExplainer::new().with_format(text_format);
     
     // Get symbol from query or default to BTC
     let requested_symbol = query.symbol.clone().unwrap_or_else(|| "BTC".to_string());
     let symbol_upper = requested_symbol.to_uppercase();
     
     // Validate symbol
-    let valid_symbols = ["BTC", "ETH", "SOL", "PAXG"];
-    if !valid_symbols.contains(&symbol_upper.as_str()) {
+    if !config::get().is_supported(&symbol_upper) {
         return HttpResponse::BadRequest().json(json!({
             "error": "Unsupported symbol",
-            "message": format!("Only {} are supported", valid_symbols.join(", ")),
-            "symbol": symbol_upper
+            "message": format!("Only {} are supported", config::get().supported_symbols().join(", ")),
+            "symbol": format::symbol(&symbol_upper)
         }));
     }
     
@@ -427,6 +1264,7 @@ pub async fn explain_signal(query: web::Query<ExplainQuery>) -> impl Responder {
         Ok(price_data) => {
             // Generate signal from price data
             let (signal, _) = generate_signal(&price_data);
+            let reasons = signal_reasons(&price_data);
             
             // Create AI explanation
             let explanation = explainer.explain_signal(
@@ -434,6 +1272,8 @@ pub async fn explain_signal(query: web::Query<ExplainQuery>) -> impl Responder {
                 &signal,
                 price_data.price,
                 price_data.change_24h,
+                &reasons,
+                verbosity,
             ).await;
             
             HttpResponse::Ok().json(explanation)
@@ -442,53 +1282,471 @@ pub async fn explain_signal(query: web::Query<ExplainQuery>) -> impl Responder {
             HttpResponse::ServiceUnavailable().json(json!({
                 "error": "Failed to fetch data",
                 "message": e,
-                "symbol": symbol_upper
+                "symbol": format::symbol(&symbol_upper)
             }))
         }
     }
 }
 
+// Placeholder explanation for a coin whose price couldn't be fetched
+fn error_explanation(symbol: &str, error: &str, text_format: TextFormat) -> SignalExplanation {
+    SignalExplanation {
+        symbol: symbol.to_string(),
+        current_signal: "error".to_string(),
+        explanation: text_format.render(&format!("Failed to fetch data: {}", error), &[]),
+        confidence: 0.0,
+        emoji: "❌".to_string(),
+        vibe: "Error vibes".to_string(),
+        simple_advice: "Data unavailable".to_string(),
+        risk_level: "Unknown".to_string(),
+        source: "error".to_string(),
+        reasons: Vec::new(),
+        low_liquidity_period: None,
+    }
+}
+
 // Regular async function (NOT #[get] macro)
-pub async fn explain_all_signals() -> impl Responder {
-    let explainer = AIExplainer::new();
-    let symbols = vec!["BTC", "ETH", "SOL", "PAXG"];
+// Explains the listed coins in one call. Each explanation still goes through the
+// explainer's concurrency limit and daily budget; the cap only bounds one request.
+pub async fn explain_signals(query: web::Query<ExplainBatchQuery>) -> impl Responder {
+    let parsed = parse_verbosity(query.verbosity.as_deref())
+        .and_then(|verbosity| Ok((verbosity, parse_text_format(query.format.as_deref())?)));
+    let (verbosity, text_format) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
+    let explainer = AIExplainer::new().with_format(text_format);
+
+    let mut symbols: Vec<String> = Vec::new();
+    for symbol in query.symbols.as_deref().unwrap_or("").split(',') {
+        let symbol = symbol.trim().to_uppercase();
+        if !symbol.is_empty() && !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    if symbols.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": "symbols is required, e.g. ?symbols=BTC,ETH",
+        }));
+    }
+
+    let max_symbols = config::get().explain_batch_max_symbols;
+    if symbols.len() > max_symbols {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": format!(
+                "At most {} symbols per request, got {}",
+                max_symbols,
+                symbols.len()
+            ),
+            "max_symbols": max_symbols,
+        }));
+    }
+    if let Some(unknown) = symbols.iter().find(|s| !config::get().is_supported(s)) {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": format!("Unsupported symbol: {}", unknown),
+            "symbol": format::symbol(unknown),
+        }));
+    }
+
     let mut explanations = Vec::new();
-    
-    for symbol in symbols {
+    for symbol in &symbols {
         match fetch_live_price(symbol).await {
             Ok(price_data) => {
                 let (signal, _) = generate_signal(&price_data);
-                
+                let reasons = signal_reasons(&price_data);
                 let explanation = explainer.explain_signal(
                     symbol,
                     &signal,
                     price_data.price,
                     price_data.change_24h,
+                    &reasons,
+                    verbosity,
                 ).await;
-                
                 explanations.push(explanation);
             },
-            Err(e) => {
-                // Add error explanation
-                explanations.push(SignalExplanation {
-                    symbol: symbol.to_string(),
-                    current_signal: "error".to_string(),
-                    explanation: format!("Failed to fetch data: {}", e),
-                    confidence: 0.0,
-                    emoji: "❌".to_string(),
-                    vibe: "Error vibes".to_string(),
-                    simple_advice: "Data unavailable".to_string(),
-                    risk_level: "Unknown".to_string(),
-                });
-            }
+            Err(e) => explanations.push(error_explanation(symbol, &e, text_format)),
         }
-        // Small delay to avoid rate limiting
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
     }
-    
+
     HttpResponse::Ok().json(json!({
         "explanations": explanations,
         "count": explanations.len(),
-        "timestamp": Utc::now().timestamp()
+        "timestamp": format::now()
+    }))
+}
+
+// Regular async function (NOT #[get] macro)
+pub async fn explain_all_signals(query: web::Query<ExplainAllQuery>) -> impl Responder {
+    let parsed = parse_verbosity(query.verbosity.as_deref()).and_then(|verbosity| {
+        let group_by = query.group_by.as_deref().map(GroupBy::parse).unwrap_or(Ok(GroupBy::Symbol))?;
+        Ok((verbosity, group_by, parse_text_format(query.format.as_deref())?))
+    });
+    let (verbosity, group_by, text_format) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
+    let explainer = AIExplainer::new().with_format(text_format);
+    let symbols = config::get().supported_symbols();
+    
+    // Prices come from the cache in normal operation, so working out the current
+    // signals is cheap; only the explanations are worth throttling.
+    let mut current = Vec::new();
+    for symbol in &symbols {
+        let price = fetch_live_price(symbol).await;
+        let signal = price.as_ref()
+            .map(|p| generate_signal(p).0)
+            .unwrap_or_else(|_| "error".to_string());
+        current.push((symbol.clone(), signal, price));
+    }
+    let signature: Vec<(String, String)> = current.iter()
+        .map(|(symbol, signal, _)| (symbol.clone(), signal.clone()))
+        .collect();
+    
+    let min_interval = Duration::from_secs(config::get().explain_all_min_interval_seconds);
+    {
+        let last_sweep = LAST_SWEEP.get_or_init(|| Mutex::new(None)).lock().unwrap();
+        if let Some(sweep) = last_sweep.as_ref() {
+            if sweep.at.elapsed() < min_interval
+                && sweep.verbosity == verbosity
+                && sweep.text_format == text_format
+                && sweep.group_by == group_by
+                && sweep.signature == signature
+            {
+                return HttpResponse::Ok().json(json!({
+                    group_by.key(): sweep.entries,
+                    "count": sweep.entries.len(),
+                    "fresh": false,
+                    "generated_at": format::timestamp(sweep.generated_at),
+                    "timestamp": format::now()
+                }));
+            }
+        }
+    }
+    
+    let entries = match group_by {
+        GroupBy::Symbol => explain_each(&explainer, current, verbosity, text_format).await,
+        GroupBy::Signal => explain_by_signal(&explainer, current, verbosity, text_format).await,
+    };
+    
+    // Sweeps with fetch errors aren't reused, so a recovered upstream shows up immediately
+    let generated_at = Utc::now().timestamp();
+    if signature.iter().all(|(_, signal)| signal != "error") {
+        *LAST_SWEEP.get_or_init(|| Mutex::new(None)).lock().unwrap() = Some(Sweep {
+            at: Instant::now(),
+            generated_at,
+            verbosity,
+            text_format,
+            group_by,
+            signature,
+            entries: entries.clone(),
+        });
+    }
+    
+    HttpResponse::Ok().json(json!({
+        group_by.key(): entries,
+        "count": entries.len(),
+        "fresh": true,
+        "generated_at": format::timestamp(generated_at),
+        "timestamp": format::now()
     }))
 }
+
+type CurrentSignal = (String, String, Result<PriceData, String>);
+
+async fn explain_each(
+    explainer: &AIExplainer,
+    current: Vec<CurrentSignal>,
+    verbosity: Verbosity,
+    text_format: TextFormat,
+) -> Vec<Value> {
+    let mut explanations = Vec::new();
+    for (symbol, signal, price) in current {
+        match price {
+            Ok(price_data) => {
+                let reasons = signal_reasons(&price_data);
+                
+                let explanation = explainer.explain_signal(
+                    &symbol,
+                    &signal,
+                    price_data.price,
+                    price_data.change_24h,
+                    &reasons,
+                    verbosity,
+                ).await;
+                
+                explanations.push(json!(explanation));
+            },
+            Err(e) => {
+                explanations.push(json!(error_explanation(&symbol, &e, text_format)));
+            }
+        }
+        // Small delay to avoid rate limiting
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    }
+    explanations
+}
+
+// One explanation per distinct signal, groups in order of their first coin; coins whose
+// price couldn't be fetched get their error entries at the end.
+async fn explain_by_signal(
+    explainer: &AIExplainer,
+    current: Vec<CurrentSignal>,
+    verbosity: Verbosity,
+    text_format: TextFormat,
+) -> Vec<Value> {
+    let mut groups: Vec<(String, Vec<GroupMember>)> = Vec::new();
+    let mut errors = Vec::new();
+    for (symbol, signal, price) in current {
+        let price_data = match price {
+            Ok(price_data) => price_data,
+            Err(e) => {
+                errors.push(json!(error_explanation(&symbol, &e, text_format)));
+                continue;
+            }
+        };
+        let member = GroupMember {
+            symbol,
+            price: price_data.price,
+            change_24h: price_data.change_24h,
+            reasons: signal_reasons(&price_data),
+        };
+        match groups.iter_mut().find(|(group, _)| *group == signal) {
+            Some((_, members)) => members.push(member),
+            None => groups.push((signal, vec![member])),
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (signal, members) in groups {
+        entries.push(json!(explainer.explain_group(&signal, &members, verbosity).await));
+    }
+    entries.extend(errors);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{init_config, price_data, track_cached_coins};
+    use actix_web::{test as actix_test, App};
+
+    // `count` distinct symbols the service doesn't track
+    fn untracked_symbols(count: usize) -> String {
+        (0..count).map(|n| format!("NOTACOIN{}", n)).collect::<Vec<_>>().join(",")
+    }
+
+    async fn explain_batch(symbols: &str) -> (StatusCode, Value) {
+        let app = actix_test::init_service(App::new().route("/explain-signals", web::get().to(explain_signals))).await;
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/explain-signals?symbols={}", symbols))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        let status = response.status();
+        (status, actix_test::read_body_json(response).await)
+    }
+
+    #[actix_web::test]
+    async fn explain_batch_at_the_cap_is_accepted() {
+        init_config();
+        let max = config::get().explain_batch_max_symbols;
+        let symbols = track_cached_coins("CAPCOIN", max);
+
+        // Duplicates count once
+        let (status, body) = explain_batch(&format!("{},capcoin0", symbols.join(","))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], max);
+        let explanations = body["explanations"].as_array().unwrap();
+        let explained: Vec<&str> = explanations.iter().map(|e| e["symbol"].as_str().unwrap()).collect();
+        assert_eq!(explained, symbols);
+        assert!(explanations.iter().all(|e| e["source"] != "error"), "{}", body);
+    }
+
+    #[actix_web::test]
+    async fn explain_batch_above_the_cap_is_rejected() {
+        init_config();
+        let max = config::get().explain_batch_max_symbols;
+        let (status, body) = explain_batch(&untracked_symbols(max + 1)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], format!("At most {} symbols per request, got {}", max, max + 1));
+        assert_eq!(body["max_symbols"], max);
+    }
+
+    #[test]
+    fn durations_that_overflow_are_invalid() {
+        assert_eq!(parse_duration("15m"), Some(900));
+        assert_eq!(parse_duration("7d"), Some(604_800));
+        assert_eq!(parse_duration("0h"), None);
+        assert_eq!(parse_duration(&format!("{}s", i64::MAX)), Some(i64::MAX));
+        assert_eq!(parse_duration("99999999999999999d"), None);
+        assert_eq!(parse_duration(&format!("{}m", i64::MAX / 60 + 1)), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+    }
+
+    #[actix_web::test]
+    async fn oversized_histogram_durations_are_rejected() {
+        init_config();
+        let app = actix_test::init_service(App::new().service(get_symbol_alert_histogram)).await;
+        for (query, message) in [
+            ("bucket=99999999999999999d", "Invalid bucket: 99999999999999999d. Use e.g. 15m, 1h or 1d."),
+            ("window=99999999999999999d", "Invalid window: 99999999999999999d. Use e.g. 24h or 7d."),
+            (
+                "bucket=9223372036854775807s&window=2s",
+                "Window 2s with bucket 9223372036854775807s is too long",
+            ),
+        ] {
+            let request = actix_test::TestRequest::get()
+                .uri(&format!("/alerts/BTC/histogram?{}", query))
+                .to_request();
+            let response = actix_test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+            let body: Value = actix_test::read_body_json(response).await;
+            assert_eq!(body["message"], message);
+        }
+    }
+
+    #[test]
+    fn prices_with_signal_leave_signal_history_alone() {
+        init_config();
+        let symbol = "READONLY";
+        let observed_at = Utc::now().timestamp() - 60;
+        signal_tracker::observe(symbol, "hold", 0.5, 100.0, observed_at);
+        let (_, changes_before) = signal_tracker::changes(1, None);
+
+        let entry = with_signal(price_data(symbol, 120.0, 12.0), true);
+        assert_eq!(entry["signal"], "strong_sell");
+
+        let state = signal_tracker::recent(symbol, 3600).unwrap();
+        assert_eq!(state.signal, "hold");
+        assert_eq!(state.changed_at, None);
+        assert_eq!(state.observed_at, observed_at);
+        assert_eq!(signal_tracker::changes(1, None).1, changes_before);
+        signal_tracker::forget(symbol);
+    }
+
+    #[test]
+    fn brief_gap_holds_last_signal_with_fading_confidence() {
+        init_config();
+        let symbol = "GAPPY";
+        signal_tracker::observe(symbol, "buy", 0.8, 100.0, Utc::now().timestamp() - 15);
+
+        let entry = held_signal(symbol, 60).unwrap();
+        assert_eq!(entry["signal"], "buy");
+        assert_eq!(entry["held"], true);
+        assert_eq!(entry["price"], 100.0);
+        // 15s into a 60s hold; a second may tick over between observing and reading
+        let age = entry["data_age_seconds"].as_i64().unwrap();
+        assert!((15..=16).contains(&age));
+        let confidence = entry["confidence"].as_f64().unwrap();
+        assert!((0.58..=0.6).contains(&confidence), "{}", confidence);
+
+        // Past the hold, or with it disabled, the gap is reported as usual
+        assert!(held_signal(symbol, 10).is_none());
+        assert!(held_signal(symbol, 0).is_none());
+        signal_tracker::forget(symbol);
+    }
+
+    // Each key with the JSON type of its value
+    fn shape(value: &Value) -> Vec<(String, &'static str)> {
+        let kind = |value: &Value| match value {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let mut shape: Vec<_> = value.as_object().unwrap().iter().map(|(k, v)| (k.clone(), kind(v))).collect();
+        shape.sort();
+        shape
+    }
+
+    #[actix_web::test]
+    async fn ndjson_lines_match_the_json_document() {
+        init_config();
+        // Unknown to CoinGecko, so each entry fails fast without going upstream
+        let symbols = vec!["NDJSONA".to_string(), "NDJSONB".to_string()];
+        let query = SignalsQuery {
+            no_cache: true,
+            include_thresholds: true,
+            include_contributions: true,
+            format: None,
+        };
+        let mut document = signals_document(&symbols, &query).await;
+
+        let response = ndjson_signals(symbols, &query);
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(body.ends_with(b"\n"));
+        let mut lines: Vec<Value> = body
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+
+        let mut summary = lines.pop().unwrap();
+        let entries = document["signals"].as_array().unwrap();
+        assert_eq!(lines.len(), entries.len());
+        for (line, entry) in lines.iter().zip(entries) {
+            assert_eq!(line["symbol"], entry["symbol"]);
+            assert_eq!(shape(line), shape(entry));
+        }
+
+        assert_eq!(summary["status"], "ok");
+        assert_eq!(summary["count"], 2);
+        assert_eq!(summary["forced_fetch"]["symbols"], json!([]));
+        summary.as_object_mut().unwrap().remove("status");
+        document.as_object_mut().unwrap().remove("signals");
+        assert_eq!(shape(&summary), shape(&document));
+        assert!(shape(&summary).contains(&("thresholds".to_string(), "object")));
+        assert!(shape(&summary).iter().any(|(key, _)| key == "next_refresh_in_seconds"));
+    }
+
+    #[test]
+    fn warming_up_coin_is_not_held_from_refresher_cycles() {
+        init_config();
+        let symbol = "WARMINGUP";
+        let price = price_data(symbol, 100.0, -12.0);
+
+        // Refresher cycles during warm-up record nothing to hold on to
+        assert_eq!(refresher::on_refreshed(symbol, &price, false), "insufficient_data");
+        assert!(signal_tracker::recent(symbol, 3600).is_none());
+        let entry = warming_up_entry(symbol, &price, 60);
+        assert_eq!(entry["signal"], "insufficient_data");
+        assert_eq!(entry.get("held"), None);
+
+        // Once it was ready, a later gap is bridged with the signal it had
+        assert_eq!(refresher::on_refreshed(symbol, &price, true), "strong_buy");
+        let entry = warming_up_entry(symbol, &price, 60);
+        assert_eq!(entry["signal"], "strong_buy");
+        assert_eq!(entry["held"], true);
+        signal_tracker::forget(symbol);
+    }
+
+    #[actix_web::test]
+    async fn health_lists_every_enabled_route() {
+        init_config();
+        let app = actix_test::init_service(App::new().service(health_check)).await;
+        let request = actix_test::TestRequest::get().uri("/health").to_request();
+        let body: Value = actix_test::call_and_read_body_json(&app, request).await;
+
+        let mut expected: Vec<&str> = routes::ROUTES.iter().map(|(_, path)| *path).collect();
+        expected.dedup();
+        assert_eq!(body["endpoints"], json!(expected));
+        for path in ["/ready", "/alerts/{symbol}/histogram", "/paper/trades", "/clear-cache"] {
+            assert!(expected.contains(&path), "{}", path);
+        }
+    }
+}
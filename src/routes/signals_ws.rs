@@ -0,0 +1,406 @@
+use actix_web::http::StatusCode;
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+
+use super::stream::requested_symbols;
+use crate::coingecko::PriceData;
+use crate::config;
+use crate::error::ApiError;
+use crate::format;
+use crate::history;
+use crate::indicators;
+use crate::liquidity;
+use crate::prices;
+use crate::signal_tracker;
+use crate::streams::{self, StreamGuard, StreamKind};
+use crate::strategy::{generate_signal, get_action_from_signal, signal_reasons};
+use crate::ws::{self, Session};
+
+const HEARTBEAT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Deserialize)]
+pub struct SignalsWsQuery {
+    pub symbols: Option<String>,
+    // Overrides SIGNALS_WS_BATCH_MS for this connection; 0 pushes every refresh
+    pub batch_ms: Option<u64>,
+}
+
+// How much each pushed entry carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Detail {
+    // Signal, confidence and action only
+    Summary,
+    // Plus the latest indicator values and the reasons behind the signal
+    Full,
+}
+
+impl Detail {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "summary" => Ok(Detail::Summary),
+            "full" => Ok(Detail::Full),
+            other => Err(format!("Invalid detail: {}. Use summary or full.", other)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Detail::Summary => "summary",
+            Detail::Full => "full",
+        }
+    }
+}
+
+// One of {"subscribe": [..]} (add), {"unsubscribe": [..]} (remove) or {"set": [..]}
+// (replace), optionally with "detail"; or just {"detail": ..}.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientMessage {
+    subscribe: Option<Vec<String>>,
+    unsubscribe: Option<Vec<String>>,
+    set: Option<Vec<String>>,
+    detail: Option<String>,
+}
+
+enum Change {
+    Add(Vec<String>),
+    Remove(Vec<String>),
+    Replace(Vec<String>),
+    DetailOnly,
+}
+
+// Schema checks beyond what serde enforces: one operation per message, known symbols
+fn parse_message(text: &str) -> Result<(Change, Option<Detail>), String> {
+    let message: ClientMessage =
+        serde_json::from_str(text).map_err(|e| format!("Invalid message: {}", e))?;
+    let detail = message.detail.as_deref().map(Detail::parse).transpose()?;
+    let symbols = |list: Vec<String>, op: &str| -> Result<Vec<String>, String> {
+        if list.is_empty() && op != "set" {
+            return Err(format!("{} needs at least one symbol", op));
+        }
+        requested_symbols(Some(&list.join(",")))
+    };
+
+    let change = match (message.subscribe, message.unsubscribe, message.set) {
+        (Some(list), None, None) => Change::Add(symbols(list, "subscribe")?),
+        (None, Some(list), None) => Change::Remove(symbols(list, "unsubscribe")?),
+        (None, None, Some(list)) => Change::Replace(symbols(list, "set")?),
+        (None, None, None) if detail.is_some() => Change::DetailOnly,
+        (None, None, None) => {
+            return Err("Expected one of subscribe, unsubscribe, set or detail".to_string())
+        }
+        _ => return Err("Send only one of subscribe, unsubscribe or set per message".to_string()),
+    };
+    Ok((change, detail))
+}
+
+struct SignalsFeed {
+    symbols: Vec<String>,
+    detail: Detail,
+    // Price timestamp last pushed per symbol, so unchanged coins are left out
+    sent: HashMap<String, i64>,
+}
+
+impl SignalsFeed {
+    // Latest signal for every symbol whose cached price moved on since the last push
+    fn pending(&mut self) -> Vec<Value> {
+        let mut entries = Vec::new();
+        for symbol in &self.symbols {
+            let Some(price_data) = prices::cached_price(symbol) else {
+                continue;
+            };
+            if self.sent.get(symbol) == Some(&price_data.timestamp) {
+                continue;
+            }
+            self.sent.insert(symbol.clone(), price_data.timestamp);
+            entries.push(signal_entry(symbol, &price_data, self.detail));
+        }
+        entries
+    }
+
+    // A parsed subscription message. Anything newly subscribed, or every coin once the
+    // detail changes, counts as unsent so the next push carries it.
+    fn apply(&mut self, change: Change, detail: Option<Detail>) {
+        match change {
+            Change::Add(symbols) => {
+                for symbol in symbols {
+                    if !self.symbols.contains(&symbol) {
+                        self.sent.remove(&symbol);
+                        self.symbols.push(symbol);
+                    }
+                }
+            }
+            Change::Remove(symbols) => {
+                self.symbols.retain(|s| !symbols.contains(s));
+                self.sent.retain(|s, _| !symbols.contains(s));
+            }
+            Change::Replace(symbols) => {
+                self.symbols = symbols;
+                self.sent.clear();
+            }
+            Change::DetailOnly => {}
+        }
+        if let Some(detail) = detail {
+            if detail != self.detail {
+                self.detail = detail;
+                // Everything is re-sent in the new form
+                self.sent.clear();
+            }
+        }
+    }
+}
+
+fn signal_entry(symbol: &str, price_data: &PriceData, detail: Detail) -> Value {
+    if !history::is_ready(symbol) {
+        return json!({
+            "symbol": format::symbol(symbol),
+            "price": price_data.price,
+            "signal": "insufficient_data",
+            "timestamp": format::timestamp(price_data.timestamp),
+        });
+    }
+
+    let (signal, confidence) = generate_signal(price_data);
+    let (confidence, low_liquidity) = liquidity::adjust_confidence(symbol, confidence);
+    let state = signal_tracker::observe(symbol, &signal, confidence, price_data.price, price_data.timestamp);
+    let mut entry = json!({
+        "symbol": format::symbol(symbol),
+        "price": price_data.price,
+        "change_24h": price_data.change_24h,
+        "signal": signal,
+        "confidence": (confidence * 100.0).round() / 100.0,
+        "action": get_action_from_signal(&signal),
+        "timestamp": format::timestamp(price_data.timestamp),
+    });
+    if low_liquidity {
+        entry["low_liquidity_period"] = json!(true);
+    }
+    if let (Some(previous), Some(changed_at)) = (state.previous_signal, state.changed_at) {
+        entry["previous_signal"] = json!(previous);
+        entry["changed_at"] = format::timestamp(changed_at);
+    }
+    if detail == Detail::Full {
+        let settings = config::get().indicator_settings();
+        let snapshot = indicators::compute(&history::prices(symbol), &settings);
+        entry["indicators"] = json!({
+            "rsi": snapshot.rsi,
+            "ema_short": snapshot.ema_short,
+            "ema_long": snapshot.ema_long,
+            "macd_histogram": snapshot.macd_histogram,
+            "divergence": snapshot.divergence,
+        });
+        if !snapshot.non_finite.is_empty() {
+            entry["indicators"]["non_finite"] = json!(snapshot.non_finite);
+        }
+        entry["reasons"] = json!(signal_reasons(price_data));
+    }
+    entry
+}
+
+// Applies a subscription message and acknowledges it with the resulting subscription,
+// followed by a snapshot of whatever the client hasn't seen in its current form yet.
+// A malformed message changes nothing and gets an `error` message.
+fn handle_message(session: &Session, feed: &mut SignalsFeed, text: &str) -> Result<(), ()> {
+    let (change, detail) = match parse_message(text) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return session.text(json!({"type": "error", "message": message}).to_string());
+        }
+    };
+
+    feed.apply(change, detail);
+
+    let ack = json!({
+        "type": "subscribed",
+        "symbols": format::symbols(&feed.symbols),
+        "detail": feed.detail.name(),
+    });
+    session.text(ack.to_string())?;
+    push(session, "snapshot", feed)
+}
+
+// One message carrying every pending entry; Err once the client is gone
+fn push(session: &Session, kind: &str, feed: &mut SignalsFeed) -> Result<(), ()> {
+    let entries = feed.pending();
+    if entries.is_empty() && kind != "snapshot" {
+        return Ok(());
+    }
+    let message = json!({
+        "type": kind,
+        "signals": entries,
+        "timestamp": format::now(),
+    });
+    session.text(format::to_json_string(&message, config::get().number_format))
+}
+
+async fn run_session(
+    session: Session,
+    mut incoming: mpsc::UnboundedReceiver<String>,
+    mut feed: SignalsFeed,
+    window: Duration,
+    guard: StreamGuard,
+) {
+    let mut updates: watch::Receiver<u64> = prices::subscribe_updates();
+    updates.borrow_and_update();
+    if push(&session, "snapshot", &mut feed).is_err() {
+        return;
+    }
+
+    // Set by the first refresh in a batch; everything until then goes out in one push
+    let mut flush_at: Option<Instant> = None;
+    let mut heartbeat = tokio::time::interval_at(Instant::now() + HEARTBEAT, HEARTBEAT);
+
+    loop {
+        tokio::select! {
+            _ = guard.evicted() => {
+                session.close(Some("connection limit reached; reconnect later"));
+                return;
+            }
+            changed = updates.changed(), if flush_at.is_none() => {
+                if changed.is_err() {
+                    // Sender gone: the server is shutting down
+                    session.close(None);
+                    return;
+                }
+                if window.is_zero() {
+                    updates.borrow_and_update();
+                    if push(&session, "signals", &mut feed).is_err() {
+                        return;
+                    }
+                    guard.touch();
+                } else {
+                    flush_at = Some(Instant::now() + window);
+                }
+            }
+            _ = tokio::time::sleep_until(flush_at.unwrap_or_else(Instant::now)), if flush_at.is_some() => {
+                flush_at = None;
+                updates.borrow_and_update();
+                if push(&session, "signals", &mut feed).is_err() {
+                    return;
+                }
+                guard.touch();
+            }
+            _ = heartbeat.tick() => {
+                if session.ping().is_err() {
+                    return;
+                }
+            }
+            message = incoming.recv() => {
+                let Some(text) = message else {
+                    // Client disconnected
+                    return;
+                };
+                if handle_message(&session, &mut feed, &text).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// ========== SIGNALS WEBSOCKET ==========
+#[get("/signals/ws")]
+pub async fn signals_ws(
+    req: HttpRequest,
+    payload: web::Payload,
+    query: web::Query<SignalsWsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let symbols = requested_symbols(query.symbols.as_deref())
+        .map_err(|message| ApiError::new(StatusCode::BAD_REQUEST, "unsupported_symbol", message))?;
+    let window = Duration::from_millis(query.batch_ms.unwrap_or(config::get().signals_ws_batch_ms));
+
+    let guard = streams::open(StreamKind::WebSocket)
+        .map_err(|message| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "too_many_streams", message))?;
+    let (response, session, incoming) = ws::start(&req, payload)?;
+    let feed = SignalsFeed {
+        symbols,
+        detail: Detail::Summary,
+        sent: HashMap::new(),
+    };
+    actix_web::rt::spawn(run_session(session, incoming, feed, window, guard));
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::init_config;
+
+    fn feed(symbols: &[&str]) -> SignalsFeed {
+        SignalsFeed {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            detail: Detail::Summary,
+            sent: symbols.iter().map(|s| (s.to_string(), 1)).collect(),
+        }
+    }
+
+    fn send(feed: &mut SignalsFeed, text: &str) {
+        let (change, detail) = parse_message(text).unwrap();
+        feed.apply(change, detail);
+    }
+
+    #[test]
+    fn subscribe_adds_symbols_once() {
+        init_config();
+        let mut feed = feed(&["BTC"]);
+        send(&mut feed, r#"{"subscribe": ["eth", "BTC", "sol"]}"#);
+        assert_eq!(feed.symbols, vec!["BTC", "ETH", "SOL"]);
+        // Only the new coins are owed a snapshot
+        assert!(feed.sent.contains_key("BTC"));
+        assert!(!feed.sent.contains_key("ETH"));
+    }
+
+    #[test]
+    fn unsubscribe_removes_symbols() {
+        init_config();
+        let mut feed = feed(&["BTC", "ETH", "SOL"]);
+        send(&mut feed, r#"{"unsubscribe": ["eth", "paxg"]}"#);
+        assert_eq!(feed.symbols, vec!["BTC", "SOL"]);
+        assert!(!feed.sent.contains_key("ETH"));
+        assert!(feed.sent.contains_key("SOL"));
+    }
+
+    #[test]
+    fn set_replaces_the_subscription() {
+        init_config();
+        let mut feed = feed(&["BTC", "ETH"]);
+        send(&mut feed, r#"{"set": ["SOL", "PAXG"], "detail": "summary"}"#);
+        assert_eq!(feed.symbols, vec!["SOL", "PAXG"]);
+        assert!(feed.sent.is_empty());
+
+        send(&mut feed, r#"{"set": []}"#);
+        assert!(feed.symbols.is_empty());
+    }
+
+    #[test]
+    fn a_detail_change_resends_everything() {
+        init_config();
+        let mut feed = feed(&["BTC", "ETH"]);
+        send(&mut feed, r#"{"detail": "full"}"#);
+        assert_eq!(feed.detail, Detail::Full);
+        assert_eq!(feed.symbols, vec!["BTC", "ETH"]);
+        assert!(feed.sent.is_empty());
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        init_config();
+        for (text, error) in [
+            (r#"{"subscribe": ["BTC"], "set": ["ETH"]}"#, "Send only one of subscribe, unsubscribe or set per message"),
+            (r#"{"subscribe": []}"#, "subscribe needs at least one symbol"),
+            (r#"{"unsubscribe": ["NOTACOIN"]}"#, "Unsupported symbol: NOTACOIN"),
+            (r#"{"detail": "verbose"}"#, "Invalid detail: verbose. Use summary or full."),
+            ("{}", "Expected one of subscribe, unsubscribe, set or detail"),
+        ] {
+            assert_eq!(parse_message(text).err().as_deref(), Some(error), "{}", text);
+        }
+        assert!(parse_message(r#"{"subscribe": ["BTC"], "extra": 1}"#).is_err());
+        assert!(parse_message("not json").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use futures_util::stream;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::config;
+use crate::format;
+use crate::prices;
+use crate::streams::{self, StreamGuard, StreamKind};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    pub symbols: Option<String>,
+}
+
+struct TickerState {
+    updates: watch::Receiver<u64>,
+    symbols: Vec<String>,
+    // Last timestamp sent per symbol, so unchanged prices are not re-sent
+    sent: HashMap<String, i64>,
+    first: bool,
+    // Slot under MAX_STREAM_CONNECTIONS, released when the client disconnects
+    guard: StreamGuard,
+    evicted: bool,
+}
+
+impl TickerState {
+    // One small `price` event per symbol whose cached price moved on since the last send.
+    fn pending_events(&mut self) -> String {
+        let mut out = String::new();
+        for symbol in &self.symbols {
+            let Some(price_data) = prices::cached_price(symbol) else {
+                continue;
+            };
+            if self.sent.get(symbol) == Some(&price_data.timestamp) {
+                continue;
+            }
+            self.sent.insert(symbol.clone(), price_data.timestamp);
+            let event = json!({
+                "symbol": format::symbol(&price_data.symbol),
+                "price": price_data.price,
+                "timestamp": format::timestamp(price_data.timestamp),
+            });
+            let data = format::to_json_string(&event, config::get().number_format);
+            out.push_str(&format!("event: price\ndata: {}\n\n", data));
+        }
+        out
+    }
+}
+
+// `?symbols=BTC,ETH` filter shared by the streaming endpoints; every coin when absent
+pub fn requested_symbols(raw: Option<&str>) -> Result<Vec<String>, String> {
+    let config = config::get();
+    let symbols: Vec<String> = match raw {
+        Some(raw) => raw
+            .split(',')
+            .map(|s| s.trim().to_uppercase())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        None => config.supported_symbols(),
+    };
+
+    match symbols.iter().find(|s| !config.is_supported(s)) {
+        Some(unknown) => Err(format!("Unsupported symbol: {}", unknown)),
+        None => Ok(symbols),
+    }
+}
+
+// ========== PRICE TICKER STREAM (SSE) ==========
+#[get("/prices/stream")]
+pub async fn price_stream(query: web::Query<StreamQuery>) -> impl Responder {
+    let symbols = match requested_symbols(query.symbols.as_deref()) {
+        Ok(symbols) => symbols,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
+
+    let guard = match streams::open(StreamKind::Sse) {
+        Ok(guard) => guard,
+        Err(message) => {
+            return HttpResponse::ServiceUnavailable().json(json!({
+                "status": "error",
+                "message": message,
+            }));
+        }
+    };
+
+    let state = TickerState {
+        updates: prices::subscribe_updates(),
+        symbols,
+        sent: HashMap::new(),
+        first: true,
+        guard,
+        evicted: false,
+    };
+
+    let body = stream::unfold(state, |mut state| async move {
+        if state.evicted {
+            return None;
+        }
+        if state.first {
+            state.first = false;
+        } else {
+            tokio::select! {
+                _ = state.guard.evicted() => {
+                    state.evicted = true;
+                    let chunk = web::Bytes::from_static(
+                        b"event: evicted\ndata: {\"message\":\"Connection limit reached; reconnect later\"}\n\n",
+                    );
+                    return Some((Ok::<_, actix_web::Error>(chunk), state));
+                }
+                changed = tokio::time::timeout(KEEP_ALIVE, state.updates.changed()) => match changed {
+                    Ok(Ok(())) => {}
+                    // Sender gone: the server is shutting down
+                    Ok(Err(_)) => return None,
+                    Err(_) => {
+                        let chunk = web::Bytes::from_static(b": keep-alive\n\n");
+                        return Some((Ok(chunk), state));
+                    }
+                },
+            }
+        }
+        state.updates.borrow_and_update();
+        let events = state.pending_events();
+        if !events.is_empty() {
+            state.guard.touch();
+        }
+        Some((Ok(web::Bytes::from(events)), state))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
@@ -0,0 +1,130 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::format;
+
+static TASKS: OnceLock<Mutex<Vec<Arc<Mutex<TaskStatus>>>>> = OnceLock::new();
+static SHUTDOWN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+// Successful runs are only logged when they take longer than this; every run's
+// duration is on /diagnostics regardless
+const SLOW_RUN: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub name: String,
+    pub interval_seconds: u64,
+    pub runs: u64,
+    pub failures: u64,
+    #[serde(serialize_with = "format::serialize_optional_timestamp")]
+    pub last_run: Option<i64>,
+    pub last_duration_ms: Option<u128>,
+    pub last_status: String,
+    pub last_error: Option<String>,
+    // When the next run is due; in the past while a run is in progress
+    #[serde(serialize_with = "format::serialize_optional_timestamp")]
+    pub next_run: Option<i64>,
+}
+
+fn shutdown_sender() -> &'static watch::Sender<bool> {
+    SHUTDOWN.get_or_init(|| watch::channel(false).0)
+}
+
+// Runs `task` immediately and then `interval` after each run finishes, so runs never
+// overlap. Every run's duration and outcome is kept for /diagnostics; failures and
+// slow runs are also logged.
+pub fn register<F, Fut>(name: &str, interval: Duration, task: F)
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send,
+{
+    let status = Arc::new(Mutex::new(TaskStatus {
+        name: name.to_string(),
+        interval_seconds: interval.as_secs(),
+        runs: 0,
+        failures: 0,
+        last_run: None,
+        last_duration_ms: None,
+        last_status: "never".to_string(),
+        last_error: None,
+        next_run: None,
+    }));
+    TASKS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap()
+        .push(status.clone());
+
+    let name = name.to_string();
+    let mut shutdown = shutdown_sender().subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            let started = Instant::now();
+            let result = task().await;
+            let elapsed = started.elapsed();
+
+            {
+                let mut status = status.lock().unwrap();
+                status.runs += 1;
+                status.last_run = Some(Utc::now().timestamp());
+                status.last_duration_ms = Some(elapsed.as_millis());
+                status.next_run = Some(Utc::now().timestamp() + interval.as_secs() as i64);
+                match &result {
+                    Ok(()) => {
+                        status.last_status = "ok".to_string();
+                        status.last_error = None;
+                    }
+                    Err(e) => {
+                        status.failures += 1;
+                        status.last_status = "error".to_string();
+                        status.last_error = Some(e.clone());
+                    }
+                }
+            }
+
+            match result {
+                Ok(()) if elapsed > SLOW_RUN => println!("🐢 {} took {}ms", name, elapsed.as_millis()),
+                Ok(()) => {}
+                Err(e) => println!("⚠️ {} failed after {}ms: {}", name, elapsed.as_millis(), e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.changed() => break,
+            }
+        }
+        println!("🛑 {} stopped", name);
+    });
+}
+
+pub fn statuses() -> Vec<TaskStatus> {
+    TASKS
+        .get()
+        .map(|tasks| {
+            tasks
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|status| status.lock().unwrap().clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// When the named task is next due to run, if it is registered
+pub fn next_run(name: &str) -> Option<i64> {
+    statuses()
+        .into_iter()
+        .find(|status| status.name == name)
+        .and_then(|status| status.next_run)
+}
+
+// Stops every task at its next wait; a run already in progress is allowed to finish.
+pub fn shutdown() {
+    let _ = shutdown_sender().send(true);
+}
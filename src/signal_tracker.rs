@@ -0,0 +1,146 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config;
+use crate::format;
+
+static TRACKER: OnceLock<Mutex<Tracker>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalState {
+    pub signal: String,
+    pub previous_signal: Option<String>,
+    // When the signal last changed; None until it has changed at least once
+    #[serde(serialize_with = "format::serialize_optional_timestamp")]
+    pub changed_at: Option<i64>,
+    // Confidence, price and price timestamp of the latest observation, for SIGNAL_HOLD_SECONDS
+    #[serde(skip)]
+    pub confidence: f64,
+    #[serde(skip)]
+    pub price: f64,
+    #[serde(skip)]
+    pub observed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalChange {
+    // Increases with every change; used as the pagination cursor
+    pub id: u64,
+    #[serde(serialize_with = "format::serialize_symbol")]
+    pub symbol: String,
+    pub from: String,
+    pub to: String,
+    pub price: f64,
+    #[serde(serialize_with = "format::serialize_timestamp")]
+    pub at: i64,
+}
+
+#[derive(Default)]
+struct Tracker {
+    states: HashMap<String, SignalState>,
+    changes: VecDeque<SignalChange>,
+    next_id: u64,
+}
+
+fn tracker() -> &'static Mutex<Tracker> {
+    TRACKER.get_or_init(|| Mutex::new(Tracker::default()))
+}
+
+// Records the symbol's latest signal and returns its state. Called wherever a signal
+// is computed from a fresh price (refresher and /signals), so either path notices a flip.
+pub fn observe(symbol: &str, signal: &str, confidence: f64, price: f64, at: i64) -> SignalState {
+    let symbol = symbol.to_uppercase();
+    let mut tracker = tracker().lock().unwrap();
+
+    let id = tracker.next_id;
+    let change = match tracker.states.get_mut(&symbol) {
+        Some(state) if state.signal != signal => {
+            let from = std::mem::replace(&mut state.signal, signal.to_string());
+            state.previous_signal = Some(from.clone());
+            state.changed_at = Some(at);
+            Some(SignalChange {
+                id,
+                symbol: symbol.clone(),
+                from,
+                to: signal.to_string(),
+                price,
+                at,
+            })
+        }
+        Some(_) => None,
+        None => {
+            tracker.states.insert(
+                symbol.clone(),
+                SignalState {
+                    signal: signal.to_string(),
+                    previous_signal: None,
+                    changed_at: None,
+                    confidence,
+                    price,
+                    observed_at: at,
+                },
+            );
+            None
+        }
+    };
+    if let Some(state) = tracker.states.get_mut(&symbol) {
+        state.confidence = confidence;
+        state.price = price;
+        state.observed_at = at;
+    }
+
+    if let Some(change) = change {
+        tracker.next_id += 1;
+        tracker.changes.push_back(change);
+        let retention = config::get().signal_changes_retention;
+        while tracker.changes.len() > retention {
+            tracker.changes.pop_front();
+        }
+    }
+
+    tracker.states[&symbol].clone()
+}
+
+// Every coin's changes merged newest first, `limit` at a time. `before` is the id of
+// the last change on the previous page. Changes are ordered as they were detected.
+pub fn changes(limit: usize, before: Option<u64>) -> (Vec<SignalChange>, usize) {
+    let tracker = tracker().lock().unwrap();
+    let page = tracker
+        .changes
+        .iter()
+        .rev()
+        .filter(|change| before.is_none_or(|before| change.id < before))
+        .take(limit)
+        .cloned()
+        .collect();
+    (page, tracker.changes.len())
+}
+
+// The symbol's latest signal while its price is at most `max_age` seconds old, so a
+// short gap in the data can be bridged with it
+pub fn recent(symbol: &str, max_age: u64) -> Option<SignalState> {
+    let now = Utc::now().timestamp();
+    tracker()
+        .lock()
+        .unwrap()
+        .states
+        .get(&symbol.to_uppercase())
+        .filter(|state| now - state.observed_at <= max_age as i64)
+        .cloned()
+}
+
+pub fn symbols() -> Vec<String> {
+    tracker().lock().unwrap().states.keys().cloned().collect()
+}
+
+// Drops the symbol's state and its recorded changes; returns how many changes were removed
+pub fn forget(symbol: &str) -> usize {
+    let symbol = symbol.to_uppercase();
+    let mut tracker = tracker().lock().unwrap();
+    tracker.states.remove(&symbol);
+    let before = tracker.changes.len();
+    tracker.changes.retain(|change| change.symbol != symbol);
+    before - tracker.changes.len()
+}
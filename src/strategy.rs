@@ -0,0 +1,438 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::coingecko::PriceData;
+use crate::config;
+use crate::format;
+use crate::history;
+use crate::indicators::{self, IndicatorSettings};
+
+// 24h-change bands (percent, either direction) for strong, normal and weak signals
+const STRONG_CHANGE_PERCENT: f64 = 10.0;
+const CHANGE_PERCENT: f64 = 5.0;
+const WEAK_CHANGE_PERCENT: f64 = 2.0;
+
+// Composite-score bands (either direction) for strong, normal and weak signals. With only
+// the 24h change to go on they fall on the change bands above.
+const STRONG_SCORE: f64 = 50.0;
+const SCORE: f64 = 25.0;
+const WEAK_SCORE: f64 = 10.0;
+
+// SIGNAL_BASIS: what the signal and its confidence are banded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalBasis {
+    // The 24h change bands, with a fixed confidence per band
+    Change24h,
+    // The composite score reported by `strength`
+    Composite,
+}
+
+impl SignalBasis {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "change_24h" => Ok(SignalBasis::Change24h),
+            "composite" => Ok(SignalBasis::Composite),
+            other => Err(format!("Invalid SIGNAL_BASIS: {}. Use change_24h or composite.", other)),
+        }
+    }
+}
+
+// Stablecoins (STABLECOINS) are designed not to move, so they never get momentum
+// signals; they always read "stable".
+pub fn generate_signal(price_data: &PriceData) -> (String, f64) {
+    signal_with(price_data, config::get().signal_basis)
+}
+
+fn signal_with(price_data: &PriceData, basis: SignalBasis) -> (String, f64) {
+    if config::get().is_stablecoin(&price_data.symbol) {
+        return ("stable".to_string(), 0.95);
+    }
+    match basis {
+        SignalBasis::Change24h => signal_for_change(price_data.change_24h),
+        SignalBasis::Composite => signal_for_score(strength(price_data).0),
+    }
+}
+
+fn signal_for_change(change_24h: f64) -> (String, f64) {
+    match change_24h {
+        c if c > STRONG_CHANGE_PERCENT => ("strong_sell".to_string(), 0.85),
+        c if c > CHANGE_PERCENT => ("sell".to_string(), 0.75),
+        c if c > WEAK_CHANGE_PERCENT => ("weak_sell".to_string(), 0.65),
+        c if c < -STRONG_CHANGE_PERCENT => ("strong_buy".to_string(), 0.85),
+        c if c < -CHANGE_PERCENT => ("buy".to_string(), 0.75),
+        c if c < -WEAK_CHANGE_PERCENT => ("weak_buy".to_string(), 0.65),
+        _ => ("hold".to_string(), 0.8),
+    }
+}
+
+// A positive score is bullish. A directional signal's confidence grows with the score,
+// from 0.59 at the weak band to 0.95 at full agreement; a hold is most confident at 0.
+fn signal_for_score(score: f64) -> (String, f64) {
+    let signal = match score {
+        s if s < -STRONG_SCORE => "strong_sell",
+        s if s < -SCORE => "sell",
+        s if s < -WEAK_SCORE => "weak_sell",
+        s if s > STRONG_SCORE => "strong_buy",
+        s if s > SCORE => "buy",
+        s if s > WEAK_SCORE => "weak_buy",
+        _ => "hold",
+    };
+    let confidence = if signal == "hold" {
+        0.8 - score.abs() / WEAK_SCORE * 0.2
+    } else {
+        0.55 + score.abs() * 0.004
+    };
+    (signal.to_string(), (confidence * 100.0).round() / 100.0)
+}
+
+// Every cutoff behind the signals and their reasons, as currently in effect.
+pub fn thresholds() -> Value {
+    let config = config::get();
+    let settings = config.indicator_settings();
+    json!({
+        "change_24h_percent": {
+            "strong": STRONG_CHANGE_PERCENT,
+            "normal": CHANGE_PERCENT,
+            "weak": WEAK_CHANGE_PERCENT,
+        },
+        "signal_basis": config.signal_basis,
+        "score": {
+            "strong": STRONG_SCORE,
+            "normal": SCORE,
+            "weak": WEAK_SCORE,
+        },
+        "rsi": {
+            "period": settings.rsi_period,
+            "overbought": settings.rsi_overbought,
+            "oversold": settings.rsi_oversold,
+        },
+        "ema": {
+            "short_period": settings.ema_short_period,
+            "long_period": settings.ema_long_period,
+        },
+        "macd": {
+            "fast": settings.macd_fast,
+            "slow": settings.macd_slow,
+            "signal": settings.macd_signal,
+        },
+        "divergence": {
+            "lookback": settings.divergence_lookback,
+            "min_rsi_gap": settings.divergence_min_rsi_gap,
+            "required_samples": settings.divergence_required_samples(),
+        },
+        "strength_smoothing": {
+            "samples": config.signal_smoothing_samples,
+            "half_life_samples": config.signal_half_life_samples,
+        },
+        "min_history_samples": config.min_history_samples,
+        "stablecoins": format::symbols(&config.stablecoins),
+    })
+}
+
+// Factors of the signal-strength gauge, in the order they are reported
+pub const STRENGTH_FACTORS: &[&str] = &["change_24h", "rsi", "macd", "ema", "divergence"];
+
+// SIGNAL_STRENGTH_WEIGHTS defaults; like DATA_QUALITY_WEIGHTS only the ratios matter
+pub fn default_strength_weights() -> HashMap<String, f64> {
+    [("change_24h", 40.0), ("rsi", 20.0), ("macd", 15.0), ("ema", 15.0), ("divergence", 10.0)]
+        .iter()
+        .map(|(factor, weight)| (factor.to_string(), *weight))
+        .collect()
+}
+
+// How far each factor leans from -1 (bearish) to +1 (bullish); None while it has no value yet.
+fn strength_leans(price_data: &PriceData) -> Vec<(&'static str, Option<f64>)> {
+    let config = config::get();
+    let settings = config.indicator_settings();
+    let prices = history::prices(&price_data.symbol);
+
+    // A drop is a buy; full lean at twice the strong band, so the score bands line up
+    // with the change bands
+    let change = lean(-price_data.change_24h / (2.0 * STRONG_CHANGE_PERCENT));
+    let [rsi, macd, ema, divergence] = smoothed_leans(
+        &prices,
+        price_data.price,
+        &settings,
+        config.signal_smoothing_samples,
+        config.signal_half_life_samples,
+    );
+
+    vec![
+        ("change_24h", Some(change)),
+        ("rsi", rsi),
+        ("macd", macd),
+        ("ema", ema),
+        ("divergence", divergence),
+    ]
+}
+
+// The indicator leans averaged over the last `window` history positions (SIGNAL_SMOOTHING_SAMPLES).
+// Position `age` samples back sees the history as it was then. Weights halve every
+// `half_life` samples (SIGNAL_HALF_LIFE_SAMPLES); 0 weighs every position equally. A
+// position where an indicator has no value yet doesn't count towards its average.
+fn smoothed_leans(
+    prices: &[f64],
+    price: f64,
+    settings: &IndicatorSettings,
+    window: usize,
+    half_life: f64,
+) -> [Option<f64>; 4] {
+    let window = window.clamp(1, prices.len().max(1));
+    let mut totals = [(0.0, 0.0); 4];
+    for age in 0..window {
+        let end = prices.len().saturating_sub(age);
+        let price = if age == 0 { price } else { prices[end - 1] };
+        let weight = if half_life > 0.0 { 0.5_f64.powf(age as f64 / half_life) } else { 1.0 };
+        for ((total, total_weight), lean) in totals.iter_mut().zip(indicator_leans(&prices[..end], price, settings)) {
+            if let Some(lean) = lean {
+                *total += lean * weight;
+                *total_weight += weight;
+            }
+        }
+    }
+    totals.map(|(total, total_weight)| (total_weight > 0.0).then(|| total / total_weight))
+}
+
+fn lean(value: f64) -> f64 {
+    if value.is_finite() {
+        value.clamp(-1.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+// rsi, macd, ema and divergence leans from the indicators over `prices`, the latest
+// of which is `price`
+fn indicator_leans(prices: &[f64], price: f64, settings: &IndicatorSettings) -> [Option<f64>; 4] {
+    let snapshot = indicators::compute(prices, settings);
+    // Full lean at the oversold / overbought levels
+    let rsi = snapshot.rsi.map(|rsi| {
+        if rsi < 50.0 {
+            lean((50.0 - rsi) / (50.0 - settings.rsi_oversold))
+        } else {
+            lean(-(rsi - 50.0) / (settings.rsi_overbought - 50.0))
+        }
+    });
+    // Full lean at a histogram of 1% of the price
+    let macd = snapshot.macd_histogram.map(|hist| lean(hist / price * 100.0));
+    // Full lean at a 2% gap between the EMAs
+    let ema = match (snapshot.ema_short, snapshot.ema_long) {
+        (Some(short), Some(long)) => Some(lean((short - long) / long * 100.0 / 2.0)),
+        _ => None,
+    };
+    // No divergence is a neutral reading once the window is full
+    let divergence = (settings.divergence_lookback > 0 && prices.len() >= settings.divergence_required_samples())
+        .then(|| match snapshot.divergence {
+            Some(indicators::Divergence::BullishDivergence) => 1.0,
+            Some(indicators::Divergence::BearishDivergence) => -1.0,
+            None => 0.0,
+        });
+    [rsi, macd, ema, divergence]
+}
+
+// Composite score from -100 (everything bearish) to +100 (everything bullish) and each
+// factor's signed share of it: lean * weight / total weight * 100, rounded to 2 decimals.
+// Only factors with a value count towards the total weight; the rest share 0. The score
+// is the sum of the rounded shares, so they always add up to it, and it is what
+// generate_signal bands into the signal with SIGNAL_BASIS=composite. Stablecoins score 0
+// throughout.
+pub fn strength(price_data: &PriceData) -> (f64, Vec<(&'static str, f64)>) {
+    let config = config::get();
+    let weights = &config.signal_strength_weights;
+    let weight = |factor: &str| weights.get(factor).copied().unwrap_or(0.0);
+    let stable = config.is_stablecoin(&price_data.symbol);
+
+    let leans = strength_leans(price_data);
+    let total: f64 = leans.iter().filter(|(_, lean)| lean.is_some()).map(|(factor, _)| weight(factor)).sum();
+    let contributions: Vec<(&'static str, f64)> = leans
+        .into_iter()
+        .map(|(factor, lean)| {
+            let share = match lean {
+                Some(lean) if !stable && total > 0.0 => lean * weight(factor) / total * 100.0,
+                _ => 0.0,
+            };
+            (factor, (share * 100.0).round() / 100.0)
+        })
+        .collect();
+    let score = contributions.iter().map(|(_, share)| share).sum::<f64>();
+    ((score * 100.0).round() / 100.0, contributions)
+}
+
+// Which 24h-change band produced the signal; mirrors the thresholds in signal_for_change.
+fn change_reason(change_24h: f64) -> String {
+    let band = match change_24h.abs() {
+        c if c > STRONG_CHANGE_PERCENT => Some(STRONG_CHANGE_PERCENT),
+        c if c > CHANGE_PERCENT => Some(CHANGE_PERCENT),
+        c if c > WEAK_CHANGE_PERCENT => Some(WEAK_CHANGE_PERCENT),
+        _ => None,
+    };
+    match band {
+        Some(band) if change_24h > 0.0 => {
+            format!(
+                "24h change of {:+.2}% is above +{:.0}% (overextended)",
+                change_24h, band
+            )
+        }
+        Some(band) => format!(
+            "24h change of {:+.2}% is below -{:.0}% (oversold dip)",
+            change_24h, band
+        ),
+        None => format!(
+            "24h change of {:+.2}% is inside the ±{:.0}% hold band",
+            change_24h, WEAK_CHANGE_PERCENT
+        ),
+    }
+}
+
+// Concrete facts behind a signal: the 24h-change band plus any indicator thresholds
+// crossed in the price history.
+pub fn signal_reasons(price_data: &PriceData) -> Vec<String> {
+    if config::get().is_stablecoin(&price_data.symbol) {
+        return vec![format!(
+            "{} is a stablecoin, so momentum signals are skipped (24h change {:+.2}%)",
+            price_data.symbol, price_data.change_24h
+        )];
+    }
+
+    let settings = config::get().indicator_settings();
+    let snapshot = indicators::compute(&history::prices(&price_data.symbol), &settings);
+
+    let mut reasons = vec![change_reason(price_data.change_24h)];
+    reasons.extend(indicators::describe(&snapshot, &settings));
+    reasons
+}
+
+pub fn get_action_from_signal(signal: &str) -> &'static str {
+    match signal {
+        "strong_buy" => "ENTER_LONG_NOW",
+        "buy" => "ENTER_LONG",
+        "strong_sell" => "ENTER_SHORT_NOW",
+        "sell" => "ENTER_SHORT",
+        _ => "HOLD_POSITION",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, init_config, price_data, seed_history, Scenario};
+
+    const BANDS: [(f64, &str, f64); 8] = [
+        (12.0, "strong_sell", 0.85),
+        (7.0, "sell", 0.75),
+        (3.0, "weak_sell", 0.65),
+        (1.0, "hold", 0.8),
+        (0.0, "hold", 0.8),
+        (-3.0, "weak_buy", 0.65),
+        (-7.0, "buy", 0.75),
+        (-12.0, "strong_buy", 0.85),
+    ];
+
+    #[test]
+    fn default_signal_comes_from_the_change_bands_with_fixed_confidences() {
+        init_config();
+        assert_eq!(config::get().signal_basis, SignalBasis::Change24h);
+        for (change, signal, confidence) in BANDS {
+            let expected = (signal.to_string(), confidence);
+            assert_eq!(generate_signal(&price_data("NOHISTORY", 100.0, change)), expected, "{}", change);
+        }
+
+        // Indicators with values don't move it
+        let symbol = "BASELINE";
+        let prices = Scenario::Uptrend { step_percent: 0.5 }.series(100.0, 60);
+        seed_history(symbol, &prices);
+        let price = price_data(symbol, *prices.last().unwrap(), 12.0);
+        assert_eq!(generate_signal(&price), ("strong_sell".to_string(), 0.85));
+        assert!(strength(&price).1.iter().any(|(factor, share)| *factor != "change_24h" && *share != 0.0));
+        history::forget(symbol);
+    }
+
+    #[test]
+    fn composite_signal_without_history_keeps_the_bands_but_scales_confidence() {
+        init_config();
+        for (change, signal, _) in BANDS {
+            let (composite, _) = signal_with(&price_data("NOHISTORY", 100.0, change), SignalBasis::Composite);
+            assert_eq!(composite, signal, "{}", change);
+        }
+        // Scores -60, -35, -15, -5 and 0
+        for (change, confidence) in [(12.0, 0.79), (7.0, 0.69), (3.0, 0.61), (1.0, 0.7), (0.0, 0.8)] {
+            let (_, composite) = signal_with(&price_data("NOHISTORY", 100.0, change), SignalBasis::Composite);
+            assert_eq!(composite, confidence, "{}", change);
+        }
+    }
+
+    #[test]
+    fn contributions_add_up_to_the_emitted_score() {
+        init_config();
+        let symbol = "SUMCHECK";
+        let prices = Scenario::Uptrend { step_percent: 0.5 }.series(100.0, 60);
+        seed_history(symbol, &prices);
+        let price = price_data(symbol, *prices.last().unwrap(), 4.0);
+
+        let (score, contributions) = strength(&price);
+        let factors: Vec<&str> = contributions.iter().map(|(factor, _)| *factor).collect();
+        assert_eq!(factors, STRENGTH_FACTORS);
+        assert!(contributions.iter().any(|(factor, share)| *factor != "change_24h" && *share != 0.0));
+
+        let sum: f64 = contributions.iter().map(|(_, share)| share).sum();
+        assert_eq!((sum * 100.0).round() / 100.0, score);
+        assert_eq!(signal_with(&price, SignalBasis::Composite), signal_for_score(score));
+        history::forget(symbol);
+    }
+
+    #[test]
+    fn partial_warm_up_scores_only_the_ready_indicators() {
+        init_config();
+        let symbol = "HALFWARM";
+        let settings = config::get().indicator_settings();
+        // Enough for RSI and both EMAs, not for the MACD signal line or divergence
+        let prices = Scenario::Downtrend { step_percent: 0.5 }.series(100.0, 30);
+        seed_history(symbol, &prices);
+        let pending = indicators::compute(&prices, &settings).pending(&settings, prices.len());
+        assert_eq!(pending, ["macd_histogram", "divergence"]);
+
+        let price = price_data(symbol, *prices.last().unwrap(), -3.0);
+        let (score, contributions) = strength(&price);
+        let share = |factor: &str| contributions.iter().find(|(f, _)| *f == factor).unwrap().1;
+        assert_eq!(share("macd"), 0.0);
+        assert_eq!(share("divergence"), 0.0);
+        assert!(share("rsi") != 0.0 && share("ema") != 0.0);
+
+        // The 3% drop leans +0.15, and change_24h, rsi and ema fill the scale between
+        // them: 0.15 * 40 / 75 * 100 rather than 0.15 * 40
+        assert_eq!(share("change_24h"), 8.0);
+        assert_eq!(signal_with(&price, SignalBasis::Composite), signal_for_score(score));
+        history::forget(symbol);
+    }
+
+    #[test]
+    fn decayed_weighting_follows_the_latest_readings_more_than_flat() {
+        let settings = test_support::default_settings();
+        // A long slide, then a sharp turn up over the last few samples
+        let mut prices = Scenario::Downtrend { step_percent: 0.5 }.series(100.0, 60);
+        prices.extend(Scenario::Uptrend { step_percent: 2.0 }.series(prices[59] * 1.02, 5));
+        let price = *prices.last().unwrap();
+
+        let latest = smoothed_leans(&prices, price, &settings, 1, 0.0);
+        let flat = smoothed_leans(&prices, price, &settings, 20, 0.0);
+        let decayed = smoothed_leans(&prices, price, &settings, 20, 2.0);
+
+        for (i, factor) in ["rsi", "macd", "ema"].iter().enumerate() {
+            let (latest, flat, decayed) = (latest[i].unwrap(), flat[i].unwrap(), decayed[i].unwrap());
+            assert!(
+                (decayed - latest).abs() < (flat - latest).abs(),
+                "{}: latest {} flat {} decayed {}",
+                factor,
+                latest,
+                flat,
+                decayed
+            );
+        }
+        // The turn is bullish, so decaying the older bearish readings raises the MACD and
+        // EMA leans behind the score
+        assert!(decayed[1].unwrap() > flat[1].unwrap());
+        assert!(decayed[2].unwrap() > flat[2].unwrap());
+    }
+}
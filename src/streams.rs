@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::Notify;
+
+use crate::config;
+
+// Open SSE and WebSocket connections, capped together by MAX_STREAM_CONNECTIONS.
+static CONNECTIONS: OnceLock<Mutex<Vec<Connection>>> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static EVICTIONS: AtomicU64 = AtomicU64::new(0);
+static REJECTIONS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamKind {
+    Sse,
+    WebSocket,
+}
+
+// What a new connection does when the cap is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    // Turn the new connection away
+    Reject,
+    // Close the connection that has gone longest without a message and admit the new one
+    EvictOldestIdle,
+}
+
+impl EvictionPolicy {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "reject" => Ok(EvictionPolicy::Reject),
+            "evict_oldest_idle" => Ok(EvictionPolicy::EvictOldestIdle),
+            other => Err(format!(
+                "Invalid STREAM_EVICTION: {}. Use reject or evict_oldest_idle.",
+                other
+            )),
+        }
+    }
+}
+
+struct Connection {
+    id: u64,
+    kind: StreamKind,
+    last_active: Instant,
+    evict: Arc<Notify>,
+}
+
+fn connections() -> &'static Mutex<Vec<Connection>> {
+    CONNECTIONS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+// Held for as long as a stream is open; dropping it frees the slot.
+pub struct StreamGuard {
+    id: u64,
+    evict: Arc<Notify>,
+}
+
+impl StreamGuard {
+    // Marks the connection as active, so eviction picks idler ones first
+    pub fn touch(&self) {
+        let mut connections = connections().lock().unwrap();
+        if let Some(connection) = connections.iter_mut().find(|c| c.id == self.id) {
+            connection.last_active = Instant::now();
+        }
+    }
+
+    // Resolves once this connection has been evicted to make room for a new one
+    pub async fn evicted(&self) {
+        self.evict.notified().await
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        connections().lock().unwrap().retain(|c| c.id != self.id);
+    }
+}
+
+// Claims a slot for a new stream, evicting the idlest one if the policy allows.
+pub fn open(kind: StreamKind) -> Result<StreamGuard, String> {
+    let config = config::get();
+    let max = config.max_stream_connections;
+    let mut connections = connections().lock().unwrap();
+
+    if max > 0 && connections.len() >= max {
+        let idlest = connections
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, c)| c.last_active)
+            .map(|(index, _)| index);
+        match (config.stream_eviction, idlest) {
+            (EvictionPolicy::EvictOldestIdle, Some(index)) => {
+                let evicted = connections.remove(index);
+                evicted.evict.notify_one();
+                EVICTIONS.fetch_add(1, Ordering::Relaxed);
+                println!(
+                    "🔌 Evicted idle {:?} stream #{} to admit a new connection (MAX_STREAM_CONNECTIONS={})",
+                    evicted.kind, evicted.id, max
+                );
+            }
+            _ => {
+                REJECTIONS.fetch_add(1, Ordering::Relaxed);
+                return Err(format!(
+                    "Too many streaming connections (limit {}); try again later",
+                    max
+                ));
+            }
+        }
+    }
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let evict = Arc::new(Notify::new());
+    connections.push(Connection {
+        id,
+        kind,
+        last_active: Instant::now(),
+        evict: evict.clone(),
+    });
+    Ok(StreamGuard { id, evict })
+}
+
+pub struct StreamCounts {
+    pub sse: usize,
+    pub websocket: usize,
+    pub evictions: u64,
+    pub rejections: u64,
+}
+
+pub fn counts() -> StreamCounts {
+    let connections = connections().lock().unwrap();
+    let count = |kind| connections.iter().filter(|c| c.kind == kind).count();
+    StreamCounts {
+        sse: count(StreamKind::Sse),
+        websocket: count(StreamKind::WebSocket),
+        evictions: EVICTIONS.load(Ordering::Relaxed),
+        rejections: REJECTIONS.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,218 @@
+// Synthetic price series with known shapes, for asserting indicator and signal
+// behaviour against named scenarios instead of hand-written price lists.
+// Compiled only for tests.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::coingecko::{PriceData, PriceSource, SourceType};
+use crate::coins::{self, Coin};
+use crate::config::{self, Config};
+use crate::history::{self, Sample};
+use crate::indicators::{IndicatorSettings, NonFinite};
+use crate::prices;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scenario {
+    // Rises by `step_percent` every sample
+    Uptrend { step_percent: f64 },
+    // Falls by `step_percent` every sample
+    Downtrend { step_percent: f64 },
+    // Falls by `depth_percent` over the first half, then climbs back to the start
+    VReversal { depth_percent: f64 },
+    // Sine wave of `amplitude_percent` around the start price, `period` samples per cycle
+    Range { amplitude_percent: f64, period: usize },
+    // Flat, then one jump of `gap_percent` (negative for a gap down) at sample `at`
+    Gap { gap_percent: f64, at: usize },
+}
+
+impl Scenario {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scenario::Uptrend { .. } => "uptrend",
+            Scenario::Downtrend { .. } => "downtrend",
+            Scenario::VReversal { .. } => "v_reversal",
+            Scenario::Range { .. } => "range",
+            Scenario::Gap { .. } => "gap",
+        }
+    }
+
+    // `len` prices, oldest first, starting at `start`
+    pub fn series(&self, start: f64, len: usize) -> Vec<f64> {
+        match *self {
+            Scenario::Uptrend { step_percent } => trend(start, step_percent, len),
+            Scenario::Downtrend { step_percent } => trend(start, -step_percent, len),
+            Scenario::VReversal { depth_percent } => v_reversal(start, depth_percent, len),
+            Scenario::Range { amplitude_percent, period } => {
+                range(start, amplitude_percent, period, len)
+            }
+            Scenario::Gap { gap_percent, at } => gap(start, gap_percent, at, len),
+        }
+    }
+}
+
+// Compounding by `step_percent` per sample
+pub fn trend(start: f64, step_percent: f64, len: usize) -> Vec<f64> {
+    let factor = 1.0 + step_percent / 100.0;
+    (0..len).map(|i| start * factor.powi(i as i32)).collect()
+}
+
+// Linear down to `depth_percent` below `start` at the midpoint, then linear back up
+pub fn v_reversal(start: f64, depth_percent: f64, len: usize) -> Vec<f64> {
+    let bottom = start * (1.0 - depth_percent / 100.0);
+    let mid = (len.saturating_sub(1) / 2).max(1) as f64;
+    (0..len)
+        .map(|i| {
+            let distance = (i as f64 - mid).abs() / mid;
+            bottom + (start - bottom) * distance.min(1.0)
+        })
+        .collect()
+}
+
+pub fn range(center: f64, amplitude_percent: f64, period: usize, len: usize) -> Vec<f64> {
+    let amplitude = center * amplitude_percent / 100.0;
+    let period = period.max(2) as f64;
+    (0..len)
+        .map(|i| center + amplitude * (2.0 * std::f64::consts::PI * i as f64 / period).sin())
+        .collect()
+}
+
+pub fn gap(start: f64, gap_percent: f64, at: usize, len: usize) -> Vec<f64> {
+    let after = start * (1.0 + gap_percent / 100.0);
+    (0..len).map(|i| if i < at { start } else { after }).collect()
+}
+
+// The defaults Config uses, so fixtures don't need the environment
+pub fn default_settings() -> IndicatorSettings {
+    IndicatorSettings {
+        rsi_period: 14,
+        rsi_overbought: 70.0,
+        rsi_oversold: 30.0,
+        ema_short_period: 12,
+        ema_long_period: 26,
+        macd_fast: 12,
+        macd_slow: 26,
+        macd_signal: 9,
+        non_finite: NonFinite::Null,
+        divergence_lookback: 30,
+        divergence_min_rsi_gap: 2.0,
+    }
+}
+
+// A live CoinGecko price fetched at the current time
+pub fn price_data(symbol: &str, price: f64, change_24h: f64) -> PriceData {
+    PriceData {
+        symbol: symbol.to_string(),
+        price,
+        timestamp: chrono::Utc::now().timestamp(),
+        change_24h,
+        market_cap: None,
+        volume_24h: None,
+        market_cap_rank: None,
+        ath: None,
+        ath_change_percentage: None,
+        change_7d: None,
+        change_30d: None,
+        raw_price: None,
+        adjustment: None,
+        source: PriceSource::Coingecko,
+        source_type: SourceType::Live,
+    }
+}
+
+// Replaces the symbol's history with `prices`, one sample every 30s up to now
+pub fn seed_history(symbol: &str, prices: &[f64]) {
+    let now = chrono::Utc::now().timestamp();
+    let samples = prices
+        .iter()
+        .enumerate()
+        .map(|(i, &price)| Sample {
+            timestamp: now - 30 * (prices.len() - 1 - i) as i64,
+            price,
+        })
+        .collect();
+    history::seed(symbol, samples);
+}
+
+// Adds `count` made-up coins to the tracked set, each with a freshly cached price, so
+// handlers serve them without going upstream. Returns their symbols.
+pub fn track_cached_coins(prefix: &str, count: usize) -> Vec<String> {
+    let symbols: Vec<String> = (0..count).map(|n| format!("{}{}", prefix, n)).collect();
+    let mut tracked = config::get().coins();
+    for symbol in &symbols {
+        tracked.push(Coin {
+            symbol: symbol.clone(),
+            coingecko_id: symbol.to_lowercase(),
+        });
+        prices::insert_cached(price_data(symbol, 100.0, 1.0));
+    }
+    coins::set_tracked(tracked);
+    symbols
+}
+
+// Config::from_env for code that reads config::get(), loaded once per test binary. Tests
+// don't set variables, so this is every default unless the shell exports some.
+pub fn init_config() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| config::init(Config::from_env().expect("default config is valid")));
+}
+
+// A local HTTP server standing in for an upstream API: every request gets `status` and
+// `body` after `delay`, and is counted
+pub struct StubServer {
+    pub base_url: String,
+    pub requests: Arc<AtomicUsize>,
+}
+
+pub async fn stub_server(status: u16, body: String, delay: Duration) -> StubServer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let (counter, body) = (counter.clone(), body.clone());
+            tokio::spawn(async move {
+                read_request(&mut socket).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                let response = format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    StubServer { base_url, requests }
+}
+
+// Headers, then as much body as Content-Length announces
+async fn read_request(socket: &mut tokio::net::TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let Ok(read) = socket.read(&mut buf).await else { return };
+        if read == 0 {
+            return;
+        }
+        request.extend_from_slice(&buf[..read]);
+        let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let headers = String::from_utf8_lossy(&request[..end]).to_lowercase();
+        let length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        if request.len() >= end + 4 + length {
+            return;
+        }
+    }
+}
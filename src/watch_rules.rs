@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use crate::coingecko::PriceData;
+use crate::config::{self, Config};
+use crate::history;
+use crate::indicators::{self, IndicatorSettings, IndicatorSnapshot};
+use crate::routes::signals::{self, TradingViewAlert};
+
+// Indexes of rules whose condition held at the last evaluation. A rule fires
+// only when its condition becomes true, not on every refresh while it stays true.
+static ACTIVE: OnceLock<Mutex<HashSet<usize>>> = OnceLock::new();
+
+// Signal and confidence per symbol at the previous refresh
+static LAST_CONFIDENCE: OnceLock<Mutex<HashMap<String, (String, f64)>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Indicator {
+    Price,
+    Change24h,
+    Rsi,
+    EmaShort,
+    EmaLong,
+    MacdHistogram,
+}
+
+impl Indicator {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "price" => Ok(Indicator::Price),
+            "change_24h" => Ok(Indicator::Change24h),
+            "rsi" => Ok(Indicator::Rsi),
+            "ema_short" => Ok(Indicator::EmaShort),
+            "ema_long" => Ok(Indicator::EmaLong),
+            "macd_histogram" => Ok(Indicator::MacdHistogram),
+            other => Err(format!(
+                "Unknown indicator '{}'. Use price, change_24h, rsi, ema_short, ema_long or macd_histogram.",
+                other
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Indicator::Price => "price",
+            Indicator::Change24h => "change_24h",
+            Indicator::Rsi => "rsi",
+            Indicator::EmaShort => "ema_short",
+            Indicator::EmaLong => "ema_long",
+            Indicator::MacdHistogram => "macd_histogram",
+        }
+    }
+
+    // Price samples needed before the indicator has a value.
+    fn required_samples(self, settings: &IndicatorSettings) -> usize {
+        match self {
+            Indicator::Price | Indicator::Change24h => 0,
+            Indicator::Rsi => settings.rsi_period + 1,
+            Indicator::EmaShort => settings.ema_short_period,
+            Indicator::EmaLong => settings.ema_long_period,
+            Indicator::MacdHistogram => settings.macd_slow + settings.macd_signal - 1,
+        }
+    }
+
+    fn value(self, price: &PriceData, snapshot: &IndicatorSnapshot) -> Option<f64> {
+        match self {
+            Indicator::Price => Some(price.price),
+            Indicator::Change24h => Some(price.change_24h),
+            Indicator::Rsi => snapshot.rsi,
+            Indicator::EmaShort => snapshot.ema_short,
+            Indicator::EmaLong => snapshot.ema_long,
+            Indicator::MacdHistogram => snapshot.macd_histogram,
+        }
+    }
+
+    fn label(self, settings: &IndicatorSettings) -> String {
+        match self {
+            Indicator::Price => "Price".to_string(),
+            Indicator::Change24h => "24h change".to_string(),
+            Indicator::Rsi => format!("RSI{}", settings.rsi_period),
+            Indicator::EmaShort => format!("EMA{}", settings.ema_short_period),
+            Indicator::EmaLong => format!("EMA{}", settings.ema_long_period),
+            Indicator::MacdHistogram => "MACD histogram".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "<=")]
+    AtOrBelow,
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = ">=")]
+    AtOrAbove,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Below => value < threshold,
+            Comparison::AtOrBelow => value <= threshold,
+            Comparison::Above => value > threshold,
+            Comparison::AtOrAbove => value >= threshold,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Comparison::Below => "<",
+            Comparison::AtOrBelow => "<=",
+            Comparison::Above => ">",
+            Comparison::AtOrAbove => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchRule {
+    pub symbol: String,
+    pub indicator: Indicator,
+    pub comparison: Comparison,
+    pub threshold: f64,
+}
+
+impl fmt::Display for WatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}{}{}",
+            self.symbol,
+            self.indicator.name(),
+            self.comparison,
+            self.threshold
+        )
+    }
+}
+
+// "BTC:rsi<25,ETH:price>=4000". Each rule's symbol must be supported and its
+// indicator must be able to warm up within HISTORY_CAPACITY.
+pub fn parse_watch_rules(raw: &str, config: &Config) -> Result<Vec<WatchRule>, String> {
+    let settings = config.indicator_settings();
+    let mut rules = Vec::new();
+
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = |reason: String| format!("Invalid WATCH_RULES entry '{}': {}", entry, reason);
+
+        let (symbol, condition) = entry
+            .split_once(':')
+            .ok_or_else(|| invalid("expected SYMBOL:indicator<value".to_string()))?;
+        let symbol = symbol.trim().to_uppercase();
+        if !config.is_supported(&symbol) {
+            return Err(invalid(format!("{} is not in SUPPORTED_COINS", symbol)));
+        }
+
+        // Two-character operators first so "<=" isn't read as "<"
+        let (indicator, comparison, threshold) = [
+            ("<=", Comparison::AtOrBelow),
+            (">=", Comparison::AtOrAbove),
+            ("<", Comparison::Below),
+            (">", Comparison::Above),
+        ]
+        .iter()
+        .find_map(|(op, comparison)| {
+            condition
+                .split_once(op)
+                .map(|(indicator, threshold)| (indicator, *comparison, threshold))
+        })
+        .ok_or_else(|| invalid("expected one of <, <=, >, >=".to_string()))?;
+
+        let indicator = Indicator::parse(indicator).map_err(invalid)?;
+        let threshold: f64 = threshold
+            .trim()
+            .parse()
+            .map_err(|e| invalid(format!("bad threshold: {}", e)))?;
+
+        let required = indicator.required_samples(&settings);
+        if required > config.history_capacity {
+            return Err(invalid(format!(
+                "{} needs {} samples but HISTORY_CAPACITY is {}",
+                indicator.label(&settings),
+                required,
+                config.history_capacity
+            )));
+        }
+
+        rules.push(WatchRule {
+            symbol,
+            indicator,
+            comparison,
+            threshold,
+        });
+    }
+
+    Ok(rules)
+}
+
+// Evaluates the symbol's rules against the latest price and indicator values,
+// storing an alert (and notifying) for each rule whose condition just became true.
+pub fn evaluate(price: &PriceData) {
+    let config = config::get();
+    let symbol = price.symbol.to_uppercase();
+    let rules: Vec<(usize, &WatchRule)> = config
+        .watch_rules
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| rule.symbol == symbol)
+        .collect();
+    if rules.is_empty() {
+        return;
+    }
+
+    let settings = config.indicator_settings();
+    let snapshot = indicators::compute(&history::prices(&symbol), &settings);
+    let mut active = ACTIVE
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .unwrap();
+
+    for (index, rule) in rules {
+        // No value yet (indicator still warming up) counts as not holding
+        let value = rule.indicator.value(price, &snapshot);
+        let holds = value.is_some_and(|v| rule.comparison.holds(v, rule.threshold));
+
+        if !holds {
+            active.remove(&index);
+            continue;
+        }
+        if !active.insert(index) {
+            continue;
+        }
+
+        let label = rule.indicator.label(&settings);
+        let alert_name = format!(
+            "Watch: {}({}) {} {} (now {:.2})",
+            label,
+            symbol,
+            rule.comparison,
+            rule.threshold,
+            value.unwrap_or_default()
+        );
+        println!("👀 {}", alert_name);
+
+        signals::store_alert(TradingViewAlert {
+            symbol: symbol.clone(),
+            price: price.price,
+            alert_name,
+            action: None,
+            raw_action: None,
+            severity: config.alert_severity.classify(&symbol, None),
+            detail: None,
+            observed: None,
+            timestamp: price.timestamp,
+        });
+    }
+}
+
+// CONFIDENCE_DROP_ALERT: stores an alert when a symbol's confidence falls by more than
+// its threshold from one refresh to the next, whether or not the signal changed with it.
+pub fn check_confidence_drop(price: &PriceData, signal: &str, confidence: f64) {
+    let config = config::get();
+    let symbol = price.symbol.to_uppercase();
+    let previous = LAST_CONFIDENCE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap()
+        .insert(symbol.clone(), (signal.to_string(), confidence));
+
+    let threshold = config.confidence_drop_threshold(&symbol);
+    let Some((previous_signal, previous_confidence)) = previous else {
+        return;
+    };
+    let drop = previous_confidence - confidence;
+    if threshold <= 0.0 || drop <= threshold {
+        return;
+    }
+
+    let alert_name = format!(
+        "Confidence drop: {} {:.2} -> {:.2} ({} -> {})",
+        symbol, previous_confidence, confidence, previous_signal, signal
+    );
+    println!("📉 {}", alert_name);
+    signals::store_alert(TradingViewAlert {
+        symbol: symbol.clone(),
+        price: price.price,
+        alert_name,
+        action: None,
+        raw_action: None,
+        severity: config.alert_severity.classify(&symbol, None),
+        detail: Some(json!({
+            "condition": "confidence_drop",
+            "confidence_before": previous_confidence,
+            "confidence_after": confidence,
+            "signal_before": previous_signal,
+            "signal_after": signal,
+            "threshold": threshold,
+        })),
+        observed: None,
+        timestamp: price.timestamp,
+    });
+}
+
+// Drops the confidence remembered for the symbol
+pub fn forget(symbol: &str) {
+    if let Some(last) = LAST_CONFIDENCE.get() {
+        last.lock().unwrap().remove(&symbol.to_uppercase());
+    }
+}
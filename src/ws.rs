@@ -0,0 +1,117 @@
+use actix_http::ws::{self, CloseCode, CloseReason, Codec, Frame, Message};
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::{stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::ApiError;
+
+// Minimal WebSocket plumbing on top of actix-http's codec. The route gets a
+// `Session` to push text messages and a receiver for the text messages the client
+// sends; pings, pongs and close frames are handled here.
+
+#[derive(Clone)]
+pub struct Session {
+    outgoing: mpsc::UnboundedSender<Message>,
+}
+
+impl Session {
+    // Err once the client has gone away
+    pub fn text(&self, text: String) -> Result<(), ()> {
+        self.outgoing.send(Message::Text(text.into())).map_err(|_| ())
+    }
+
+    pub fn ping(&self) -> Result<(), ()> {
+        self.outgoing.send(Message::Ping(web::Bytes::new())).map_err(|_| ())
+    }
+
+    pub fn close(&self, reason: Option<&str>) {
+        let reason = reason.map(|description| CloseReason {
+            code: CloseCode::Policy,
+            description: Some(description.to_string()),
+        });
+        let _ = self.outgoing.send(Message::Close(reason));
+    }
+}
+
+// Completes the handshake. The returned response must be sent back as-is; the
+// receiver yields each text message from the client and ends when it disconnects.
+pub fn start(
+    req: &HttpRequest,
+    payload: web::Payload,
+) -> Result<(HttpResponse, Session, mpsc::UnboundedReceiver<String>), ApiError> {
+    if let Err(e) = ws::verify_handshake(req.head()) {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "websocket_handshake",
+            format!("Expected a WebSocket upgrade: {}", e),
+        ));
+    }
+    let key = req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap();
+    let accept = ws::hash_key(key.as_bytes());
+
+    let (out_tx, out_rx) = mpsc::unbounded_channel::<Message>();
+    let (in_tx, in_rx) = mpsc::unbounded_channel::<String>();
+    let session = Session { outgoing: out_tx };
+
+    actix_web::rt::spawn(read_frames(payload, session.clone(), in_tx));
+
+    // Encoded frames, ending right after a close frame
+    let body = stream::unfold((out_rx, Codec::new(), false), |(mut rx, mut codec, closed)| async move {
+        if closed {
+            return None;
+        }
+        let message = rx.recv().await?;
+        let closing = matches!(message, Message::Close(_));
+        let mut buf = web::BytesMut::new();
+        if let Err(e) = codec.encode(message, &mut buf) {
+            println!("⚠️ WebSocket encode failed: {}", e);
+            return None;
+        }
+        Some((Ok::<_, actix_web::Error>(buf.freeze()), (rx, codec, closing)))
+    });
+
+    let response = HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, &accept[..]))
+        .streaming(body);
+
+    Ok((response, session, in_rx))
+}
+
+async fn read_frames(mut payload: web::Payload, session: Session, incoming: mpsc::UnboundedSender<String>) {
+    let mut codec = Codec::new();
+    let mut buf = web::BytesMut::new();
+
+    while let Some(chunk) = payload.next().await {
+        let Ok(chunk) = chunk else {
+            break;
+        };
+        buf.extend_from_slice(&chunk);
+        loop {
+            match codec.decode(&mut buf) {
+                Ok(Some(Frame::Text(text))) => {
+                    if incoming.send(String::from_utf8_lossy(&text).into_owned()).is_err() {
+                        return;
+                    }
+                }
+                Ok(Some(Frame::Ping(data))) => {
+                    let _ = session.outgoing.send(Message::Pong(data));
+                }
+                Ok(Some(Frame::Close(reason))) => {
+                    let _ = session.outgoing.send(Message::Close(reason));
+                    return;
+                }
+                // Binary, pong and continuation frames are not used by our clients
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => {
+                    println!("⚠️ WebSocket protocol error: {}", e);
+                    session.close(Some("protocol error"));
+                    return;
+                }
+            }
+        }
+    }
+}